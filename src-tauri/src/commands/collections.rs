@@ -18,7 +18,7 @@ pub struct AppState {
 ///
 /// Converts to lowercase, replaces spaces with dashes, and filters to alphanumeric + dashes.
 /// Returns an error if the result is empty or contains only dashes.
-fn sanitize_filename(name: &str) -> Result<String, String> {
+pub(crate) fn sanitize_filename(name: &str) -> Result<String, String> {
     let filename: String = name
         .to_lowercase()
         .replace(' ', "-")
@@ -38,7 +38,10 @@ fn sanitize_filename(name: &str) -> Result<String, String> {
 ///
 /// Prevents directory traversal attacks by ensuring the canonical path
 /// is within the collection manager's base directory.
-fn validate_path_in_collections(path: &Path, base_path: &Path) -> Result<PathBuf, String> {
+pub(crate) fn validate_path_in_collections(
+    path: &Path,
+    base_path: &Path,
+) -> Result<PathBuf, String> {
     // Canonicalize both paths to resolve .. and symlinks
     let canonical_path = path
         .canonicalize()
@@ -0,0 +1,270 @@
+//! Cookie jar commands
+//!
+//! This module provides Tauri commands for viewing and editing the cookie
+//! jar of a collection.
+
+use crate::commands::collections::{validate_path_in_collections, AppState};
+use crate::models::{Cookie, CookieList};
+use crate::storage::{CollectionManager, CookieJar, CookieJars};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Get the cookie jar of a collection inside the collections directory
+fn jar_for(
+    collection_path: &str,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<Arc<CookieJar>, String> {
+    let path =
+        validate_path_in_collections(Path::new(collection_path), &collection_manager.base_path)?;
+    cookie_jars
+        .for_collection(&path)
+        .map_err(|e| format!("Failed to load cookies: {}", e))
+}
+
+/// List the cookies of a collection
+///
+/// # Arguments
+///
+/// * `collection_path` - Path of the collection file
+/// * `app_state` - Application state containing the collection manager
+/// * `cookie_jars` - Shared cookie jars
+///
+/// # Returns
+///
+/// Whether the jar is enabled and its unexpired cookies
+#[tauri::command]
+pub fn list_cookies(
+    collection_path: String,
+    app_state: tauri::State<'_, AppState>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+) -> Result<CookieList, String> {
+    list_cookies_impl(
+        &collection_path,
+        &app_state.collection_manager,
+        &cookie_jars,
+    )
+}
+
+/// Implementation of list_cookies (for testing)
+pub fn list_cookies_impl(
+    collection_path: &str,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<CookieList, String> {
+    Ok(jar_for(collection_path, collection_manager, cookie_jars)?.list())
+}
+
+/// Add or replace a cookie in a collection's jar
+///
+/// A cookie with the same domain, path and name is replaced.
+///
+/// # Arguments
+///
+/// * `collection_path` - Path of the collection file
+/// * `cookie` - The cookie to store
+/// * `app_state` - Application state containing the collection manager
+/// * `cookie_jars` - Shared cookie jars
+#[tauri::command]
+pub fn set_cookie(
+    collection_path: String,
+    cookie: Cookie,
+    app_state: tauri::State<'_, AppState>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+) -> Result<(), String> {
+    set_cookie_impl(
+        &collection_path,
+        cookie,
+        &app_state.collection_manager,
+        &cookie_jars,
+    )
+}
+
+/// Implementation of set_cookie (for testing)
+pub fn set_cookie_impl(
+    collection_path: &str,
+    cookie: Cookie,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<(), String> {
+    jar_for(collection_path, collection_manager, cookie_jars)?
+        .set_cookie(&cookie)
+        .map_err(|e| format!("Failed to set cookie: {}", e))
+}
+
+/// Delete a cookie from a collection's jar
+///
+/// # Arguments
+///
+/// * `collection_path` - Path of the collection file
+/// * `domain` - Domain of the cookie
+/// * `path` - Path of the cookie
+/// * `name` - Name of the cookie
+/// * `app_state` - Application state containing the collection manager
+/// * `cookie_jars` - Shared cookie jars
+///
+/// # Returns
+///
+/// `true` if the cookie existed
+#[tauri::command]
+pub fn delete_cookie(
+    collection_path: String,
+    domain: String,
+    path: String,
+    name: String,
+    app_state: tauri::State<'_, AppState>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+) -> Result<bool, String> {
+    delete_cookie_impl(
+        &collection_path,
+        &domain,
+        &path,
+        &name,
+        &app_state.collection_manager,
+        &cookie_jars,
+    )
+}
+
+/// Implementation of delete_cookie (for testing)
+pub fn delete_cookie_impl(
+    collection_path: &str,
+    domain: &str,
+    path: &str,
+    name: &str,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<bool, String> {
+    jar_for(collection_path, collection_manager, cookie_jars)?
+        .remove_cookie(domain, path, name)
+        .map_err(|e| format!("Failed to delete cookie: {}", e))
+}
+
+/// Delete every cookie of a collection
+///
+/// # Arguments
+///
+/// * `collection_path` - Path of the collection file
+/// * `app_state` - Application state containing the collection manager
+/// * `cookie_jars` - Shared cookie jars
+#[tauri::command]
+pub fn clear_cookies(
+    collection_path: String,
+    app_state: tauri::State<'_, AppState>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+) -> Result<(), String> {
+    clear_cookies_impl(
+        &collection_path,
+        &app_state.collection_manager,
+        &cookie_jars,
+    )
+}
+
+/// Implementation of clear_cookies (for testing)
+pub fn clear_cookies_impl(
+    collection_path: &str,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<(), String> {
+    jar_for(collection_path, collection_manager, cookie_jars)?
+        .clear()
+        .map_err(|e| format!("Failed to clear cookies: {}", e))
+}
+
+/// Enable or disable a collection's cookie jar
+///
+/// While disabled, requests neither send nor store cookies; stored cookies
+/// are kept.
+///
+/// # Arguments
+///
+/// * `collection_path` - Path of the collection file
+/// * `enabled` - Whether the jar is used
+/// * `app_state` - Application state containing the collection manager
+/// * `cookie_jars` - Shared cookie jars
+#[tauri::command]
+pub fn set_cookies_enabled(
+    collection_path: String,
+    enabled: bool,
+    app_state: tauri::State<'_, AppState>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+) -> Result<(), String> {
+    set_cookies_enabled_impl(
+        &collection_path,
+        enabled,
+        &app_state.collection_manager,
+        &cookie_jars,
+    )
+}
+
+/// Implementation of set_cookies_enabled (for testing)
+pub fn set_cookies_enabled_impl(
+    collection_path: &str,
+    enabled: bool,
+    collection_manager: &CollectionManager,
+    cookie_jars: &CookieJars,
+) -> Result<(), String> {
+    jar_for(collection_path, collection_manager, cookie_jars)?
+        .set_enabled(enabled)
+        .map_err(|e| format!("Failed to update cookie jar: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Collection;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cookie_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path()).unwrap();
+        let collection_path = manager
+            .save_collection(&Collection::new("API"), "api")
+            .unwrap();
+        let path = collection_path.to_string_lossy().into_owned();
+        let jars = CookieJars::new();
+
+        set_cookie_impl(
+            &path,
+            Cookie::new("token", "abc", "example.com"),
+            &manager,
+            &jars,
+        )
+        .unwrap();
+        set_cookie_impl(
+            &path,
+            Cookie::new("scoped", "1", "example.com").with_path("/v1"),
+            &manager,
+            &jars,
+        )
+        .unwrap();
+        assert!(
+            set_cookie_impl(&path, Cookie::new("", "x", "example.com"), &manager, &jars).is_err()
+        );
+
+        let list = list_cookies_impl(&path, &manager, &jars).unwrap();
+        assert!(list.enabled);
+        assert_eq!(list.cookies.len(), 2);
+        assert!(temp_dir.path().join("api.cookies.yaml").exists());
+
+        assert!(
+            delete_cookie_impl(&path, "example.com", "/v1", "scoped", &manager, &jars).unwrap()
+        );
+        set_cookies_enabled_impl(&path, false, &manager, &jars).unwrap();
+        let list = list_cookies_impl(&path, &manager, &jars).unwrap();
+        assert!(!list.enabled);
+        assert_eq!(list.cookies.len(), 1);
+
+        clear_cookies_impl(&path, &manager, &jars).unwrap();
+        assert!(list_cookies_impl(&path, &manager, &jars)
+            .unwrap()
+            .cookies
+            .is_empty());
+
+        // Collections outside the collections directory are rejected
+        let outside = TempDir::new().unwrap();
+        let outside_path = outside.path().join("x.collection.yaml");
+        std::fs::write(&outside_path, "{}").unwrap();
+        assert!(list_cookies_impl(&outside_path.to_string_lossy(), &manager, &jars).is_err());
+    }
+}
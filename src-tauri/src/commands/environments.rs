@@ -0,0 +1,301 @@
+//! Environment commands
+//!
+//! This module provides Tauri commands for managing the `*.env.yaml`
+//! environment files in the collections directory and choosing the active
+//! one.
+
+use crate::commands::collections::{sanitize_filename, validate_path_in_collections, AppState};
+use crate::models::{Environment, EnvironmentFile};
+use crate::storage::collection_manager::constants;
+use crate::storage::{CollectionManager, SettingsStore};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// List the environment files in the collections directory
+///
+/// Files that cannot be read are skipped.
+///
+/// # Arguments
+///
+/// * `app_state` - Application state containing the collection manager
+/// * `settings_store` - Shared settings store, which remembers the active
+///   environment
+///
+/// # Returns
+///
+/// The environments ordered by path, with the active one flagged
+#[tauri::command]
+pub fn list_environments(
+    app_state: tauri::State<'_, AppState>,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+) -> Result<Vec<EnvironmentFile>, String> {
+    list_environments_impl(&app_state.collection_manager, &settings_store)
+}
+
+/// Implementation of list_environments (for testing)
+pub fn list_environments_impl(
+    collection_manager: &CollectionManager,
+    settings_store: &SettingsStore,
+) -> Result<Vec<EnvironmentFile>, String> {
+    let active = settings_store.get().active_environment;
+    let mut paths = collection_manager
+        .scan_environments()
+        .map_err(|e| format!("Failed to scan environments: {}", e))?;
+    paths.sort();
+
+    let mut environments = Vec::new();
+    for path in paths {
+        match collection_manager.load_environment(&path) {
+            Ok(environment) => {
+                let path = path.canonicalize().unwrap_or(path);
+                let path = path.to_string_lossy().to_string();
+                environments.push(EnvironmentFile {
+                    active: active.as_deref() == Some(path.as_str()),
+                    path,
+                    environment,
+                });
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to load environment at {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(environments)
+}
+
+/// Create a new, empty environment file
+///
+/// # Arguments
+///
+/// * `name` - Display name of the environment; the file name is derived
+///   from it
+/// * `app_state` - Application state containing the collection manager
+///
+/// # Returns
+///
+/// The new environment and the path of its file
+#[tauri::command]
+pub fn create_environment(
+    name: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(Environment, String), String> {
+    create_environment_impl(&name, &app_state.collection_manager)
+}
+
+/// Implementation of create_environment (for testing)
+pub fn create_environment_impl(
+    name: &str,
+    collection_manager: &CollectionManager,
+) -> Result<(Environment, String), String> {
+    let filename = sanitize_filename(name).map_err(|_| {
+        "Environment name must contain at least one alphanumeric character".to_string()
+    })?;
+    let environment = Environment::new(name);
+
+    let path = collection_manager
+        .create_environment(&environment, &filename)
+        .map_err(|e| format!("Failed to create environment: {}", e))?;
+
+    Ok((environment, path.to_string_lossy().to_string()))
+}
+
+/// Save the name and variables of an existing environment
+///
+/// # Arguments
+///
+/// * `path` - Path of the environment file
+/// * `environment` - The edited environment
+/// * `app_state` - Application state containing the collection manager
+#[tauri::command]
+pub fn update_environment(
+    path: String,
+    environment: Environment,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    update_environment_impl(&path, &environment, &app_state.collection_manager)
+}
+
+/// Implementation of update_environment (for testing)
+pub fn update_environment_impl(
+    path: &str,
+    environment: &Environment,
+    collection_manager: &CollectionManager,
+) -> Result<(), String> {
+    let path = validate_environment_path(path, collection_manager)?;
+
+    collection_manager
+        .save_environment(&path, environment)
+        .map_err(|e| format!("Failed to save environment: {}", e))?;
+
+    Ok(())
+}
+
+/// Delete an environment file
+///
+/// Deleting the active environment deactivates it.
+///
+/// # Arguments
+///
+/// * `path` - Path of the environment file
+/// * `app_state` - Application state containing the collection manager
+/// * `settings_store` - Shared settings store
+#[tauri::command]
+pub fn delete_environment(
+    path: String,
+    app_state: tauri::State<'_, AppState>,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+) -> Result<(), String> {
+    delete_environment_impl(&path, &app_state.collection_manager, &settings_store)
+}
+
+/// Implementation of delete_environment (for testing)
+pub fn delete_environment_impl(
+    path: &str,
+    collection_manager: &CollectionManager,
+    settings_store: &SettingsStore,
+) -> Result<(), String> {
+    let path = validate_environment_path(path, collection_manager)?;
+
+    collection_manager
+        .delete_environment(&path)
+        .map_err(|e| format!("Failed to delete environment: {}", e))?;
+
+    let mut settings = settings_store.get();
+    if settings.active_environment.as_deref() == Some(&*path.to_string_lossy()) {
+        settings.active_environment = None;
+        settings_store
+            .update(settings)
+            .map_err(|e| format!("Failed to deactivate environment: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Choose the environment whose variables apply to every request
+///
+/// The choice is saved in the application settings, so it survives a
+/// restart.
+///
+/// # Arguments
+///
+/// * `path` - Path of the environment file, or `None` to use no environment
+/// * `app_state` - Application state containing the collection manager
+/// * `settings_store` - Shared settings store
+#[tauri::command]
+pub fn activate_environment(
+    path: Option<String>,
+    app_state: tauri::State<'_, AppState>,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+) -> Result<(), String> {
+    activate_environment_impl(
+        path.as_deref(),
+        &app_state.collection_manager,
+        &settings_store,
+    )
+}
+
+/// Implementation of activate_environment (for testing)
+pub fn activate_environment_impl(
+    path: Option<&str>,
+    collection_manager: &CollectionManager,
+    settings_store: &SettingsStore,
+) -> Result<(), String> {
+    let active = match path {
+        Some(path) => {
+            let path = validate_environment_path(path, collection_manager)?;
+            // Refuse files that would fail every request
+            collection_manager
+                .load_environment(&path)
+                .map_err(|e| format!("Failed to load environment: {}", e))?;
+            Some(path.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+
+    let mut settings = settings_store.get();
+    settings.active_environment = active;
+    settings_store
+        .update(settings)
+        .map_err(|e| format!("Failed to activate environment: {}", e))
+}
+
+/// Check that a path is an environment file inside the collections directory
+fn validate_environment_path(
+    path: &str,
+    collection_manager: &CollectionManager,
+) -> Result<PathBuf, String> {
+    let path = validate_path_in_collections(Path::new(path), &collection_manager.base_path)?;
+
+    if !path.to_string_lossy().ends_with(constants::ENVIRONMENT_EXT) {
+        return Err(format!("Not an environment file: {}", path.display()));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, CollectionManager, SettingsStore) {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        (temp_dir, manager, settings_store)
+    }
+
+    #[test]
+    fn test_environment_lifecycle() {
+        let (temp_dir, manager, settings_store) = setup();
+
+        let (local, local_path) = create_environment_impl("Local", &manager).unwrap();
+        assert_eq!(local.name, "Local");
+        let (_, staging_path) = create_environment_impl("Staging", &manager).unwrap();
+        assert!(create_environment_impl("Local", &manager).is_err());
+        assert!(create_environment_impl("!!!", &manager).is_err());
+
+        let staging = Environment::new("Staging").with_variable("host", "staging.example.com");
+        update_environment_impl(&staging_path, &staging, &manager).unwrap();
+        assert!(update_environment_impl(&staging_path, &Environment::new(""), &manager).is_err());
+
+        activate_environment_impl(Some(&staging_path), &manager, &settings_store).unwrap();
+        let environments = list_environments_impl(&manager, &settings_store).unwrap();
+        assert_eq!(environments.len(), 2);
+        assert_eq!(environments[0].environment, local);
+        assert!(!environments[0].active);
+        assert_eq!(environments[1].environment, staging);
+        assert!(environments[1].active);
+
+        // The active environment is remembered across restarts
+        let reloaded = SettingsStore::load(settings_store.path()).unwrap();
+        assert!(list_environments_impl(&manager, &reloaded).unwrap()[1].active);
+
+        // Deleting the active environment deactivates it
+        delete_environment_impl(&staging_path, &manager, &settings_store).unwrap();
+        assert!(settings_store.get().active_environment.is_none());
+
+        activate_environment_impl(Some(&local_path), &manager, &settings_store).unwrap();
+        activate_environment_impl(None, &manager, &settings_store).unwrap();
+        assert!(settings_store.get().active_environment.is_none());
+
+        // Only environment files inside the collections directory are accepted
+        let outside = temp_dir.path().join("outside.env.yaml");
+        std::fs::write(&outside, "name: Outside\n").unwrap();
+        assert!(activate_environment_impl(
+            Some(&outside.to_string_lossy()),
+            &manager,
+            &settings_store
+        )
+        .is_err());
+        let collection = manager.base_path.join("api.collection.yaml");
+        std::fs::write(&collection, "name: API\n").unwrap();
+        assert!(activate_environment_impl(
+            Some(&collection.to_string_lossy()),
+            &manager,
+            &settings_store
+        )
+        .is_err());
+    }
+}
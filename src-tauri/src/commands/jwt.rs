@@ -0,0 +1,24 @@
+//! JWT commands
+//!
+//! This module provides a Tauri command for inspecting the JSON Web Tokens in
+//! a response. Tokens for requests are signed when the request is executed.
+
+use crate::models::Response;
+use crate::services::jwt::{self, FoundJwt};
+use chrono::Utc;
+
+/// Decode the JWTs found in a response's headers and body
+///
+/// Signatures are not verified; the tokens are decoded for inspection only.
+///
+/// # Arguments
+///
+/// * `response` - Response to search
+///
+/// # Returns
+///
+/// Each token with its location, header, claims and expiry status
+#[tauri::command]
+pub fn inspect_response_jwts(response: Response) -> Vec<FoundJwt> {
+    jwt::find_in_response(&response, Utc::now())
+}
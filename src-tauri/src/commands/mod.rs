@@ -4,7 +4,21 @@
 //! to the frontend via the Tauri IPC bridge.
 
 pub mod collections;
+pub mod cookies;
+pub mod environments;
+pub mod jwt;
+pub mod oauth2;
 pub mod requests;
+pub mod secrets;
+pub mod settings;
+pub mod variables;
 
 pub use collections::*;
+pub use cookies::*;
+pub use environments::*;
+pub use jwt::*;
+pub use oauth2::*;
 pub use requests::*;
+pub use secrets::*;
+pub use settings::*;
+pub use variables::*;
@@ -0,0 +1,231 @@
+//! OAuth 2.0 commands
+//!
+//! This module provides Tauri commands for the browser part of the OAuth 2.0
+//! authorization code flow and for forgetting cached tokens. Other grants
+//! obtain their tokens when a request is executed.
+
+use crate::commands::collections::{validate_path_in_collections, AppState};
+use crate::commands::requests::{prepare_request, resolve_execution_context};
+use crate::models::{Auth, OAuth2Grant, Request};
+use crate::services::http::{
+    ClientConfig, ExecutionContext, HTTPService, HTTPServiceError, HTTPServiceResult,
+};
+use crate::services::oauth2::{AuthorizationStart, OAuth2TokenInfo};
+use crate::storage::{CookieJars, SecretVault, SettingsStore};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long the user has to complete the authorization in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Start the authorization code flow for a request
+///
+/// Opens a loopback listener for the redirect. The frontend opens the
+/// returned URL in the browser, then calls `finish_oauth2_authorization`.
+///
+/// # Arguments
+///
+/// * `request` - Request whose auth (own or inherited) uses the
+///   authorization code grant
+/// * `collection_path` - Optional path of the collection the request belongs to
+/// * `http_service` - Shared HTTP service, which caches the tokens
+/// * `app_state` - Application state containing the collection manager
+/// * `settings_store` - Shared application settings
+/// * `cookie_jars` - Shared cookie jars
+/// * `secret_vault` - Shared secret vault
+#[tauri::command]
+pub async fn start_oauth2_authorization(
+    request: Request,
+    collection_path: Option<String>,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+    app_state: tauri::State<'_, AppState>,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> HTTPServiceResult<AuthorizationStart> {
+    let context = resolve_execution_context(
+        collection_path.as_deref(),
+        &app_state.collection_manager,
+        &settings_store,
+        &cookie_jars,
+        &secret_vault,
+        http_service.runtime_variables(),
+    )?;
+    start_oauth2_authorization_impl(request, &context, &http_service).await
+}
+
+/// Implementation of start_oauth2_authorization (for testing)
+pub async fn start_oauth2_authorization_impl(
+    request: Request,
+    context: &ExecutionContext,
+    http_service: &HTTPService,
+) -> HTTPServiceResult<AuthorizationStart> {
+    let request = prepare_request(request, context)?.request;
+    let config = match &request.auth {
+        Auth::OAuth2(config) if config.grant == OAuth2Grant::AuthorizationCode => config,
+        _ => {
+            return Err(HTTPServiceError::InvalidRequest(
+                "Request does not use the OAuth 2.0 authorization code grant".to_string(),
+            ))
+        }
+    };
+
+    let client = http_service.client_for(&ClientConfig::for_request(&request, context))?;
+    Ok(http_service
+        .oauth2_tokens()
+        .begin_authorization(client, config, context.collection_path.as_deref())
+        .await?)
+}
+
+/// Wait for the browser to return and exchange the code for a token
+///
+/// # Arguments
+///
+/// * `session_id` - ID returned by `start_oauth2_authorization`
+/// * `http_service` - Shared HTTP service, which caches the tokens
+///
+/// # Returns
+///
+/// A description of the token; the token itself is not returned
+#[tauri::command]
+pub async fn finish_oauth2_authorization(
+    session_id: String,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+) -> HTTPServiceResult<OAuth2TokenInfo> {
+    finish_oauth2_authorization_impl(&session_id, &http_service).await
+}
+
+/// Implementation of finish_oauth2_authorization (for testing)
+pub async fn finish_oauth2_authorization_impl(
+    session_id: &str,
+    http_service: &HTTPService,
+) -> HTTPServiceResult<OAuth2TokenInfo> {
+    Ok(http_service
+        .oauth2_tokens()
+        .finish_authorization(session_id, AUTHORIZATION_TIMEOUT)
+        .await?)
+}
+
+/// Forget cached OAuth 2.0 tokens
+///
+/// # Arguments
+///
+/// * `collection_path` - Collection whose tokens are forgotten, or `None`
+///   for every token
+/// * `http_service` - Shared HTTP service, which caches the tokens
+/// * `app_state` - Application state containing the collection manager
+#[tauri::command]
+pub fn clear_oauth2_tokens(
+    collection_path: Option<String>,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let path = collection_path
+        .map(|path| {
+            validate_path_in_collections(Path::new(&path), &app_state.collection_manager.base_path)
+        })
+        .transpose()?;
+    http_service.oauth2_tokens().clear(path.as_deref());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::requests::execute_request_impl;
+    use crate::models::{Collection, OAuth2Config};
+    use crate::services::extraction::RuntimeVariables;
+    use crate::storage::CollectionManager;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_authorization_code_flow_for_collection() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=authorization_code"))
+            .and(body_string_contains("code=c0de"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access-1",
+                "token_type": "Bearer",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/me"))
+            .and(header("authorization", "Bearer access-1"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        let config = OAuth2Config::new(
+            OAuth2Grant::AuthorizationCode,
+            "{{auth_server}}/token",
+            "app",
+        )
+        .with_authorization_url("{{auth_server}}/authorize");
+        let collection = Collection::new("Me")
+            .with_variable("auth_server", mock_server.uri())
+            .with_auth(Auth::OAuth2(config));
+        let collection_path = manager.save_collection(&collection, "me").unwrap();
+        let context = resolve_execution_context(
+            Some(&collection_path.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &CookieJars::new(),
+            &vault,
+            &RuntimeVariables::new(),
+        )
+        .unwrap();
+
+        let service = Arc::new(HTTPService::new().unwrap());
+        let request = Request::new("Me", format!("{}/me", mock_server.uri()));
+
+        // Nothing is sent before the user has authorized
+        let result = execute_request_impl(request.clone(), None, &context, &service).await;
+        assert_eq!(result, Err(HTTPServiceError::AuthorizationRequired));
+        let other = request.clone().with_auth(Auth::bearer("t0ken"));
+        assert!(start_oauth2_authorization_impl(other, &context, &service)
+            .await
+            .is_err());
+
+        let start = start_oauth2_authorization_impl(request.clone(), &context, &service)
+            .await
+            .unwrap();
+        assert!(start
+            .authorization_url
+            .starts_with(&format!("{}/authorize?", mock_server.uri())));
+        let state = url::Url::parse(&start.authorization_url)
+            .unwrap()
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .unwrap()
+            .1
+            .into_owned();
+        let callback = format!("{}?code=c0de&state={}", start.redirect_uri, state);
+        let browser = tokio::spawn(async move { reqwest::get(callback).await.unwrap().status() });
+
+        let info = finish_oauth2_authorization_impl(&start.session_id, &service)
+            .await
+            .unwrap();
+        assert_eq!(info.token_type, "Bearer");
+        assert!(!info.refreshable);
+        assert_eq!(browser.await.unwrap(), 200);
+
+        let response = execute_request_impl(request.clone(), None, &context, &service)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        // Tokens belong to their collection
+        service.oauth2_tokens().clear(Some(&collection_path));
+        let result = execute_request_impl(request, None, &context, &service).await;
+        assert_eq!(result, Err(HTTPServiceError::AuthorizationRequired));
+    }
+}
@@ -1,43 +1,291 @@
-use crate::models::{Request, Response};
-use crate::services::http::HTTPService;
+use crate::commands::collections::{validate_path_in_collections, AppState};
+use crate::models::{ProxyConfig, Request, Response};
+use crate::services::assertions;
+use crate::services::extraction::{self, RuntimeVariables};
+use crate::services::http::{ExecutionContext, HTTPService, HTTPServiceError, HTTPServiceResult};
+use crate::services::template::{self, ResolvedRequest};
 use crate::storage::request_store::RequestStore;
+use crate::storage::{CollectionManager, CookieJars, SecretVault, SettingsStore, VaultError};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use tokio::sync::Mutex as TokioMutex;
 
 /// Execute an HTTP request
 ///
 /// # Arguments
 ///
 /// * `request` - The HTTP request to execute
+/// * `execution_id` - Optional caller-chosen ID that makes the execution
+///   cancellable via `cancel_request`
+/// * `collection_path` - Optional path of the collection the request belongs
+///   to, whose settings the request inherits
 /// * `http_service` - Shared HTTP service instance
+/// * `app_state` - Application state containing the collection manager
+/// * `settings_store` - Shared application settings
+/// * `cookie_jars` - Shared cookie jars
+/// * `secret_vault` - Shared secret vault
 ///
 /// # Returns
 ///
-/// The HTTP response or an error message
+/// The HTTP response or a structured error (`kind` is `cancelled` when the
+/// execution was cancelled)
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // Each managed state is a separate argument
 pub async fn execute_request(
     request: Request,
-    http_service: tauri::State<'_, Arc<TokioMutex<HTTPService>>>,
-) -> Result<Response, String> {
-    execute_request_impl(request, &http_service).await
+    execution_id: Option<String>,
+    collection_path: Option<String>,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+    app_state: tauri::State<'_, AppState>,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+    cookie_jars: tauri::State<'_, Arc<CookieJars>>,
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> HTTPServiceResult<Response> {
+    let context = resolve_execution_context(
+        collection_path.as_deref(),
+        &app_state.collection_manager,
+        &settings_store,
+        &cookie_jars,
+        &secret_vault,
+        http_service.runtime_variables(),
+    )?;
+    execute_request_impl(request, execution_id, &context, &http_service).await
+}
+
+/// Resolve the settings a request inherits from its collection and the
+/// application
+///
+/// The collection is read from disk so the latest saved settings apply, and
+/// relative certificate paths are resolved against its directory. Requests
+/// in a collection use its cookie jar and variables; the variables of the
+/// active environment override the collection's, and runtime variables
+/// extracted from earlier responses override both.
+///
+/// # Arguments
+///
+/// * `collection_path` - Optional path of the collection file
+/// * `collection_manager` - Manager of the collections directory
+/// * `settings_store` - Application settings
+/// * `cookie_jars` - Cookie jars of the collections
+/// * `secret_vault` - Vault holding the values of secret variables
+/// * `runtime_variables` - Runtime variables of the session
+pub fn resolve_execution_context(
+    collection_path: Option<&str>,
+    collection_manager: &CollectionManager,
+    settings_store: &SettingsStore,
+    cookie_jars: &CookieJars,
+    secret_vault: &SecretVault,
+    runtime_variables: &RuntimeVariables,
+) -> HTTPServiceResult<ExecutionContext> {
+    let mut context = ExecutionContext {
+        app: settings_store.get(),
+        ..Default::default()
+    };
+
+    if let Some(path) = collection_path {
+        let path = validate_path_in_collections(Path::new(path), &collection_manager.base_path)
+            .map_err(HTTPServiceError::InvalidRequest)?;
+        let collection = collection_manager.load_collection(&path).map_err(|e| {
+            HTTPServiceError::InvalidRequest(format!("Failed to load collection: {}", e))
+        })?;
+        collection.settings.validate().map_err(|e| {
+            HTTPServiceError::InvalidRequest(format!("Invalid collection settings: {}", e))
+        })?;
+
+        context
+            .variables
+            .extend_from(&collection.variables, Some(secret_vault))
+            .map_err(vault_error)?;
+        context.collection = collection.settings;
+        context.auth = collection.auth;
+        context.collection_path = Some(path.clone());
+        context.collection_dir = path.parent().map(Path::to_path_buf);
+        context.cookie_jar = Some(cookie_jars.for_collection(&path).map_err(|e| {
+            HTTPServiceError::InvalidRequest(format!("Failed to load cookies: {}", e))
+        })?);
+    }
+
+    // Environment variables take precedence over collection variables
+    if let Some(path) = &context.app.active_environment {
+        let environment = collection_manager.load_environment(path).map_err(|e| {
+            HTTPServiceError::InvalidRequest(format!("Failed to load active environment: {}", e))
+        })?;
+        context
+            .variables
+            .extend_from(&environment.variables, Some(secret_vault))
+            .map_err(vault_error)?;
+    }
+
+    // Runtime variables take precedence over both. Their values come from
+    // servers, so they are never rendered: a response must not be able to
+    // pull other variables or secrets into a request
+    for variable in runtime_variables.get(context.collection_path.as_deref()) {
+        context.variables.set_literal(variable.key, variable.value);
+    }
+
+    Ok(context)
+}
+
+fn vault_error(error: VaultError) -> HTTPServiceError {
+    match error {
+        VaultError::Locked => HTTPServiceError::VaultLocked,
+        error => HTTPServiceError::InvalidRequest(format!("Failed to read secrets: {}", error)),
+    }
 }
 
 /// Implementation of execute_request (for testing)
 pub async fn execute_request_impl(
     request: Request,
-    http_service: &Arc<TokioMutex<HTTPService>>,
-) -> Result<Response, String> {
+    execution_id: Option<String>,
+    context: &ExecutionContext,
+    http_service: &Arc<HTTPService>,
+) -> HTTPServiceResult<Response> {
+    let resolved = prepare_request(request, context)?;
+    let request = resolved.request;
+
+    // Execute the request; the service is shared without a lock so other
+    // requests keep running while this one is in flight
+    let response = match execution_id {
+        Some(execution_id) => {
+            http_service
+                .execute_cancellable(&request, &execution_id, context)
+                .await
+        }
+        None => {
+            http_service
+                .execute_request_with_context(&request, context)
+                .await
+        }
+    }?;
+
+    // Check the response against the request's assertions, and record the
+    // generated values so the request can be reproduced
+    let assertions = assertions::evaluate(&request.assertions, &response);
+
+    // Store the extracted values for the requests that follow
+    let extractions = extraction::extract(&request.extractions, &response);
+    for result in &extractions {
+        if let Some(value) = &result.value {
+            http_service.runtime_variables().set(
+                context.collection_path.as_deref(),
+                &result.variable,
+                value,
+            );
+        }
+    }
+
+    Ok(response
+        .with_dynamic_variables(resolved.dynamic_variables)
+        .with_assertions(assertions)
+        .with_extractions(extractions))
+}
+
+/// Turn a saved request into the request that is sent
+///
+/// Takes the collection's auth if the request inherits it and the proxy that
+/// applies to it, fills in the variables, and validates the result.
+pub fn prepare_request(
+    mut request: Request,
+    context: &ExecutionContext,
+) -> HTTPServiceResult<ResolvedRequest> {
+    // Take the collection's auth before filling in the variables, so
+    // inherited credentials can use them too
+    if request.auth.is_inherit() {
+        request.auth = context.auth.clone();
+    }
+    // Likewise the proxy, whose password can name a secret
+    if let proxy @ ProxyConfig::Manual(_) = context.proxy_for(&request) {
+        request.settings.get_or_insert_with(Default::default).proxy = Some(proxy);
+    }
+
+    // Fill in the variables first so the request that is sent is validated.
+    // Secrets only need the vault if the request uses them
+    let resolved =
+        template::resolve_request(&request, &context.variables).map_err(|unresolved| {
+            if unresolved
+                .iter()
+                .any(|variable| context.variables.is_locked(&variable.name))
+            {
+                HTTPServiceError::VaultLocked
+            } else {
+                HTTPServiceError::UnresolvedVariables(unresolved)
+            }
+        })?;
+
     // Validate the request
-    request.validate().map_err(|e| e.to_string())?;
+    resolved
+        .request
+        .validate()
+        .map_err(|e| HTTPServiceError::InvalidRequest(e.to_string()))?;
 
-    // Get the HTTP service
-    let service = http_service.lock().await;
+    Ok(resolved)
+}
 
-    // Execute the request
-    service
-        .execute_request(&request)
-        .await
-        .map_err(|e| e.to_string())
+/// Cancel an in-flight request
+///
+/// # Arguments
+///
+/// * `execution_id` - ID passed to `execute_request` for the execution
+/// * `http_service` - Shared HTTP service instance
+///
+/// # Returns
+///
+/// Ok(()) if the request was aborted or an error message if no request with
+/// that ID is in flight
+#[tauri::command]
+pub fn cancel_request(
+    execution_id: String,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+) -> Result<(), String> {
+    cancel_request_impl(execution_id, &http_service)
+}
+
+/// Implementation of cancel_request (for testing)
+pub fn cancel_request_impl(
+    execution_id: String,
+    http_service: &Arc<HTTPService>,
+) -> Result<(), String> {
+    if http_service.cancel_request(&execution_id) {
+        Ok(())
+    } else {
+        Err(format!(
+            "No in-flight request with execution ID: {}",
+            execution_id
+        ))
+    }
+}
+
+/// Set the passphrase of an encrypted client certificate key
+///
+/// The passphrase is kept in memory for the rest of the session and never
+/// written to disk.
+///
+/// # Arguments
+///
+/// * `key_path` - Path of the key file, as reported by a
+///   `passphrase_required` error
+/// * `passphrase` - The passphrase
+/// * `http_service` - Shared HTTP service instance
+#[tauri::command]
+pub fn set_certificate_passphrase(
+    key_path: String,
+    passphrase: String,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+) -> Result<(), String> {
+    set_certificate_passphrase_impl(key_path, passphrase, &http_service)
+}
+
+/// Implementation of set_certificate_passphrase (for testing)
+pub fn set_certificate_passphrase_impl(
+    key_path: String,
+    passphrase: String,
+    http_service: &Arc<HTTPService>,
+) -> Result<(), String> {
+    if key_path.trim().is_empty() {
+        return Err("Key path cannot be empty".to_string());
+    }
+
+    http_service.set_certificate_passphrase(&key_path, &passphrase);
+    Ok(())
 }
 
 /// Save a request to the store
@@ -143,19 +391,15 @@ pub fn delete_request_impl(name: String, store: &Arc<Mutex<RequestStore>>) -> Re
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::HttpMethod;
-    use std::collections::HashMap;
-    use wiremock::matchers::{method, path};
+    use crate::models::{
+        Assertion, Auth, Collection, CollectionSettings, Environment, ManualProxy, ProxyConfig,
+        RequestSettings,
+    };
+    use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     async fn create_test_request(mock_server: &MockServer) -> Request {
-        Request {
-            name: "Test Request".to_string(),
-            method: HttpMethod::Get,
-            url: format!("{}/get", mock_server.uri()),
-            headers: HashMap::new(),
-            body: None,
-        }
+        Request::new("Test Request", format!("{}/get", mock_server.uri()))
     }
 
     #[tokio::test]
@@ -168,47 +412,134 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
-        ));
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
         let request = create_test_request(&mock_server).await;
 
-        let result = execute_request_impl(request, &service).await;
+        let result =
+            execute_request_impl(request, None, &ExecutionContext::default(), &service).await;
 
         assert!(result.is_ok());
         let response = result.unwrap();
         assert!(response.status >= 200 && response.status < 300);
     }
 
+    #[tokio::test]
+    async fn test_execute_request_evaluates_assertions() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/get"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/json")
+                    .set_body_string(r#"{"status":"ok","items":[1,2]}"#),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+        let request = create_test_request(&mock_server)
+            .await
+            .with_assertion(Assertion::Status { equals: 200 })
+            .with_assertion(Assertion::JsonEquals {
+                path: "$.status".to_string(),
+                value: serde_json::json!("failed"),
+            });
+
+        let response = execute_request_impl(request, None, &ExecutionContext::default(), &service)
+            .await
+            .unwrap();
+        let outcomes: Vec<_> = response
+            .assertions
+            .iter()
+            .map(|result| (result.passed, result.actual.clone()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (true, Some(serde_json::json!(200))),
+                (false, Some(serde_json::json!("ok"))),
+            ]
+        );
+
+        // Assertions are validated with the request
+        let invalid =
+            create_test_request(&mock_server)
+                .await
+                .with_assertion(Assertion::BodyMatches {
+                    pattern: "[".to_string(),
+                });
+        let result =
+            execute_request_impl(invalid, None, &ExecutionContext::default(), &service).await;
+        assert!(matches!(result, Err(HTTPServiceError::InvalidRequest(_))));
+    }
+
     #[tokio::test]
     async fn test_execute_request_invalid_url() {
-        let service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+        let request = Request::new("Invalid", "invalid-url");
+
+        let result =
+            execute_request_impl(request, None, &ExecutionContext::default(), &service).await;
+
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            HTTPServiceError::InvalidRequest(msg) if msg.contains("Invalid URL")
         ));
-        let request = Request {
-            name: "Invalid".to_string(),
-            method: HttpMethod::Get,
-            url: "invalid-url".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_cancelled() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/get"))
+            .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(10)))
+            .mount(&mock_server)
+            .await;
+
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+        let request = create_test_request(&mock_server).await;
 
-        let result = execute_request_impl(request, &service).await;
+        let task_service = Arc::clone(&service);
+        let handle = tokio::spawn(async move {
+            execute_request_impl(
+                request,
+                Some("exec-42".to_string()),
+                &ExecutionContext::default(),
+                &task_service,
+            )
+            .await
+        });
+
+        while !service.is_in_flight("exec-42") {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        assert!(cancel_request_impl("exec-42".to_string(), &service).is_ok());
+
+        let result = handle.await.unwrap();
+        assert_eq!(
+            result.unwrap_err(),
+            HTTPServiceError::Cancelled("exec-42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cancel_request_not_in_flight() {
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+
+        let result = cancel_request_impl("unknown".to_string(), &service);
 
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid URL"));
+        assert!(result.unwrap_err().contains("No in-flight request"));
     }
 
     #[test]
     fn test_save_request_success() {
         let store = Arc::new(Mutex::new(RequestStore::new()));
-        let request = Request {
-            name: "Test".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request = Request::new("Test", "https://example.com");
 
         let result = save_request_impl(request, &store);
 
@@ -222,13 +553,7 @@ mod tests {
     #[test]
     fn test_save_request_invalid() {
         let store = Arc::new(Mutex::new(RequestStore::new()));
-        let request = Request {
-            name: "".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request = Request::new("", "https://example.com");
 
         let result = save_request_impl(request, &store);
 
@@ -239,13 +564,7 @@ mod tests {
     #[test]
     fn test_save_request_duplicate() {
         let store = Arc::new(Mutex::new(RequestStore::new()));
-        let request = Request {
-            name: "Test".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request = Request::new("Test", "https://example.com");
 
         // Save once
         let result1 = save_request_impl(request.clone(), &store);
@@ -272,22 +591,10 @@ mod tests {
         let store = Arc::new(Mutex::new(RequestStore::new()));
 
         // Add multiple requests
-        let req1 = Request {
-            name: "Request 1".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com/1".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let req1 = Request::new("Request 1", "https://example.com/1");
         save_request_impl(req1, &store).unwrap();
 
-        let req2 = Request {
-            name: "Request 2".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com/2".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let req2 = Request::new("Request 2", "https://example.com/2");
         save_request_impl(req2, &store).unwrap();
 
         let result = list_requests_impl(&store);
@@ -299,13 +606,7 @@ mod tests {
     #[test]
     fn test_delete_request_success() {
         let store = Arc::new(Mutex::new(RequestStore::new()));
-        let request = Request {
-            name: "Test".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request = Request::new("Test", "https://example.com");
         let name = request.name.clone();
 
         // Save first
@@ -352,18 +653,15 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
-        ));
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
 
         // Create multiple requests
         let requests: Vec<Request> = (0..5)
-            .map(|i| Request {
-                name: format!("Request {}", i),
-                method: HttpMethod::Get,
-                url: format!("{}/get", mock_server.uri()),
-                headers: HashMap::new(),
-                body: None,
+            .map(|i| {
+                Request::new(
+                    format!("Request {}", i),
+                    format!("{}/get", mock_server.uri()),
+                )
             })
             .collect();
 
@@ -372,7 +670,10 @@ mod tests {
             .into_iter()
             .map(|req| {
                 let service_clone = Arc::clone(&service);
-                tokio::spawn(async move { execute_request_impl(req, &service_clone).await })
+                tokio::spawn(async move {
+                    execute_request_impl(req, None, &ExecutionContext::default(), &service_clone)
+                        .await
+                })
             })
             .collect();
 
@@ -397,13 +698,10 @@ mod tests {
         for i in 0..10 {
             let store_clone = Arc::clone(&store);
             let handle = thread::spawn(move || {
-                let req = Request {
-                    name: format!("Request {}", i),
-                    method: HttpMethod::Get,
-                    url: format!("https://example.com/{}", i),
-                    headers: HashMap::new(),
-                    body: None,
-                };
+                let req = Request::new(
+                    format!("Request {}", i),
+                    format!("https://example.com/{}", i),
+                );
                 save_request_impl(req, &store_clone)
             });
             handles.push(handle);
@@ -430,13 +728,10 @@ mod tests {
 
         // Pre-populate with 10 requests
         for i in 0..10 {
-            let req = Request {
-                name: format!("Request {}", i),
-                method: HttpMethod::Get,
-                url: format!("https://example.com/{}", i),
-                headers: HashMap::new(),
-                body: None,
-            };
+            let req = Request::new(
+                format!("Request {}", i),
+                format!("https://example.com/{}", i),
+            );
             save_request_impl(req, &store).unwrap();
         }
 
@@ -474,19 +769,11 @@ mod tests {
             .await;
 
         // Setup
-        let http_service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
-        ));
+        let http_service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
         let store = Arc::new(Mutex::new(RequestStore::new()));
 
         // 1. Save a request
-        let request = Request {
-            name: "Test Request".to_string(),
-            method: HttpMethod::Get,
-            url: format!("{}/get", mock_server.uri()),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request = Request::new("Test Request", format!("{}/get", mock_server.uri()));
         save_request_impl(request.clone(), &store).unwrap();
 
         // 2. List requests and verify
@@ -495,19 +782,18 @@ mod tests {
         assert_eq!(requests[0].name, "Test Request");
 
         // 3. Execute the request
-        let response = execute_request_impl(request.clone(), &http_service)
-            .await
-            .unwrap();
+        let response = execute_request_impl(
+            request.clone(),
+            None,
+            &ExecutionContext::default(),
+            &http_service,
+        )
+        .await
+        .unwrap();
         assert!(response.status >= 200 && response.status < 300);
 
         // 4. Save another request
-        let request2 = Request {
-            name: "Test Request 2".to_string(),
-            method: HttpMethod::Get,
-            url: format!("{}/get", mock_server.uri()),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let request2 = Request::new("Test Request 2", format!("{}/get", mock_server.uri()));
         save_request_impl(request2, &store).unwrap();
 
         // 5. List again
@@ -525,30 +811,22 @@ mod tests {
 
     #[tokio::test]
     async fn test_integration_error_handling() {
-        let http_service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
-        ));
+        let http_service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
         let store = Arc::new(Mutex::new(RequestStore::new()));
 
         // Test invalid URL in execute
-        let bad_request = Request {
-            name: "Bad".to_string(),
-            method: HttpMethod::Get,
-            url: "not-a-url".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
-        let result = execute_request_impl(bad_request, &http_service).await;
+        let bad_request = Request::new("Bad", "not-a-url");
+        let result = execute_request_impl(
+            bad_request,
+            None,
+            &ExecutionContext::default(),
+            &http_service,
+        )
+        .await;
         assert!(result.is_err());
 
         // Test invalid name in save
-        let bad_request = Request {
-            name: "".to_string(),
-            method: HttpMethod::Get,
-            url: "https://example.com".to_string(),
-            headers: HashMap::new(),
-            body: None,
-        };
+        let bad_request = Request::new("", "https://example.com");
         let result = save_request_impl(bad_request, &store);
         assert!(result.is_err());
 
@@ -560,4 +838,203 @@ mod tests {
         let requests = list_requests_impl(&store).unwrap();
         assert_eq!(requests.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_execute_request_inherits_collection_proxy() {
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(|request: &wiremock::Request| {
+                request.url.host_str() == Some("api.unreachable.invalid")
+            })
+            // alice:s3cret
+            .and(header("proxy-authorization", "Basic YWxpY2U6czNjcmV0"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("via proxy"))
+            .mount(&proxy)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        let cookie_jars = CookieJars::new();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        vault.unlock("passphrase").unwrap();
+        vault.set("proxy-password", "s3cret").unwrap();
+        // The proxy password names a secret instead of being saved in the file
+        let collection = Collection::new("Proxied")
+            .with_secret("proxy_password", "proxy-password")
+            .with_settings(CollectionSettings {
+                proxy: Some(ProxyConfig::Manual(
+                    ManualProxy::new(proxy.uri()).with_credentials("alice", "{{proxy_password}}"),
+                )),
+                ..Default::default()
+            });
+        let collection_path = manager.save_collection(&collection, "proxied").unwrap();
+        let saved = std::fs::read_to_string(&collection_path).unwrap();
+        assert!(!saved.contains("s3cret"));
+
+        let context = resolve_execution_context(
+            Some(&collection_path.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &cookie_jars,
+            &vault,
+            &RuntimeVariables::new(),
+        )
+        .unwrap();
+        assert_eq!(context.collection, collection.settings);
+        assert_eq!(
+            context.cookie_jar.as_ref().unwrap().path(),
+            collection_path.with_file_name("proxied.cookies.yaml")
+        );
+
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+        let request = Request::new("Status", "http://api.unreachable.invalid/status");
+        let response = execute_request_impl(request.clone(), None, &context, &service)
+            .await
+            .unwrap();
+        assert_eq!(response.body, "via proxy");
+
+        // Request settings take precedence over the collection
+        let direct = request.with_settings(RequestSettings {
+            proxy: Some(ProxyConfig::None),
+            ..Default::default()
+        });
+        assert!(execute_request_impl(direct, None, &context, &service)
+            .await
+            .is_err());
+
+        // Collections outside the collections directory are rejected
+        let outside = temp_dir.path().join("settings.yaml");
+        std::fs::write(&outside, "{}").unwrap();
+        let result = resolve_execution_context(
+            Some(&outside.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &cookie_jars,
+            &SecretVault::new(temp_dir.path().join("secrets.vault")),
+            &RuntimeVariables::new(),
+        );
+        assert!(matches!(result, Err(HTTPServiceError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_resolves_collection_variables() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/users/7"))
+            .and(header("authorization", "Bearer t0ken"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        let collection = Collection::new("Users")
+            .with_variable("base_url", mock_server.uri())
+            .with_secret("token", "api-token")
+            .with_auth(Auth::bearer("{{token}}"));
+        let collection_path = manager.save_collection(&collection, "users").unwrap();
+
+        let service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
+
+        // Secret variables need the vault to be unlocked, but only for the
+        // requests that use them
+        let locked = resolve_execution_context(
+            Some(&collection_path.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &CookieJars::new(),
+            &vault,
+            &RuntimeVariables::new(),
+        )
+        .unwrap();
+        let health = Request::new("Health", "{{base_url}}/health").with_auth(Auth::None);
+        let response = execute_request_impl(health, None, &locked, &service)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        let secret = Request::new("User", "{{base_url}}/users/7");
+        let result = execute_request_impl(secret, None, &locked, &service).await;
+        assert!(matches!(result, Err(HTTPServiceError::VaultLocked)));
+
+        vault.unlock("passphrase").unwrap();
+        vault.set("api-token", "t0ken").unwrap();
+
+        let context = resolve_execution_context(
+            Some(&collection_path.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &CookieJars::new(),
+            &vault,
+            &RuntimeVariables::new(),
+        )
+        .unwrap();
+
+        // The request inherits the collection's auth
+        let request = Request::new("User", "{{base_url}}/users/{{user_id}}")
+            .with_header("Idempotency-Key", "{{$uuid}}");
+        let result = execute_request_impl(request.clone(), None, &context, &service).await;
+        let Err(HTTPServiceError::UnresolvedVariables(unresolved)) = result else {
+            panic!("Expected unresolved variables, got {:?}", result);
+        };
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].name, "user_id");
+        assert_eq!(unresolved[0].location, "url");
+
+        // The error lists each placeholder for the frontend
+        let json = serde_json::to_value(HTTPServiceError::UnresolvedVariables(unresolved)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "unresolved_variables",
+                "message": [{ "name": "user_id", "location": "url" }]
+            })
+        );
+
+        // The active environment fills in the rest and overrides the
+        // collection
+        let environment = Environment::new("Local")
+            .with_variable("user_id", "7")
+            .with_variable("base_url", mock_server.uri());
+        let environment_path = manager.create_environment(&environment, "local").unwrap();
+        let mut settings = settings_store.get();
+        settings.active_environment = Some(environment_path.to_string_lossy().to_string());
+        settings_store.update(settings).unwrap();
+        let context = resolve_execution_context(
+            Some(&collection_path.to_string_lossy()),
+            &manager,
+            &settings_store,
+            &CookieJars::new(),
+            &vault,
+            &RuntimeVariables::new(),
+        )
+        .unwrap();
+        assert_eq!(context.variables.get("user_id"), Some("7"));
+
+        let response = execute_request_impl(request, None, &context, &service)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        // Generated values are recorded with the response
+        assert_eq!(response.dynamic_variables.len(), 1);
+        assert_eq!(response.dynamic_variables[0].expression, "$uuid");
+        let received = mock_server.received_requests().await.unwrap();
+        assert_eq!(
+            received
+                .last()
+                .unwrap()
+                .headers
+                .get("idempotency-key")
+                .unwrap(),
+            response.dynamic_variables[0].value.as_str()
+        );
+    }
 }
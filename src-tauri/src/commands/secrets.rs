@@ -0,0 +1,186 @@
+//! Secret commands
+//!
+//! This module provides Tauri commands for unlocking the local secret vault
+//! and managing the secrets that secret variables refer to. Secret values
+//! are never sent back to the frontend.
+
+use crate::storage::SecretVault;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// State of the secret vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultStatus {
+    /// Whether the vault file exists; if not, unlocking sets the passphrase
+    pub exists: bool,
+
+    /// Whether secrets can be read and stored
+    pub unlocked: bool,
+}
+
+/// Get the state of the secret vault
+///
+/// # Arguments
+///
+/// * `secret_vault` - Shared secret vault
+#[tauri::command]
+pub fn get_vault_status(secret_vault: tauri::State<'_, Arc<SecretVault>>) -> VaultStatus {
+    get_vault_status_impl(&secret_vault)
+}
+
+/// Implementation of get_vault_status (for testing)
+pub fn get_vault_status_impl(secret_vault: &SecretVault) -> VaultStatus {
+    VaultStatus {
+        exists: secret_vault.exists(),
+        unlocked: secret_vault.is_unlocked(),
+    }
+}
+
+/// Unlock the secret vault for the rest of the session
+///
+/// The first unlock, before the vault file exists, sets the passphrase.
+///
+/// # Arguments
+///
+/// * `passphrase` - Passphrase of the vault
+/// * `secret_vault` - Shared secret vault
+#[tauri::command]
+pub fn unlock_vault(
+    passphrase: String,
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> Result<(), String> {
+    unlock_vault_impl(&passphrase, &secret_vault)
+}
+
+/// Implementation of unlock_vault (for testing)
+pub fn unlock_vault_impl(passphrase: &str, secret_vault: &SecretVault) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty".to_string());
+    }
+
+    secret_vault
+        .unlock(passphrase)
+        .map_err(|e| format!("Failed to unlock vault: {}", e))
+}
+
+/// Lock the secret vault, forgetting the decrypted secrets
+///
+/// # Arguments
+///
+/// * `secret_vault` - Shared secret vault
+#[tauri::command]
+pub fn lock_vault(secret_vault: tauri::State<'_, Arc<SecretVault>>) {
+    secret_vault.lock();
+}
+
+/// List the names of the stored secrets
+///
+/// # Arguments
+///
+/// * `secret_vault` - Shared secret vault
+///
+/// # Returns
+///
+/// The secret names in alphabetical order
+#[tauri::command]
+pub fn list_secrets(
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> Result<Vec<String>, String> {
+    list_secrets_impl(&secret_vault)
+}
+
+/// Implementation of list_secrets (for testing)
+pub fn list_secrets_impl(secret_vault: &SecretVault) -> Result<Vec<String>, String> {
+    secret_vault
+        .names()
+        .map_err(|e| format!("Failed to list secrets: {}", e))
+}
+
+/// Store a secret, replacing any previous value
+///
+/// # Arguments
+///
+/// * `name` - Name that secret variables refer to
+/// * `value` - Secret value
+/// * `secret_vault` - Shared secret vault
+#[tauri::command]
+pub fn set_secret(
+    name: String,
+    value: String,
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> Result<(), String> {
+    set_secret_impl(&name, &value, &secret_vault)
+}
+
+/// Implementation of set_secret (for testing)
+pub fn set_secret_impl(name: &str, value: &str, secret_vault: &SecretVault) -> Result<(), String> {
+    secret_vault
+        .set(name, value)
+        .map_err(|e| format!("Failed to store secret: {}", e))
+}
+
+/// Delete a secret
+///
+/// # Arguments
+///
+/// * `name` - Name of the secret
+/// * `secret_vault` - Shared secret vault
+///
+/// # Returns
+///
+/// `true` if the secret existed
+#[tauri::command]
+pub fn delete_secret(
+    name: String,
+    secret_vault: tauri::State<'_, Arc<SecretVault>>,
+) -> Result<bool, String> {
+    delete_secret_impl(&name, &secret_vault)
+}
+
+/// Implementation of delete_secret (for testing)
+pub fn delete_secret_impl(name: &str, secret_vault: &SecretVault) -> Result<bool, String> {
+    secret_vault
+        .remove(name)
+        .map_err(|e| format!("Failed to delete secret: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_secret_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        assert_eq!(
+            get_vault_status_impl(&vault),
+            VaultStatus {
+                exists: false,
+                unlocked: false
+            }
+        );
+        assert!(list_secrets_impl(&vault).is_err());
+        assert!(set_secret_impl("token", "s3cr3t", &vault).is_err());
+        assert!(unlock_vault_impl("", &vault).is_err());
+
+        unlock_vault_impl("passphrase", &vault).unwrap();
+        set_secret_impl("token", "s3cr3t", &vault).unwrap();
+        assert!(set_secret_impl(" ", "s3cr3t", &vault).is_err());
+        assert_eq!(list_secrets_impl(&vault).unwrap(), vec!["token"]);
+        assert_eq!(
+            get_vault_status_impl(&vault),
+            VaultStatus {
+                exists: true,
+                unlocked: true
+            }
+        );
+
+        assert!(delete_secret_impl("token", &vault).unwrap());
+        assert!(!delete_secret_impl("token", &vault).unwrap());
+
+        vault.lock();
+        let error = unlock_vault_impl("wrong", &vault).unwrap_err();
+        assert!(error.contains("Wrong passphrase"));
+    }
+}
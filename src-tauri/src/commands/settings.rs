@@ -0,0 +1,123 @@
+//! Application settings commands
+//!
+//! This module provides Tauri commands for reading and updating the
+//! application-wide settings.
+
+use crate::models::AppSettings;
+use crate::storage::SettingsStore;
+use std::sync::Arc;
+
+/// Get the application settings
+///
+/// # Arguments
+///
+/// * `settings_store` - Shared settings store instance
+///
+/// # Returns
+///
+/// The current application settings
+#[tauri::command]
+pub fn get_app_settings(settings_store: tauri::State<'_, Arc<SettingsStore>>) -> AppSettings {
+    get_app_settings_impl(&settings_store)
+}
+
+/// Implementation of get_app_settings (for testing)
+pub fn get_app_settings_impl(settings_store: &Arc<SettingsStore>) -> AppSettings {
+    settings_store.get()
+}
+
+/// Update and persist the application settings
+///
+/// The active environment is kept as stored; only the environment commands
+/// change it.
+///
+/// # Arguments
+///
+/// * `settings` - The new settings
+/// * `settings_store` - Shared settings store instance
+///
+/// # Returns
+///
+/// Ok(()) on success or an error message if the settings are invalid or
+/// could not be saved
+#[tauri::command]
+pub fn update_app_settings(
+    settings: AppSettings,
+    settings_store: tauri::State<'_, Arc<SettingsStore>>,
+) -> Result<(), String> {
+    update_app_settings_impl(settings, &settings_store)
+}
+
+/// Implementation of update_app_settings (for testing)
+pub fn update_app_settings_impl(
+    mut settings: AppSettings,
+    settings_store: &Arc<SettingsStore>,
+) -> Result<(), String> {
+    settings.active_environment = settings_store.get().active_environment;
+    settings_store
+        .update(settings)
+        .map_err(|e| format!("Failed to update settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ManualProxy, ProxyConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_update_and_get_app_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = Arc::new(SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap());
+        assert_eq!(get_app_settings_impl(&store).proxy, ProxyConfig::System);
+
+        let settings = AppSettings {
+            proxy: ProxyConfig::Manual(
+                ManualProxy::new("http://proxy.corp:3128").with_bypass("localhost"),
+            ),
+            ..Default::default()
+        };
+        update_app_settings_impl(settings.clone(), &store).unwrap();
+        assert_eq!(get_app_settings_impl(&store), settings);
+
+        let invalid = AppSettings {
+            proxy: ProxyConfig::Manual(ManualProxy::new("")),
+            ..Default::default()
+        };
+        assert!(update_app_settings_impl(invalid, &store).is_err());
+        assert_eq!(get_app_settings_impl(&store), settings);
+    }
+
+    #[test]
+    fn test_update_app_settings_keeps_active_environment() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = Arc::new(SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap());
+        let active = Some("/collections/api/environments/staging.yaml".to_string());
+        store
+            .update(AppSettings {
+                active_environment: active.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // A stale copy does not deactivate the environment
+        let stale = AppSettings {
+            proxy: ProxyConfig::Manual(ManualProxy::new("http://proxy.corp:3128")),
+            active_environment: None,
+        };
+        update_app_settings_impl(stale, &store).unwrap();
+        assert_eq!(get_app_settings_impl(&store).active_environment, active);
+        assert!(matches!(
+            get_app_settings_impl(&store).proxy,
+            ProxyConfig::Manual(_)
+        ));
+
+        // Nor does it activate an unchecked path
+        let forged = AppSettings {
+            active_environment: Some("/etc/passwd".to_string()),
+            ..Default::default()
+        };
+        update_app_settings_impl(forged, &store).unwrap();
+        assert_eq!(get_app_settings_impl(&store).active_environment, active);
+    }
+}
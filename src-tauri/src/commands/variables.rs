@@ -0,0 +1,251 @@
+//! Runtime variable commands
+//!
+//! This module provides Tauri commands for viewing and forgetting the runtime
+//! variables that requests extract from their responses. Variables are set
+//! when a request is executed.
+
+use crate::commands::collections::{validate_path_in_collections, AppState};
+use crate::models::Variable;
+use crate::services::http::HTTPService;
+use crate::storage::CollectionManager;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// List the runtime variables of a collection
+///
+/// # Arguments
+///
+/// * `collection_path` - Collection whose variables are listed, or `None`
+///   for those of requests outside collections
+/// * `http_service` - Shared HTTP service, which holds the variables
+/// * `app_state` - Application state containing the collection manager
+///
+/// # Returns
+///
+/// The variables sorted by name
+#[tauri::command]
+pub fn list_runtime_variables(
+    collection_path: Option<String>,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<Variable>, String> {
+    list_runtime_variables_impl(
+        collection_path,
+        &http_service,
+        &app_state.collection_manager,
+    )
+}
+
+/// Implementation of list_runtime_variables (for testing)
+pub fn list_runtime_variables_impl(
+    collection_path: Option<String>,
+    http_service: &HTTPService,
+    collection_manager: &CollectionManager,
+) -> Result<Vec<Variable>, String> {
+    let path = scope(collection_path, collection_manager)?;
+    Ok(http_service.runtime_variables().get(path.as_deref()))
+}
+
+/// Forget the runtime variables of a collection
+///
+/// # Arguments
+///
+/// * `collection_path` - Collection whose variables are forgotten, or `None`
+///   for those of requests outside collections
+/// * `http_service` - Shared HTTP service, which holds the variables
+/// * `app_state` - Application state containing the collection manager
+#[tauri::command]
+pub fn clear_runtime_variables(
+    collection_path: Option<String>,
+    http_service: tauri::State<'_, Arc<HTTPService>>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    clear_runtime_variables_impl(
+        collection_path,
+        &http_service,
+        &app_state.collection_manager,
+    )
+}
+
+/// Implementation of clear_runtime_variables (for testing)
+pub fn clear_runtime_variables_impl(
+    collection_path: Option<String>,
+    http_service: &HTTPService,
+    collection_manager: &CollectionManager,
+) -> Result<(), String> {
+    let path = scope(collection_path, collection_manager)?;
+    http_service.runtime_variables().clear(path.as_deref());
+    Ok(())
+}
+
+/// Validate a collection path the same way `execute_request` does, so the
+/// variables are found under the same scope
+fn scope(
+    collection_path: Option<String>,
+    collection_manager: &CollectionManager,
+) -> Result<Option<PathBuf>, String> {
+    collection_path
+        .map(|path| validate_path_in_collections(Path::new(&path), &collection_manager.base_path))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::requests::{execute_request_impl, resolve_execution_context};
+    use crate::models::{Collection, Extraction, ExtractionSource, HttpMethod, Request};
+    use crate::storage::{CookieJars, SecretVault, SettingsStore};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_extracted_variables_chain_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "token": "t0ken" }))
+                    .insert_header("Set-Cookie", "SESSION=s1; Path=/"),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/me"))
+            .and(header("authorization", "Bearer t0ken"))
+            .and(header("x-session", "s1"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        let collection = Collection::new("Api").with_variable("token", "from-collection");
+        let collection_path = manager.save_collection(&collection, "api").unwrap();
+        let service = Arc::new(HTTPService::new().unwrap());
+        let context = || {
+            resolve_execution_context(
+                Some(&collection_path.to_string_lossy()),
+                &manager,
+                &settings_store,
+                &CookieJars::new(),
+                &vault,
+                service.runtime_variables(),
+            )
+            .unwrap()
+        };
+
+        let login = Request::new("Login", format!("{}/login", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_extraction(Extraction::new(
+                "token",
+                ExtractionSource::JsonPath {
+                    path: "$.token".to_string(),
+                },
+            ))
+            .with_extraction(Extraction::new(
+                "session",
+                ExtractionSource::Cookie {
+                    name: "SESSION".to_string(),
+                },
+            ));
+        let response = execute_request_impl(login, None, &context(), &service)
+            .await
+            .unwrap();
+        assert_eq!(response.extractions[0].value.as_deref(), Some("t0ken"));
+
+        let variables = list_runtime_variables_impl(
+            Some(collection_path.to_string_lossy().into_owned()),
+            &service,
+            &manager,
+        )
+        .unwrap();
+        assert_eq!(
+            variables,
+            vec![
+                Variable::new("session", "s1"),
+                Variable::new("token", "t0ken")
+            ]
+        );
+        // Requests outside the collection do not see them
+        assert!(list_runtime_variables_impl(None, &service, &manager)
+            .unwrap()
+            .is_empty());
+
+        // The runtime value overrides the collection's
+        let me = Request::new("Me", format!("{}/me", mock_server.uri()))
+            .with_header("Authorization", "Bearer {{token}}")
+            .with_header("X-Session", "{{session}}");
+        let response = execute_request_impl(me.clone(), None, &context(), &service)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        clear_runtime_variables_impl(
+            Some(collection_path.to_string_lossy().into_owned()),
+            &service,
+            &manager,
+        )
+        .unwrap();
+        let result = execute_request_impl(me, None, &context(), &service).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extracted_values_are_not_rendered() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "token": "{{secret}} {{$uuid}}" })),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/me"))
+            .and(header("authorization", "Bearer {{secret}} {{$uuid}}"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path().join("collections")).unwrap();
+        let settings_store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        let collection = Collection::new("Api").with_variable("secret", "hunter2");
+        let collection_path = manager.save_collection(&collection, "api").unwrap();
+        let service = Arc::new(HTTPService::new().unwrap());
+        let context = || {
+            resolve_execution_context(
+                Some(&collection_path.to_string_lossy()),
+                &manager,
+                &settings_store,
+                &CookieJars::new(),
+                &vault,
+                service.runtime_variables(),
+            )
+            .unwrap()
+        };
+
+        let login = Request::new("Login", format!("{}/login", mock_server.uri())).with_extraction(
+            Extraction::new(
+                "token",
+                ExtractionSource::JsonPath {
+                    path: "$.token".to_string(),
+                },
+            ),
+        );
+        execute_request_impl(login, None, &context(), &service)
+            .await
+            .unwrap();
+
+        // A hostile server cannot pull the collection's variables into the
+        // requests that use its values
+        let me = Request::new("Me", format!("{}/me", mock_server.uri()))
+            .with_header("Authorization", "Bearer {{token}}");
+        let response = execute_request_impl(me, None, &context(), &service)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+    }
+}
@@ -7,11 +7,32 @@ use commands::collections::{
     create_new_collection, delete_collection, list_collections, load_collection,
     open_collection_dialog, save_collection, validate_collection, AppState,
 };
-use commands::requests::{delete_request, execute_request, list_requests, save_request};
+use commands::cookies::{
+    clear_cookies, delete_cookie, list_cookies, set_cookie, set_cookies_enabled,
+};
+use commands::environments::{
+    activate_environment, create_environment, delete_environment, list_environments,
+    update_environment,
+};
+use commands::jwt::inspect_response_jwts;
+use commands::oauth2::{
+    clear_oauth2_tokens, finish_oauth2_authorization, start_oauth2_authorization,
+};
+use commands::requests::{
+    cancel_request, delete_request, execute_request, list_requests, save_request,
+    set_certificate_passphrase,
+};
+use commands::secrets::{
+    delete_secret, get_vault_status, list_secrets, lock_vault, set_secret, unlock_vault,
+};
+use commands::settings::{get_app_settings, update_app_settings};
+use commands::variables::{clear_runtime_variables, list_runtime_variables};
 use services::http::HTTPService;
 use std::sync::{Arc, Mutex};
-use storage::{collection_manager::CollectionManager, request_store::RequestStore};
-use tokio::sync::Mutex as TokioMutex;
+use storage::{
+    collection_manager::CollectionManager, cookie_jar::CookieJars, request_store::RequestStore,
+    secret_vault::SecretVault, settings_store::SettingsStore,
+};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -22,25 +43,40 @@ fn greet(name: &str) -> String {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize shared state
-    let http_service = Arc::new(TokioMutex::new(
-        HTTPService::new().expect("Failed to create HTTP service"),
-    ));
+    let http_service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
     let request_store = Arc::new(Mutex::new(RequestStore::new()));
 
+    // Secrets live outside the collections directory, which may be committed
+    let secret_vault = Arc::new(SecretVault::new("./secrets.vault"));
+
     // Initialize collection manager
     let collection_manager = Arc::new(
-        CollectionManager::new("./collections").expect("Failed to create collection manager"),
+        CollectionManager::new("./collections")
+            .expect("Failed to create collection manager")
+            .with_secret_vault(Arc::clone(&secret_vault)),
     );
     let app_state = AppState { collection_manager };
 
+    // Initialize application settings
+    let settings_store =
+        Arc::new(SettingsStore::load("./settings.yaml").expect("Failed to load settings"));
+
+    // Cookie jars are loaded per collection on first use
+    let cookie_jars = Arc::new(CookieJars::new());
+
     tauri::Builder::default()
         .manage(http_service)
         .manage(request_store)
         .manage(app_state)
+        .manage(settings_store)
+        .manage(cookie_jars)
+        .manage(secret_vault)
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             execute_request,
+            cancel_request,
+            set_certificate_passphrase,
             save_request,
             list_requests,
             delete_request,
@@ -50,7 +86,31 @@ pub fn run() {
             open_collection_dialog,
             list_collections,
             delete_collection,
-            validate_collection
+            validate_collection,
+            get_app_settings,
+            update_app_settings,
+            list_cookies,
+            set_cookie,
+            delete_cookie,
+            clear_cookies,
+            set_cookies_enabled,
+            list_environments,
+            create_environment,
+            update_environment,
+            delete_environment,
+            activate_environment,
+            get_vault_status,
+            unlock_vault,
+            lock_vault,
+            list_secrets,
+            set_secret,
+            delete_secret,
+            start_oauth2_authorization,
+            finish_oauth2_authorization,
+            clear_oauth2_tokens,
+            inspect_response_jwts,
+            list_runtime_variables,
+            clear_runtime_variables
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -94,10 +154,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_http_service_initialization() {
-        // Test that HTTPService can be created and wrapped in Arc<TokioMutex>
-        let http_service = Arc::new(TokioMutex::new(
-            HTTPService::new().expect("Failed to create HTTP service"),
-        ));
+        // Test that HTTPService can be created and shared via Arc without a lock
+        let http_service = Arc::new(HTTPService::new().expect("Failed to create HTTP service"));
         assert!(Arc::strong_count(&http_service) == 1);
     }
 
@@ -0,0 +1,194 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A check of the response, declared on a request
+///
+/// Stored with a `type` tag, e.g.:
+///
+/// ```yaml
+/// assertions:
+///   - type: status
+///     equals: 200
+///   - type: json_equals
+///     path: $.items[0].id
+///     value: 42
+///   - type: response_time
+///     under_ms: 500
+/// ```
+///
+/// JSON paths use the RFC 9535 syntax; patterns are regular expressions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// The status code equals a value
+    Status { equals: u16 },
+
+    /// The status code is within a range, both ends included
+    StatusRange { min: u16, max: u16 },
+
+    /// The response has a header
+    HeaderPresent { name: String },
+
+    /// A value of the header matches a pattern
+    HeaderMatches { name: String, pattern: String },
+
+    /// The value at a JSON path equals a value
+    JsonEquals { path: String, value: Value },
+
+    /// The array at a JSON path has an element equal to the value, or the
+    /// string at the path contains the value
+    JsonContains { path: String, value: Value },
+
+    /// The value at a JSON path has a type
+    JsonType { path: String, json_type: JsonType },
+
+    /// The body matches a pattern
+    BodyMatches { pattern: String },
+
+    /// The response arrived in less than a number of milliseconds
+    ResponseTime { under_ms: u64 },
+}
+
+/// Type of a JSON value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonType {
+    String,
+    Number,
+    /// A number without a fractional part
+    Integer,
+    Boolean,
+    Array,
+    Object,
+    Null,
+}
+
+impl JsonType {
+    /// Checks whether a value has this type
+    pub fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+            Self::Null => value.is_null(),
+        }
+    }
+}
+
+impl Assertion {
+    /// Validates the range, patterns and JSON paths
+    pub fn validate(&self) -> ModelResult<()> {
+        match self {
+            Self::StatusRange { min, max } if min > max => Err(ModelError::ValidationError(
+                format!("Status range {}-{} is empty", min, max),
+            )),
+            Self::HeaderPresent { name } | Self::HeaderMatches { name, .. }
+                if name.trim().is_empty() =>
+            {
+                Err(ModelError::EmptyField("assertion.name".to_string()))
+            }
+            Self::HeaderMatches { pattern, .. } | Self::BodyMatches { pattern } => {
+                regex::Regex::new(pattern).map(|_| ()).map_err(|e| {
+                    ModelError::ValidationError(format!("Invalid pattern '{}': {}", pattern, e))
+                })
+            }
+            Self::JsonEquals { path, .. }
+            | Self::JsonContains { path, .. }
+            | Self::JsonType { path, .. } => serde_json_path::JsonPath::parse(path)
+                .map(|_| ())
+                .map_err(|e| {
+                    ModelError::ValidationError(format!("Invalid JSON path '{}': {}", path, e))
+                }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Outcome of an assertion against a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub assertion: Assertion,
+
+    pub passed: bool,
+
+    /// The value that was checked; absent if there was none, e.g. a missing
+    /// header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<Value>,
+
+    /// Why the assertion could not be checked, e.g. a body that is not JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_assertion_yaml() {
+        let yaml = "- type: status_range\n  min: 200\n  max: 299\n- type: json_type\n  path: $.items\n  json_type: array\n- type: json_contains\n  path: $.tags\n  value: admin\n";
+        let assertions: Vec<Assertion> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            assertions,
+            vec![
+                Assertion::StatusRange { min: 200, max: 299 },
+                Assertion::JsonType {
+                    path: "$.items".to_string(),
+                    json_type: JsonType::Array,
+                },
+                Assertion::JsonContains {
+                    path: "$.tags".to_string(),
+                    value: json!("admin"),
+                },
+            ]
+        );
+        assert_eq!(serde_yaml::to_string(&assertions).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_assertion_validation() {
+        assert!(Assertion::StatusRange { min: 200, max: 299 }
+            .validate()
+            .is_ok());
+        assert!(Assertion::StatusRange { min: 300, max: 200 }
+            .validate()
+            .is_err());
+        assert!(Assertion::HeaderPresent {
+            name: " ".to_string()
+        }
+        .validate()
+        .is_err());
+        assert!(Assertion::BodyMatches {
+            pattern: "(unclosed".to_string()
+        }
+        .validate()
+        .is_err());
+        assert!(Assertion::JsonEquals {
+            path: "items[0]".to_string(),
+            value: json!(1),
+        }
+        .validate()
+        .is_err());
+        assert!(Assertion::JsonEquals {
+            path: "$.items[0]".to_string(),
+            value: json!(1),
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_json_type() {
+        assert!(JsonType::Number.matches(&json!(1.5)));
+        assert!(!JsonType::Integer.matches(&json!(1.5)));
+        assert!(JsonType::Integer.matches(&json!(2)));
+        assert!(JsonType::Null.matches(&Value::Null));
+        assert!(!JsonType::Object.matches(&json!([])));
+    }
+}
@@ -0,0 +1,847 @@
+use crate::models::body::KeyValue;
+use crate::models::error::{ModelError, ModelResult};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+
+/// How a request authenticates
+///
+/// Stored with a `type` tag, e.g.:
+///
+/// ```yaml
+/// auth:
+///   type: api_key
+///   key: X-Api-Key
+///   value: "{{api_key}}"
+///   location: header
+/// ```
+///
+/// Credentials are applied when the request is sent and are never added to
+/// the request's headers. A collection's auth has nothing to inherit from, so
+/// `inherit` there means no auth.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    /// Send no credentials, even if the collection has some
+    None,
+
+    /// Use the auth of the collection
+    #[default]
+    Inherit,
+
+    /// HTTP Basic authentication
+    Basic {
+        username: String,
+
+        #[serde(default)]
+        password: String,
+    },
+
+    /// `Authorization: Bearer <token>`
+    Bearer { token: String },
+
+    /// A key sent as a header or query parameter
+    ApiKey {
+        /// Header or query parameter name
+        key: String,
+
+        value: String,
+
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+
+    /// A bearer token obtained from an OAuth 2.0 token endpoint
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuth2Config),
+
+    /// AWS Signature Version 4
+    #[serde(rename = "aws_sigv4")]
+    AwsSigV4(AwsSigV4Config),
+
+    /// HTTP Digest authentication, answering the server's challenge
+    Digest {
+        username: String,
+
+        #[serde(default)]
+        password: String,
+    },
+
+    /// An HMAC signature over parts of the request
+    Hmac(HmacConfig),
+
+    /// A JSON Web Token signed at send time, sent as a bearer token
+    Jwt(JwtConfig),
+}
+
+/// Where an API key is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// OAuth 2.0 client settings
+///
+/// ```yaml
+/// auth:
+///   type: oauth2
+///   grant: client_credentials
+///   token_url: https://auth.example.com/oauth/token
+///   client_id: my-client
+///   client_secret: "{{client_secret}}"
+///   scope: read write
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OAuth2Config {
+    pub grant: OAuth2Grant,
+
+    /// URL tokens are requested from
+    pub token_url: String,
+
+    pub client_id: String,
+
+    /// Empty for public clients
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub client_secret: String,
+
+    /// Space separated scopes
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+
+    /// How the client credentials are sent to the token endpoint
+    #[serde(default)]
+    pub client_authentication: ClientAuthentication,
+
+    /// Resource owner username (password grant)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+
+    /// Resource owner password (password grant)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+
+    /// URL the user is sent to for consent (authorization code grant)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub authorization_url: String,
+
+    /// Port of the loopback redirect listener (authorization code grant);
+    /// a free port is used when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_port: Option<u16>,
+}
+
+/// OAuth 2.0 grant used to obtain the first token
+///
+/// Tokens that come with a refresh token are renewed with the refresh token
+/// grant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuth2Grant {
+    ClientCredentials,
+    Password,
+    /// Authorization code with PKCE, through the user's browser
+    AuthorizationCode,
+}
+
+/// How the client credentials are sent to the token endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuthentication {
+    /// HTTP Basic authentication
+    #[default]
+    Basic,
+
+    /// `client_id` and `client_secret` form fields
+    Body,
+}
+
+impl OAuth2Config {
+    /// Creates settings for a grant
+    pub fn new(
+        grant: OAuth2Grant,
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            grant,
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: String::new(),
+            scope: String::new(),
+            client_authentication: ClientAuthentication::default(),
+            username: String::new(),
+            password: String::new(),
+            authorization_url: String::new(),
+            redirect_port: None,
+        }
+    }
+
+    /// Sets the client secret
+    pub fn with_client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = client_secret.into();
+        self
+    }
+
+    /// Sets the requested scopes
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = scope.into();
+        self
+    }
+
+    /// Sets the resource owner credentials (password grant)
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = username.into();
+        self.password = password.into();
+        self
+    }
+
+    /// Sets the authorization endpoint (authorization code grant)
+    pub fn with_authorization_url(mut self, authorization_url: impl Into<String>) -> Self {
+        self.authorization_url = authorization_url.into();
+        self
+    }
+
+    /// Validates the settings required by the grant
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.token_url.trim().is_empty() {
+            return Err(ModelError::EmptyField("auth.token_url".to_string()));
+        }
+        if self.client_id.trim().is_empty() {
+            return Err(ModelError::EmptyField("auth.client_id".to_string()));
+        }
+
+        match self.grant {
+            OAuth2Grant::ClientCredentials => Ok(()),
+            OAuth2Grant::Password if self.username.is_empty() => {
+                Err(ModelError::EmptyField("auth.username".to_string()))
+            }
+            OAuth2Grant::Password => Ok(()),
+            OAuth2Grant::AuthorizationCode if self.authorization_url.trim().is_empty() => {
+                Err(ModelError::EmptyField("auth.authorization_url".to_string()))
+            }
+            OAuth2Grant::AuthorizationCode => Ok(()),
+        }
+    }
+}
+
+/// AWS Signature Version 4 credentials
+///
+/// ```yaml
+/// auth:
+///   type: aws_sigv4
+///   access_key_id: "{{aws_access_key_id}}"
+///   secret_access_key: "{{aws_secret_access_key}}"
+///   region: eu-west-1
+///   service: execute-api
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AwsSigV4Config {
+    pub access_key_id: String,
+
+    pub secret_access_key: String,
+
+    /// Session token of temporary credentials
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub session_token: String,
+
+    pub region: String,
+
+    /// Service signing name, e.g. `execute-api` or `s3`
+    pub service: String,
+}
+
+impl AwsSigV4Config {
+    /// Creates credentials for a region and service
+    pub fn new(
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        region: impl Into<String>,
+        service: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: String::new(),
+            region: region.into(),
+            service: service.into(),
+        }
+    }
+
+    /// Sets the session token of temporary credentials
+    pub fn with_session_token(mut self, session_token: impl Into<String>) -> Self {
+        self.session_token = session_token.into();
+        self
+    }
+
+    /// Validates that every required field is set
+    pub fn validate(&self) -> ModelResult<()> {
+        for (field, value) in [
+            ("access_key_id", &self.access_key_id),
+            ("secret_access_key", &self.secret_access_key),
+            ("region", &self.region),
+            ("service", &self.service),
+        ] {
+            if value.trim().is_empty() {
+                return Err(ModelError::EmptyField(format!("auth.{}", field)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// JSON Web Token settings
+///
+/// ```yaml
+/// auth:
+///   type: jwt
+///   algorithm: RS256
+///   key: "{{jwt_private_key}}"
+///   claims:
+///     sub: "{{user_id}}"
+///     aud: orders-api
+///   expires_in: 300
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwtConfig {
+    pub algorithm: JwtAlgorithm,
+
+    /// HMAC secret, or PEM private key for RS256 and ES256; usually a secret
+    /// variable
+    pub key: String,
+
+    /// Claims of the token; `iat` and `exp` are added unless set here
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub claims: serde_json::Map<String, serde_json::Value>,
+
+    /// `kid` header
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key_id: String,
+
+    /// Lifetime of the token in seconds
+    #[serde(default = "default_jwt_expires_in")]
+    pub expires_in: u64,
+}
+
+fn default_jwt_expires_in() -> u64 {
+    300
+}
+
+/// Signing algorithm of a JSON Web Token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JwtAlgorithm {
+    /// HMAC with SHA-256
+    Hs256,
+    /// RSA PKCS#1 v1.5 with SHA-256
+    Rs256,
+    /// ECDSA P-256 with SHA-256
+    Es256,
+}
+
+impl JwtConfig {
+    /// Creates a token with no claims besides `iat` and `exp`
+    pub fn new(algorithm: JwtAlgorithm, key: impl Into<String>) -> Self {
+        Self {
+            algorithm,
+            key: key.into(),
+            claims: serde_json::Map::new(),
+            key_id: String::new(),
+            expires_in: default_jwt_expires_in(),
+        }
+    }
+
+    /// Adds a claim
+    pub fn with_claim(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.claims.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the `kid` header
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = key_id.into();
+        self
+    }
+
+    /// Sets the lifetime of the token in seconds
+    pub fn with_expires_in(mut self, expires_in: u64) -> Self {
+        self.expires_in = expires_in;
+        self
+    }
+
+    /// Validates that a key is set
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.key.trim().is_empty() {
+            return Err(ModelError::EmptyField("auth.key".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Placeholders available in HMAC signature templates
+///
+/// - `{method}` - the request method
+/// - `{path}` - the path and query, as sent
+/// - `{query}` - the query, without the `?`
+/// - `{host}` - the host, with the port if it is not the default
+/// - `{date}` - the `Date` header, which is added if the request has none
+/// - `{timestamp}` - Unix time in seconds
+/// - `{body_hash}` - hex SHA-256 of the body
+///
+/// The header value may also use `{signature}`.
+pub const HMAC_PLACEHOLDERS: &[&str] = &[
+    "method",
+    "path",
+    "query",
+    "host",
+    "date",
+    "timestamp",
+    "body_hash",
+];
+
+/// HMAC request signature settings
+///
+/// ```yaml
+/// auth:
+///   type: hmac
+///   secret: "{{partner_secret}}"
+///   algorithm: sha256
+///   canonical: "{method}\n{path}\n{date}\n{body_hash}"
+///   header: Authorization
+///   value: "HMAC partner-1:{signature}"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HmacConfig {
+    pub secret: String,
+
+    #[serde(default)]
+    pub algorithm: HmacAlgorithm,
+
+    /// Template of the string that is signed
+    #[serde(default = "default_hmac_canonical")]
+    pub canonical: String,
+
+    /// Header the signature is sent in
+    #[serde(default = "default_hmac_header")]
+    pub header: String,
+
+    /// Template of the header value
+    #[serde(default = "default_hmac_value")]
+    pub value: String,
+
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+}
+
+fn default_hmac_canonical() -> String {
+    "{method}\n{path}\n{date}\n{body_hash}".to_string()
+}
+
+fn default_hmac_header() -> String {
+    "X-Signature".to_string()
+}
+
+fn default_hmac_value() -> String {
+    "{signature}".to_string()
+}
+
+/// Hash function of an HMAC signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+/// How a signature is written into a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+impl HmacConfig {
+    /// Creates a SHA-256 signature sent in `X-Signature`
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+            algorithm: HmacAlgorithm::default(),
+            canonical: default_hmac_canonical(),
+            header: default_hmac_header(),
+            value: default_hmac_value(),
+            encoding: SignatureEncoding::default(),
+        }
+    }
+
+    /// Sets the hash function
+    pub fn with_algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the template of the signed string
+    pub fn with_canonical(mut self, canonical: impl Into<String>) -> Self {
+        self.canonical = canonical.into();
+        self
+    }
+
+    /// Sets the header and the template of its value
+    pub fn with_header(mut self, header: impl Into<String>, value: impl Into<String>) -> Self {
+        self.header = header.into();
+        self.value = value.into();
+        self
+    }
+
+    /// Sets how the signature is encoded
+    pub fn with_encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Validates the secret, the header and the templates' placeholders
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.secret.is_empty() {
+            return Err(ModelError::EmptyField("auth.secret".to_string()));
+        }
+        if self.header.trim().is_empty() {
+            return Err(ModelError::EmptyField("auth.header".to_string()));
+        }
+        if !self.value.contains("{signature}") {
+            return Err(ModelError::ValidationError(
+                "HMAC header value must contain {signature}".to_string(),
+            ));
+        }
+
+        for (template, extra) in [(&self.canonical, None), (&self.value, Some("signature"))] {
+            for name in placeholders(template) {
+                if !HMAC_PLACEHOLDERS.contains(&name) && Some(name) != extra {
+                    return Err(ModelError::ValidationError(format!(
+                        "Unknown HMAC placeholder {{{}}}",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names of the `{name}` placeholders in an HMAC template
+///
+/// `{{name}}` variables are left to the template resolver and skipped.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        if let Some(variable) = rest.strip_prefix('{') {
+            rest = variable.split_once("}}").map_or("", |(_, rest)| rest);
+        } else if let Some((name, after)) = rest.split_once('}') {
+            names.push(name);
+            rest = after;
+        }
+    }
+    names
+}
+
+impl Auth {
+    /// Auth that sends no credentials (the default for collections)
+    pub fn none() -> Self {
+        Self::None
+    }
+
+    /// Creates HTTP Basic auth
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Creates bearer token auth
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer {
+            token: token.into(),
+        }
+    }
+
+    /// Creates API key auth
+    pub fn api_key(
+        key: impl Into<String>,
+        value: impl Into<String>,
+        location: ApiKeyLocation,
+    ) -> Self {
+        Self::ApiKey {
+            key: key.into(),
+            value: value.into(),
+            location,
+        }
+    }
+
+    /// Checks if no credentials are sent
+    pub fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+
+    /// Checks if the collection's auth is used
+    pub fn is_inherit(&self) -> bool {
+        matches!(self, Self::Inherit)
+    }
+
+    /// Returns the auth that applies, given the collection's auth
+    pub fn inherit_from<'a>(&'a self, parent: &'a Auth) -> &'a Auth {
+        match self {
+            Self::Inherit if !parent.is_inherit() => parent,
+            Self::Inherit => &Self::None,
+            auth => auth,
+        }
+    }
+
+    /// Returns the header carrying the credentials, if any
+    pub fn header(&self) -> Option<(String, String)> {
+        match self {
+            Self::Basic { username, password } => Some((
+                "Authorization".to_string(),
+                format!(
+                    "Basic {}",
+                    BASE64_STANDARD.encode(format!("{}:{}", username, password))
+                ),
+            )),
+            Self::Bearer { token } => {
+                Some(("Authorization".to_string(), format!("Bearer {}", token)))
+            }
+            Self::ApiKey {
+                key,
+                value,
+                location: ApiKeyLocation::Header,
+            } => Some((key.clone(), value.clone())),
+            _ => None,
+        }
+    }
+
+    /// Returns the query parameter carrying the credentials, if any
+    pub fn query_param(&self) -> Option<KeyValue> {
+        match self {
+            Self::ApiKey {
+                key,
+                value,
+                location: ApiKeyLocation::Query,
+            } => Some(KeyValue::new(key.clone(), value.clone())),
+            _ => None,
+        }
+    }
+
+    /// Validates the auth
+    pub fn validate(&self) -> ModelResult<()> {
+        match self {
+            Self::Basic { username, .. } if username.is_empty() => {
+                Err(ModelError::EmptyField("auth.username".to_string()))
+            }
+            Self::Basic { username, .. } if username.contains(':') => Err(
+                ModelError::ValidationError("Basic auth username cannot contain ':'".to_string()),
+            ),
+            Self::Bearer { token } if token.trim().is_empty() => {
+                Err(ModelError::EmptyField("auth.token".to_string()))
+            }
+            Self::ApiKey { key, .. } if key.trim().is_empty() => {
+                Err(ModelError::EmptyField("auth.key".to_string()))
+            }
+            Self::OAuth2(config) => config.validate(),
+            Self::AwsSigV4(config) => config.validate(),
+            Self::Digest { username, .. } if username.is_empty() => {
+                Err(ModelError::EmptyField("auth.username".to_string()))
+            }
+            Self::Hmac(config) => config.validate(),
+            Self::Jwt(config) => config.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_yaml() {
+        let auth: Auth =
+            serde_yaml::from_str("type: api_key\nkey: X-Api-Key\nvalue: k3y\n").unwrap();
+        assert_eq!(
+            auth,
+            Auth::api_key("X-Api-Key", "k3y", ApiKeyLocation::Header)
+        );
+
+        let yaml = serde_yaml::to_string(&Auth::basic("alice", "pw")).unwrap();
+        assert_eq!(yaml, "type: basic\nusername: alice\npassword: pw\n");
+        assert_eq!(
+            serde_yaml::to_string(&Auth::Inherit).unwrap(),
+            "type: inherit\n"
+        );
+    }
+
+    #[test]
+    fn test_auth_credentials() {
+        assert_eq!(
+            Auth::basic("Aladdin", "open sesame").header(),
+            Some((
+                "Authorization".to_string(),
+                "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".to_string()
+            ))
+        );
+        assert_eq!(Auth::bearer("t0ken").header().unwrap().1, "Bearer t0ken");
+
+        let query = Auth::api_key("api_key", "k3y", ApiKeyLocation::Query);
+        assert_eq!(query.header(), None);
+        assert_eq!(query.query_param(), Some(KeyValue::new("api_key", "k3y")));
+
+        let collection = Auth::bearer("t0ken");
+        assert_eq!(Auth::Inherit.inherit_from(&collection), &collection);
+        assert_eq!(Auth::None.inherit_from(&collection), &Auth::None);
+        assert_eq!(Auth::Inherit.inherit_from(&Auth::Inherit), &Auth::None);
+    }
+
+    #[test]
+    fn test_auth_validation() {
+        assert!(Auth::basic("alice", "").validate().is_ok());
+        assert!(Auth::basic("", "pw").validate().is_err());
+        assert!(Auth::basic("a:b", "pw").validate().is_err());
+        assert!(Auth::bearer(" ").validate().is_err());
+        assert!(Auth::api_key("", "k3y", ApiKeyLocation::Header)
+            .validate()
+            .is_err());
+        assert!(Auth::Inherit.validate().is_ok());
+
+        let token_url = "https://auth.example.com/token";
+        let client_credentials =
+            OAuth2Config::new(OAuth2Grant::ClientCredentials, token_url, "app");
+        assert!(Auth::OAuth2(client_credentials.clone()).validate().is_ok());
+        let mut no_client = client_credentials;
+        no_client.client_id.clear();
+        assert!(no_client.validate().is_err());
+        assert!(OAuth2Config::new(OAuth2Grant::Password, token_url, "app")
+            .validate()
+            .is_err());
+        assert!(
+            OAuth2Config::new(OAuth2Grant::AuthorizationCode, token_url, "app")
+                .with_authorization_url("https://auth.example.com/authorize")
+                .validate()
+                .is_ok()
+        );
+
+        let aws = AwsSigV4Config::new("AKID", "s3cret", "us-east-1", "s3");
+        assert!(Auth::AwsSigV4(aws.clone()).validate().is_ok());
+        let mut no_region = aws;
+        no_region.region = " ".to_string();
+        assert!(Auth::AwsSigV4(no_region).validate().is_err());
+    }
+
+    #[test]
+    fn test_aws_sigv4_yaml() {
+        let yaml = "type: aws_sigv4\naccess_key_id: AKID\nsecret_access_key: s3cret\nsession_token: t0ken\nregion: us-east-1\nservice: s3\n";
+        let auth: Auth = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            auth,
+            Auth::AwsSigV4(
+                AwsSigV4Config::new("AKID", "s3cret", "us-east-1", "s3")
+                    .with_session_token("t0ken")
+            )
+        );
+        assert_eq!(serde_yaml::to_string(&auth).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_hmac_yaml_and_validation() {
+        let auth: Auth = serde_yaml::from_str("type: hmac\nsecret: s3cret\n").unwrap();
+        assert_eq!(auth, Auth::Hmac(HmacConfig::new("s3cret")));
+        let Auth::Hmac(config) = &auth else {
+            panic!("Expected HMAC auth, got {:?}", auth);
+        };
+        assert_eq!(config.canonical, "{method}\n{path}\n{date}\n{body_hash}");
+        assert_eq!(config.header, "X-Signature");
+        assert!(auth.validate().is_ok());
+
+        let digest: Auth =
+            serde_yaml::from_str("type: digest\nusername: alice\npassword: pw\n").unwrap();
+        assert!(digest.validate().is_ok());
+        assert!(Auth::Digest {
+            username: String::new(),
+            password: "pw".to_string(),
+        }
+        .validate()
+        .is_err());
+
+        let config = HmacConfig::new("s3cret");
+        assert!(HmacConfig::new("").validate().is_err());
+        assert!(config
+            .clone()
+            .with_canonical("{method}:{{api_key}}:{body}")
+            .validate()
+            .is_err());
+        assert!(config
+            .clone()
+            .with_header("X-Signature", "sig")
+            .validate()
+            .is_err());
+        assert!(config
+            .with_header("Authorization", "HMAC {{key_id}}:{signature}")
+            .validate()
+            .is_ok());
+        assert_eq!(
+            placeholders("{method} {{name}} {path}{"),
+            vec!["method", "path"]
+        );
+    }
+
+    #[test]
+    fn test_jwt_yaml() {
+        let yaml = "type: jwt\nalgorithm: ES256\nkey: '{{jwt_key}}'\nclaims:\n  admin: true\n  sub: alice\nexpires_in: 60\n";
+        let auth: Auth = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            auth,
+            Auth::Jwt(
+                JwtConfig::new(JwtAlgorithm::Es256, "{{jwt_key}}")
+                    .with_claim("sub", "alice")
+                    .with_claim("admin", true)
+                    .with_expires_in(60)
+            )
+        );
+        assert_eq!(serde_yaml::to_string(&auth).unwrap(), yaml);
+        assert!(auth.validate().is_ok());
+        assert!(JwtConfig::new(JwtAlgorithm::Hs256, " ").validate().is_err());
+
+        let default: Auth = serde_yaml::from_str("type: jwt\nalgorithm: HS256\nkey: k\n").unwrap();
+        assert_eq!(default, Auth::Jwt(JwtConfig::new(JwtAlgorithm::Hs256, "k")));
+    }
+
+    #[test]
+    fn test_oauth2_yaml() {
+        let yaml = "type: oauth2\ngrant: password\ntoken_url: https://auth.example.com/token\nclient_id: app\nusername: alice\npassword: pw\n";
+        let auth: Auth = serde_yaml::from_str(yaml).unwrap();
+        let Auth::OAuth2(config) = &auth else {
+            panic!("Expected OAuth 2.0 auth, got {:?}", auth);
+        };
+        assert_eq!(config.grant, OAuth2Grant::Password);
+        assert_eq!(config.client_authentication, ClientAuthentication::Basic);
+        assert_eq!(config.username, "alice");
+        assert_eq!(
+            serde_yaml::to_string(&auth).unwrap(),
+            yaml.replace(
+                "client_id: app\n",
+                "client_id: app\nclient_authentication: basic\n"
+            )
+        );
+    }
+}
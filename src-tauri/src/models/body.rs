@@ -0,0 +1,405 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// A key/value pair that can be switched off without deleting it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: String,
+
+    #[serde(default)]
+    pub value: String,
+
+    /// Disabled entries are kept in the file but not sent
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+}
+
+impl KeyValue {
+    /// Creates a new enabled key/value pair
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            enabled: true,
+        }
+    }
+
+    /// Marks the pair as disabled
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+/// One part of a multipart body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MultipartPart {
+    /// A plain text field
+    Text {
+        name: String,
+
+        #[serde(default)]
+        value: String,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+
+        #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+        enabled: bool,
+    },
+
+    /// A file read from disk when the request is sent
+    File {
+        name: String,
+
+        /// Path of the file to upload
+        path: String,
+
+        /// File name sent to the server (defaults to the name in `path`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_name: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+
+        #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+        enabled: bool,
+    },
+}
+
+impl MultipartPart {
+    /// Creates an enabled text part
+    pub fn text(name: impl Into<String>, value: impl Into<String>) -> Self {
+        MultipartPart::Text {
+            name: name.into(),
+            value: value.into(),
+            content_type: None,
+            enabled: true,
+        }
+    }
+
+    /// Creates an enabled file part
+    pub fn file(name: impl Into<String>, path: impl Into<String>) -> Self {
+        MultipartPart::File {
+            name: name.into(),
+            path: path.into(),
+            file_name: None,
+            content_type: None,
+            enabled: true,
+        }
+    }
+
+    /// Returns the form field name of the part
+    pub fn name(&self) -> &str {
+        match self {
+            MultipartPart::Text { name, .. } | MultipartPart::File { name, .. } => name,
+        }
+    }
+
+    /// Checks if the part is sent
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            MultipartPart::Text { enabled, .. } | MultipartPart::File { enabled, .. } => *enabled,
+        }
+    }
+}
+
+/// Body of an HTTP request
+///
+/// Serialized with a `type` tag, e.g.:
+///
+/// ```yaml
+/// body:
+///   type: form_urlencoded
+///   fields:
+///     - key: username
+///       value: alice
+/// ```
+///
+/// A plain string (the format used by older request files) is read as a raw
+/// body without a content type.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestBody {
+    /// No body
+    #[default]
+    None,
+
+    /// Text sent as-is, with an optional content type
+    Raw {
+        content: String,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+    },
+
+    /// JSON text, sent as `application/json`
+    Json { content: String },
+
+    /// URL-encoded form fields, sent as `application/x-www-form-urlencoded`
+    FormUrlencoded {
+        #[serde(default)]
+        fields: Vec<KeyValue>,
+    },
+
+    /// Multipart form with text and file parts, sent as `multipart/form-data`
+    Multipart {
+        #[serde(default)]
+        parts: Vec<MultipartPart>,
+    },
+
+    /// Contents of a file sent as the whole body
+    Binary {
+        path: String,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+    },
+}
+
+impl RequestBody {
+    /// Creates a JSON body
+    pub fn json(content: impl Into<String>) -> Self {
+        RequestBody::Json {
+            content: content.into(),
+        }
+    }
+
+    /// Checks if there is no body
+    pub fn is_none(&self) -> bool {
+        matches!(self, RequestBody::None)
+    }
+
+    /// Returns the `Content-Type` sent when the request doesn't set one
+    ///
+    /// Binary bodies without an explicit type are guessed from the file
+    /// extension. Multipart bodies return `None` because the header carries a
+    /// boundary generated when the request is built.
+    pub fn content_type(&self) -> Option<&str> {
+        match self {
+            RequestBody::None | RequestBody::Multipart { .. } => None,
+            RequestBody::Raw { content_type, .. } => content_type.as_deref(),
+            RequestBody::Json { .. } => Some("application/json"),
+            RequestBody::FormUrlencoded { .. } => Some("application/x-www-form-urlencoded"),
+            RequestBody::Binary { path, content_type } => {
+                Some(content_type.as_deref().unwrap_or_else(|| guess_mime(path)))
+            }
+        }
+    }
+
+    /// Validates the body
+    pub fn validate(&self) -> ModelResult<()> {
+        match self {
+            RequestBody::FormUrlencoded { fields } => {
+                if fields.iter().any(|field| field.key.trim().is_empty()) {
+                    return Err(ModelError::EmptyField("body.fields.key".to_string()));
+                }
+            }
+            RequestBody::Multipart { parts } => {
+                for part in parts {
+                    if part.name().trim().is_empty() {
+                        return Err(ModelError::EmptyField("body.parts.name".to_string()));
+                    }
+                    if let MultipartPart::File { path, .. } = part {
+                        if path.trim().is_empty() {
+                            return Err(ModelError::EmptyField("body.parts.path".to_string()));
+                        }
+                    }
+                }
+            }
+            RequestBody::Binary { path, .. } => {
+                if path.trim().is_empty() {
+                    return Err(ModelError::EmptyField("body.path".to_string()));
+                }
+            }
+            RequestBody::None | RequestBody::Raw { .. } | RequestBody::Json { .. } => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl From<String> for RequestBody {
+    fn from(content: String) -> Self {
+        RequestBody::Raw {
+            content,
+            content_type: None,
+        }
+    }
+}
+
+impl From<&str> for RequestBody {
+    fn from(content: &str) -> Self {
+        content.to_string().into()
+    }
+}
+
+impl fmt::Display for RequestBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestBody::None => write!(f, "none"),
+            RequestBody::Raw { .. } => write!(f, "raw"),
+            RequestBody::Json { .. } => write!(f, "json"),
+            RequestBody::FormUrlencoded { .. } => write!(f, "form-urlencoded"),
+            RequestBody::Multipart { .. } => write!(f, "multipart"),
+            RequestBody::Binary { .. } => write!(f, "binary"),
+        }
+    }
+}
+
+/// Deserialize a request body, accepting the legacy plain string form
+pub(crate) fn deserialize_body<'de, D>(deserializer: D) -> Result<RequestBody, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct BodyVisitor;
+
+    impl<'de> Visitor<'de> for BodyVisitor {
+        type Value = RequestBody;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a request body with a `type` or a string")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<RequestBody, E> {
+            Ok(value.into())
+        }
+
+        fn visit_string<E: de::Error>(self, value: String) -> Result<RequestBody, E> {
+            Ok(value.into())
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<RequestBody, E> {
+            Ok(RequestBody::None)
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<RequestBody, E> {
+            Ok(RequestBody::None)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<RequestBody, A::Error> {
+            RequestBody::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(BodyVisitor)
+}
+
+/// Guess the MIME type of a file from its extension
+pub(crate) fn guess_mime(path: &str) -> &'static str {
+    mime_guess::from_path(path)
+        .first_raw()
+        .unwrap_or("application/octet-stream")
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_defaults() {
+        assert_eq!(RequestBody::None.content_type(), None);
+        assert_eq!(RequestBody::from("text").content_type(), None);
+        assert_eq!(
+            RequestBody::json("{}").content_type(),
+            Some("application/json")
+        );
+        assert_eq!(
+            RequestBody::FormUrlencoded { fields: vec![] }.content_type(),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            RequestBody::Multipart { parts: vec![] }.content_type(),
+            None
+        );
+
+        let binary = RequestBody::Binary {
+            path: "/tmp/image.png".to_string(),
+            content_type: None,
+        };
+        assert_eq!(binary.content_type(), Some("image/png"));
+
+        let binary = RequestBody::Binary {
+            path: "/tmp/data".to_string(),
+            content_type: Some("application/vnd.custom".to_string()),
+        };
+        assert_eq!(binary.content_type(), Some("application/vnd.custom"));
+
+        let binary = RequestBody::Binary {
+            path: "/tmp/data".to_string(),
+            content_type: None,
+        };
+        assert_eq!(binary.content_type(), Some("application/octet-stream"));
+    }
+
+    #[test]
+    fn test_yaml_forms() {
+        let body = RequestBody::Multipart {
+            parts: vec![
+                MultipartPart::text("title", "Holiday"),
+                MultipartPart::file("photo", "/tmp/beach.jpg"),
+            ],
+        };
+
+        let yaml = serde_yaml::to_string(&body).unwrap();
+        assert_eq!(
+            yaml,
+            "type: multipart\nparts:\n- type: text\n  name: title\n  value: Holiday\n- type: file\n  name: photo\n  path: /tmp/beach.jpg\n"
+        );
+
+        let loaded: RequestBody = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded, body);
+
+        let form: RequestBody = serde_yaml::from_str(
+            "type: form_urlencoded\nfields:\n  - key: q\n    value: rust\n  - key: page\n    value: '2'\n    enabled: false\n",
+        )
+        .unwrap();
+        assert_eq!(
+            form,
+            RequestBody::FormUrlencoded {
+                fields: vec![
+                    KeyValue::new("q", "rust"),
+                    KeyValue::new("page", "2").disabled()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(RequestBody::json("{").validate().is_ok());
+
+        let body = RequestBody::Binary {
+            path: " ".to_string(),
+            content_type: None,
+        };
+        assert_eq!(
+            body.validate(),
+            Err(ModelError::EmptyField("body.path".to_string()))
+        );
+
+        let body = RequestBody::Multipart {
+            parts: vec![MultipartPart::file("upload", "")],
+        };
+        assert_eq!(
+            body.validate(),
+            Err(ModelError::EmptyField("body.parts.path".to_string()))
+        );
+
+        let body = RequestBody::FormUrlencoded {
+            fields: vec![KeyValue::new("", "value")],
+        };
+        assert!(body.validate().is_err());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::models::Request;
+use crate::models::error::ModelResult;
+use crate::models::{Auth, ProxyConfig, Request, TlsSettings, Variable};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -19,6 +20,49 @@ pub struct Collection {
     /// Collection metadata (version, author, etc.)
     #[serde(default)]
     pub metadata: CollectionMetadata,
+
+    /// Settings shared by every request in the collection
+    #[serde(default, skip_serializing_if = "CollectionSettings::is_empty")]
+    pub settings: CollectionSettings,
+
+    /// Values for the `{{name}}` placeholders of the collection's requests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<Variable>,
+
+    /// Credentials for the requests that inherit their auth
+    #[serde(default = "Auth::none", skip_serializing_if = "Auth::is_none")]
+    pub auth: Auth,
+}
+
+/// Settings applied to every request in a collection
+///
+/// Unset fields fall back to the application settings; request settings
+/// take precedence over these.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CollectionSettings {
+    /// Proxy used by the collection's requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+
+    /// Extra CA certificates and client certificates, by host
+    #[serde(default, skip_serializing_if = "TlsSettings::is_empty")]
+    pub tls: TlsSettings,
+}
+
+impl CollectionSettings {
+    /// Checks if no setting is overridden
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Validates the settings
+    pub fn validate(&self) -> ModelResult<()> {
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
+        }
+
+        self.tls.validate()
+    }
 }
 
 /// Metadata associated with a collection
@@ -60,6 +104,9 @@ impl Collection {
             requests: Vec::new(),
             description: None,
             metadata: CollectionMetadata::default(),
+            settings: CollectionSettings::default(),
+            variables: Vec::new(),
+            auth: Auth::None,
         }
     }
 
@@ -75,6 +122,34 @@ impl Collection {
         self
     }
 
+    /// Sets the collection settings
+    pub fn with_settings(mut self, settings: CollectionSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Defines a variable, replacing any previous value
+    pub fn with_variable(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.variables.retain(|variable| variable.key != key);
+        self.variables.push(Variable::new(key, value));
+        self
+    }
+
+    /// Defines a variable whose value is a vault secret
+    pub fn with_secret(mut self, key: impl Into<String>, secret: impl Into<String>) -> Self {
+        let key = key.into();
+        self.variables.retain(|variable| variable.key != key);
+        self.variables.push(Variable::secret(key, secret));
+        self
+    }
+
+    /// Sets the auth inherited by the collection's requests
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
     /// Sets the collection author
     pub fn with_author(mut self, author: impl Into<String>) -> Self {
         self.metadata.author = Some(author.into());
@@ -191,4 +266,39 @@ mod tests {
             "Collection 'My API' (1 request(s)): API endpoints for testing"
         );
     }
+
+    #[test]
+    fn test_collection_settings_yaml() {
+        let collection = Collection::new("My API");
+        let yaml = serde_yaml::to_string(&collection).unwrap();
+        assert!(!yaml.contains("settings"));
+
+        let collection = collection.with_settings(CollectionSettings {
+            proxy: Some(ProxyConfig::None),
+            ..Default::default()
+        });
+        let yaml = serde_yaml::to_string(&collection).unwrap();
+        assert!(yaml.contains("settings:\n  proxy:\n    mode: none\n"));
+
+        let loaded: Collection = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.settings.proxy, Some(ProxyConfig::None));
+        assert!(loaded.settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_collection_variables_yaml() {
+        let collection = Collection::new("My API")
+            .with_variable("base_url", "https://api.example.com")
+            .with_variable("token", "old")
+            .with_variable("token", "new");
+        assert_eq!(collection.variables.len(), 2);
+
+        let yaml = serde_yaml::to_string(&collection).unwrap();
+        assert!(yaml.contains(
+            "variables:\n- key: base_url\n  value: https://api.example.com\n- key: token\n  value: new\n"
+        ));
+
+        let loaded: Collection = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.variables, collection.variables);
+    }
 }
@@ -0,0 +1,178 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::{Deserialize, Serialize};
+
+/// A cookie stored in a collection's cookie jar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+
+    pub value: String,
+
+    /// Domain the cookie is sent to, without a leading dot
+    pub domain: String,
+
+    /// Whether only `domain` itself matches, not its subdomains (the server
+    /// set no `Domain` attribute)
+    #[serde(default)]
+    pub host_only: bool,
+
+    /// Path prefix the cookie is sent to
+    pub path: String,
+
+    /// Expiry time (RFC 3339); session cookies have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+
+    /// Only sent over HTTPS
+    #[serde(default)]
+    pub secure: bool,
+
+    /// Hidden from scripts in a browser
+    #[serde(default)]
+    pub http_only: bool,
+
+    /// `SameSite` attribute (`Strict`, `Lax` or `None`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+}
+
+/// The contents of a collection's cookie jar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CookieList {
+    /// Whether requests send and store cookies
+    pub enabled: bool,
+
+    /// Unexpired cookies, ordered by domain, path and name
+    pub cookies: Vec<Cookie>,
+}
+
+/// Values accepted for the `SameSite` attribute
+const SAME_SITE_VALUES: &[&str] = &["Strict", "Lax", "None"];
+
+impl Cookie {
+    /// Creates a session cookie for a domain and all its paths
+    pub fn new(
+        name: impl Into<String>,
+        value: impl Into<String>,
+        domain: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: domain.into(),
+            host_only: false,
+            path: "/".to_string(),
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Sets the path prefix
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets the expiry time (RFC 3339)
+    pub fn with_expires(mut self, expires: impl Into<String>) -> Self {
+        self.expires = Some(expires.into());
+        self
+    }
+
+    /// Validates the cookie
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(ModelError::EmptyField("cookie.name".to_string()));
+        }
+        if self.domain.trim().is_empty() {
+            return Err(ModelError::EmptyField("cookie.domain".to_string()));
+        }
+
+        let is_separator = |c: char| c == ';' || c.is_control();
+        if self
+            .name
+            .contains(|c: char| is_separator(c) || c == '=' || c.is_whitespace())
+        {
+            return Err(ModelError::ValidationError(format!(
+                "Invalid cookie name: {}",
+                self.name
+            )));
+        }
+        if self.value.contains(is_separator) {
+            return Err(ModelError::ValidationError(format!(
+                "Invalid value for cookie '{}'",
+                self.name
+            )));
+        }
+
+        if !self.path.starts_with('/') {
+            return Err(ModelError::ValidationError(format!(
+                "Cookie path must start with '/': {}",
+                self.path
+            )));
+        }
+
+        if let Some(expires) = &self.expires {
+            chrono::DateTime::parse_from_rfc3339(expires).map_err(|e| {
+                ModelError::ValidationError(format!("Invalid cookie expiry '{}': {}", expires, e))
+            })?;
+        }
+
+        if let Some(same_site) = &self.same_site {
+            if !SAME_SITE_VALUES.contains(&same_site.as_str()) {
+                return Err(ModelError::ValidationError(format!(
+                    "SameSite must be Strict, Lax or None: {}",
+                    same_site
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookie_validation() {
+        let cookie = Cookie::new("session", "abc123", "example.com")
+            .with_path("/api")
+            .with_expires("2030-01-01T00:00:00Z");
+        assert!(cookie.validate().is_ok());
+
+        let invalid = Cookie::new("", "x", "example.com");
+        assert!(matches!(invalid.validate(), Err(ModelError::EmptyField(_))));
+
+        for invalid in [
+            Cookie::new("a b", "x", "example.com"),
+            Cookie::new("a", "x;y", "example.com"),
+            Cookie::new("a", "x", "example.com").with_path("api"),
+            Cookie::new("a", "x", "example.com").with_expires("tomorrow"),
+        ] {
+            assert!(matches!(
+                invalid.validate(),
+                Err(ModelError::ValidationError(_))
+            ));
+        }
+
+        let mut cookie = Cookie::new("a", "x", "example.com");
+        cookie.same_site = Some("Sometimes".to_string());
+        assert!(cookie.validate().is_err());
+    }
+
+    #[test]
+    fn test_cookie_serialization_defaults() {
+        let cookie: Cookie =
+            serde_json::from_str(r#"{"name":"a","value":"1","domain":"example.com","path":"/"}"#)
+                .unwrap();
+        assert_eq!(cookie, Cookie::new("a", "1", "example.com"));
+        assert_eq!(
+            serde_json::to_string(&cookie).unwrap(),
+            r#"{"name":"a","value":"1","domain":"example.com","host_only":false,"path":"/","secure":false,"http_only":false}"#
+        );
+    }
+}
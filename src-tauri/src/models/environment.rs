@@ -0,0 +1,118 @@
+use crate::models::error::{ModelError, ModelResult};
+use crate::models::variable::Variable;
+use serde::{Deserialize, Serialize};
+
+/// A named set of variables, such as `local`, `staging` or `prod`
+///
+/// Stored in `*.env.yaml` files next to the collections. The variables of the
+/// active environment take precedence over collection variables.
+///
+/// ```yaml
+/// name: Staging
+/// variables:
+///   - key: base_url
+///     value: https://staging.example.com
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Environment {
+    /// Environment name
+    pub name: String,
+
+    /// Values for `{{name}}` placeholders
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<Variable>,
+}
+
+/// An environment file found in the collections directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironmentFile {
+    /// Path of the `.env.yaml` file
+    pub path: String,
+
+    /// Whether this is the active environment
+    pub active: bool,
+
+    pub environment: Environment,
+}
+
+impl Environment {
+    /// Creates an environment without variables
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            variables: Vec::new(),
+        }
+    }
+
+    /// Defines a variable, replacing any previous value
+    pub fn with_variable(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.variables.retain(|variable| variable.key != key);
+        self.variables.push(Variable::new(key, value));
+        self
+    }
+
+    /// Defines a variable whose value is a vault secret
+    pub fn with_secret(mut self, key: impl Into<String>, secret: impl Into<String>) -> Self {
+        let key = key.into();
+        self.variables.retain(|variable| variable.key != key);
+        self.variables.push(Variable::secret(key, secret));
+        self
+    }
+
+    /// Validates the environment
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(ModelError::EmptyField("name".to_string()));
+        }
+
+        if self
+            .variables
+            .iter()
+            .any(|variable| variable.key.trim().is_empty())
+        {
+            return Err(ModelError::EmptyField("variables.key".to_string()));
+        }
+
+        if self.variables.iter().any(|variable| {
+            variable
+                .secret
+                .as_deref()
+                .is_some_and(|s| s.trim().is_empty())
+        }) {
+            return Err(ModelError::EmptyField("variables.secret".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_yaml_and_validation() {
+        let environment = Environment::new("Staging")
+            .with_variable("base_url", "https://staging.example.com")
+            .with_variable("base_url", "https://staging.example.org");
+        assert!(environment.validate().is_ok());
+
+        let yaml = serde_yaml::to_string(&environment).unwrap();
+        assert_eq!(
+            yaml,
+            "name: Staging\nvariables:\n- key: base_url\n  value: https://staging.example.org\n"
+        );
+        let loaded: Environment = serde_yaml::from_str("name: Local\n").unwrap();
+        assert!(loaded.variables.is_empty());
+
+        assert!(matches!(
+            Environment::new(" ").validate(),
+            Err(ModelError::EmptyField(_))
+        ));
+        assert!(matches!(
+            Environment::new("Local").with_variable("", "x").validate(),
+            Err(ModelError::EmptyField(_))
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::{Deserialize, Serialize};
+
+/// A rule that stores a value of the response in a runtime variable
+///
+/// Declared on a request; the source is stored with a `type` tag, e.g.:
+///
+/// ```yaml
+/// extractions:
+///   - variable: token
+///     type: json_path
+///     path: $.access_token
+///   - variable: session
+///     type: cookie
+///     name: SESSION
+/// ```
+///
+/// Later requests of the same collection (or, outside collections, of the
+/// session) use the value as `{{token}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extraction {
+    /// Name of the runtime variable to set
+    pub variable: String,
+
+    /// Where the value is taken from
+    #[serde(flatten)]
+    pub source: ExtractionSource,
+}
+
+/// Part of a response a value is extracted from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtractionSource {
+    /// The value at a JSON path of the body; strings are taken as they are,
+    /// other values as JSON text
+    JsonPath { path: String },
+
+    /// The first value of a header
+    Header { name: String },
+
+    /// A capture group of a pattern matched against the body
+    Regex {
+        pattern: String,
+
+        /// Capture group to take; the first one by default, or the whole
+        /// match if the pattern has none
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<usize>,
+    },
+
+    /// A cookie set by the response
+    Cookie { name: String },
+}
+
+impl Extraction {
+    /// Creates a rule extracting a value into a variable
+    pub fn new(variable: impl Into<String>, source: ExtractionSource) -> Self {
+        Self {
+            variable: variable.into(),
+            source,
+        }
+    }
+
+    /// Validates the variable name, patterns and JSON paths
+    pub fn validate(&self) -> ModelResult<()> {
+        let variable = self.variable.trim();
+        if variable.is_empty() {
+            return Err(ModelError::EmptyField("extraction.variable".to_string()));
+        }
+        // Dynamic variables and placeholders cannot be assigned
+        if variable.starts_with('$') || variable.contains(['{', '}']) {
+            return Err(ModelError::ValidationError(format!(
+                "Invalid variable name '{}'",
+                variable
+            )));
+        }
+
+        match &self.source {
+            ExtractionSource::JsonPath { path } => serde_json_path::JsonPath::parse(path)
+                .map(|_| ())
+                .map_err(|e| {
+                    ModelError::ValidationError(format!("Invalid JSON path '{}': {}", path, e))
+                }),
+            ExtractionSource::Header { name } | ExtractionSource::Cookie { name }
+                if name.trim().is_empty() =>
+            {
+                Err(ModelError::EmptyField("extraction.name".to_string()))
+            }
+            ExtractionSource::Regex { pattern, group } => {
+                let regex = regex::Regex::new(pattern).map_err(|e| {
+                    ModelError::ValidationError(format!("Invalid pattern '{}': {}", pattern, e))
+                })?;
+                match group {
+                    Some(group) if *group >= regex.captures_len() => {
+                        Err(ModelError::ValidationError(format!(
+                            "Pattern '{}' has no capture group {}",
+                            pattern, group
+                        )))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Outcome of an extraction rule against a response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionResult {
+    pub variable: String,
+
+    /// The extracted value; absent if the response has none, in which case
+    /// the variable keeps its previous value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+
+    /// Why the value could not be extracted, e.g. a body that is not JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extraction_yaml() {
+        let yaml = "- variable: token\n  type: json_path\n  path: $.access_token\n- variable: order_id\n  type: regex\n  pattern: order-(\\d+)\n- variable: session\n  type: cookie\n  name: SESSION\n";
+        let extractions: Vec<Extraction> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            extractions,
+            vec![
+                Extraction::new(
+                    "token",
+                    ExtractionSource::JsonPath {
+                        path: "$.access_token".to_string()
+                    }
+                ),
+                Extraction::new(
+                    "order_id",
+                    ExtractionSource::Regex {
+                        pattern: r"order-(\d+)".to_string(),
+                        group: None,
+                    }
+                ),
+                Extraction::new(
+                    "session",
+                    ExtractionSource::Cookie {
+                        name: "SESSION".to_string()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(serde_yaml::to_string(&extractions).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_extraction_validation() {
+        let header = |variable: &str, name: &str| {
+            Extraction::new(
+                variable,
+                ExtractionSource::Header {
+                    name: name.to_string(),
+                },
+            )
+        };
+        assert!(header("request_id", "X-Request-Id").validate().is_ok());
+        assert!(header(" ", "X-Request-Id").validate().is_err());
+        assert!(header("$uuid", "X-Request-Id").validate().is_err());
+        assert!(header("{{id}}", "X-Request-Id").validate().is_err());
+        assert!(header("request_id", "").validate().is_err());
+
+        let regex = |pattern: &str, group| {
+            Extraction::new(
+                "id",
+                ExtractionSource::Regex {
+                    pattern: pattern.to_string(),
+                    group,
+                },
+            )
+        };
+        assert!(regex(r"id=(\d+)", Some(1)).validate().is_ok());
+        assert!(regex(r"id=(\d+)", Some(2)).validate().is_err());
+        assert!(regex("(unclosed", None).validate().is_err());
+
+        let json_path = |path: &str| {
+            Extraction::new(
+                "id",
+                ExtractionSource::JsonPath {
+                    path: path.to_string(),
+                },
+            )
+        };
+        assert!(json_path("$.id").validate().is_ok());
+        assert!(json_path("id").validate().is_err());
+    }
+}
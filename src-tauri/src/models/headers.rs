@@ -0,0 +1,268 @@
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// A single HTTP header entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    /// Header name as written by the user or sent by the server
+    pub key: String,
+
+    /// Header value
+    pub value: String,
+}
+
+impl Header {
+    /// Creates a new header entry
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Ordered multi-map of HTTP headers
+///
+/// Entries keep their insertion order and repeated names (e.g. several
+/// `Set-Cookie` headers) are kept as separate entries. Name lookups are
+/// case-insensitive, as header names are in HTTP.
+///
+/// Serializes as a list of `{ key, value }` entries. A plain `name: value`
+/// mapping (the format used by older request files) is still accepted when
+/// deserializing.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[serde(transparent)]
+pub struct Headers(Vec<Header>);
+
+impl Headers {
+    /// Creates an empty header list
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Appends a header, keeping any existing entries with the same name
+    pub fn append(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.push(Header::new(key, value));
+    }
+
+    /// Sets a header, replacing all existing entries with the same name
+    ///
+    /// The new value takes the position of the first replaced entry, or is
+    /// appended if the header was not present.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+
+        match self.position(&key) {
+            Some(index) => {
+                self.0[index] = Header::new(key.clone(), value);
+                let mut seen = false;
+                self.0.retain(|header| {
+                    if !header.key.eq_ignore_ascii_case(&key) {
+                        return true;
+                    }
+                    // Keep only the entry we just replaced
+                    let keep = !seen;
+                    seen = true;
+                    keep
+                });
+            }
+            None => self.append(key, value),
+        }
+    }
+
+    /// Returns the first value for a header name
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.position(key).map(|index| self.0[index].value.as_str())
+    }
+
+    /// Returns every value for a header name, in order
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|header| header.key.eq_ignore_ascii_case(key))
+            .map(|header| header.value.as_str())
+            .collect()
+    }
+
+    /// Checks if a header with this name is present
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Removes all entries with this name, returning how many were removed
+    pub fn remove(&mut self, key: &str) -> usize {
+        let before = self.0.len();
+        self.0
+            .retain(|header| !header.key.eq_ignore_ascii_case(key));
+        before - self.0.len()
+    }
+
+    /// Returns the number of entries (repeated names count separately)
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the entries in order
+    pub fn iter(&self) -> std::slice::Iter<'_, Header> {
+        self.0.iter()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|header| header.key.eq_ignore_ascii_case(key))
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = &'a Header;
+    type IntoIter = std::slice::Iter<'a, Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = Header;
+    type IntoIter = std::vec::IntoIter<Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| Header::new(key, value))
+                .collect(),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct HeadersVisitor;
+
+        impl<'de> Visitor<'de> for HeadersVisitor {
+            type Value = Headers;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of {key, value} headers or a map of header names to values")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Headers, A::Error> {
+                let mut headers = Headers::new();
+                while let Some(header) = seq.next_element::<Header>()? {
+                    headers.0.push(header);
+                }
+                Ok(headers)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Headers, A::Error> {
+                let mut headers = Headers::new();
+                while let Some((key, value)) = map.next_entry::<String, String>()? {
+                    headers.append(key, value);
+                }
+                Ok(headers)
+            }
+
+            fn visit_unit<E>(self) -> Result<Headers, E> {
+                Ok(Headers::new())
+            }
+        }
+
+        deserializer.deserialize_any(HeadersVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_keeps_duplicates_in_order() {
+        let mut headers = Headers::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Content-Type", "text/html");
+        headers.append("set-cookie", "b=2");
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("SET-COOKIE"), Some("a=1"));
+        assert_eq!(headers.get_all("Set-Cookie"), vec!["a=1", "b=2"]);
+
+        let keys: Vec<&str> = headers.iter().map(|h| h.key.as_str()).collect();
+        assert_eq!(keys, vec!["Set-Cookie", "Content-Type", "set-cookie"]);
+    }
+
+    #[test]
+    fn test_insert_replaces_in_place() {
+        let mut headers = Headers::new();
+        headers.append("Accept", "text/html");
+        headers.append("Vary", "Origin");
+        headers.append("accept", "application/xml");
+
+        headers.insert("Accept", "application/json");
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get_all("accept"), vec!["application/json"]);
+        assert_eq!(headers.iter().next().unwrap().key, "Accept");
+
+        headers.insert("X-New", "1");
+        assert_eq!(headers.len(), 3);
+    }
+
+    #[test]
+    fn test_remove_and_contains() {
+        let mut headers: Headers = vec![("Link", "<a>"), ("Link", "<b>"), ("Vary", "Accept")]
+            .into_iter()
+            .collect();
+
+        assert!(headers.contains_key("link"));
+        assert_eq!(headers.remove("LINK"), 2);
+        assert!(!headers.contains_key("Link"));
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.remove("Missing"), 0);
+    }
+
+    #[test]
+    fn test_serializes_as_ordered_list() {
+        let headers: Headers = vec![("Vary", "Origin"), ("Vary", "Accept")]
+            .into_iter()
+            .collect();
+
+        let yaml = serde_yaml::to_string(&headers).unwrap();
+        assert_eq!(
+            yaml,
+            "- key: Vary\n  value: Origin\n- key: Vary\n  value: Accept\n"
+        );
+
+        let loaded: Headers = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded, headers);
+    }
+
+    #[test]
+    fn test_deserializes_legacy_map() {
+        let yaml = "Content-Type: application/json\nAuthorization: Bearer abc\n";
+        let headers: Headers = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get("content-type"), Some("application/json"));
+        assert_eq!(headers.iter().next().unwrap().key, "Content-Type");
+
+        let headers: Headers = serde_json::from_str("{}").unwrap();
+        assert!(headers.is_empty());
+    }
+}
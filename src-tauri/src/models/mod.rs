@@ -1,9 +1,32 @@
+pub mod assertion;
+pub mod auth;
+pub mod body;
 pub mod collection;
+pub mod cookie;
+pub mod environment;
 pub mod error;
+pub mod extraction;
+pub mod headers;
+pub mod params;
+pub mod proxy;
 pub mod request;
 pub mod response;
+pub mod settings;
+pub mod tls;
+pub mod variable;
 
+pub use assertion::*;
+pub use auth::*;
+pub use body::*;
 pub use collection::*;
+pub use cookie::*;
+pub use environment::*;
 pub use error::*;
+pub use extraction::*;
+pub use headers::*;
+pub use proxy::*;
 pub use request::*;
 pub use response::*;
+pub use settings::*;
+pub use tls::*;
+pub use variable::*;
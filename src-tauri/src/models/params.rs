@@ -0,0 +1,228 @@
+use crate::models::body::KeyValue;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters encoded in query keys and values
+///
+/// Based on the WHATWG query set, plus the characters that delimit pairs.
+const QUERY_COMPONENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>');
+
+/// Characters encoded in path parameter values
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`');
+
+/// Split a URL into the part before the query and the query string
+///
+/// The fragment, if any, stays with the first part.
+pub fn split_query(url: &str) -> (String, Option<&str>) {
+    let (without_fragment, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+
+    match without_fragment.split_once('?') {
+        Some((base, query)) => (format!("{}{}", base, fragment), Some(query)),
+        None => (url.to_string(), None),
+    }
+}
+
+/// Parse a query string into decoded, enabled key/value pairs
+///
+/// `+` is read as a space, as browsers and most servers do.
+pub fn parse_query(query: &str) -> Vec<KeyValue> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            KeyValue::new(decode_component(key), decode_component(value))
+        })
+        .collect()
+}
+
+/// Encode the enabled pairs as a query string (without the leading `?`)
+///
+/// Pairs with an empty value are written as a bare key.
+pub fn encode_query(params: &[KeyValue]) -> String {
+    params
+        .iter()
+        .filter(|param| param.enabled)
+        .map(|param| {
+            let key = utf8_percent_encode(&param.key, QUERY_COMPONENT);
+            if param.value.is_empty() {
+                key.to_string()
+            } else {
+                format!(
+                    "{}={}",
+                    key,
+                    utf8_percent_encode(&param.value, QUERY_COMPONENT)
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Append a query string to a URL, before any fragment
+pub fn append_query(url: &str, query: &str) -> String {
+    if query.is_empty() {
+        return url.to_string();
+    }
+
+    let (without_fragment, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    let separator = if without_fragment.contains('?') {
+        "&"
+    } else {
+        "?"
+    };
+
+    format!("{}{}{}{}", without_fragment, separator, query, fragment)
+}
+
+/// Get the names of `:name` path parameters, in the order they appear
+pub fn path_param_names(url: &str) -> Vec<String> {
+    let (_, path) = split_path(url);
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix(':'))
+        .filter(|name| is_param_name(name))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Replace `:name` path segments with their percent-encoded values
+///
+/// Disabled parameters and segments without a matching parameter are left
+/// untouched.
+pub fn substitute_path_params(url: &str, params: &[KeyValue]) -> String {
+    let (origin, path) = split_path(url);
+    if path.is_empty() {
+        return url.to_string();
+    }
+
+    let (path, rest) = match path.find(['?', '#']) {
+        Some(index) => path.split_at(index),
+        None => (path, ""),
+    };
+
+    let path = path
+        .split('/')
+        .map(|segment| {
+            segment
+                .strip_prefix(':')
+                .and_then(|name| {
+                    params
+                        .iter()
+                        .find(|param| param.enabled && param.key == name)
+                })
+                .map(|param| utf8_percent_encode(&param.value, PATH_SEGMENT).to_string())
+                .unwrap_or_else(|| segment.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    format!("{}{}{}", origin, path, rest)
+}
+
+/// Split a URL into scheme and authority, and everything from the path on
+fn split_path(url: &str) -> (&str, &str) {
+    let authority_start = url.find("://").map(|index| index + 3).unwrap_or(0);
+    match url[authority_start..].find(['/', '?', '#']) {
+        Some(index) => url.split_at(authority_start + index),
+        None => (url, ""),
+    }
+}
+
+fn is_param_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn decode_component(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_round_trip() {
+        let (base, query) =
+            split_query("https://example.com/search?q=rust%20lang&tag=a+b&flag#top");
+        assert_eq!(base, "https://example.com/search#top");
+
+        let params = parse_query(query.unwrap());
+        assert_eq!(
+            params,
+            vec![
+                KeyValue::new("q", "rust lang"),
+                KeyValue::new("tag", "a b"),
+                KeyValue::new("flag", ""),
+            ]
+        );
+
+        let query = encode_query(&params);
+        assert_eq!(query, "q=rust%20lang&tag=a%20b&flag");
+        assert_eq!(
+            append_query(&base, &query),
+            "https://example.com/search?q=rust%20lang&tag=a%20b&flag#top"
+        );
+    }
+
+    #[test]
+    fn test_encode_query_escapes_delimiters_and_skips_disabled() {
+        let params = vec![
+            KeyValue::new("expr", "a&b=c+d"),
+            KeyValue::new("debug", "1").disabled(),
+            KeyValue::new("name", "José"),
+        ];
+
+        assert_eq!(encode_query(&params), "expr=a%26b%3Dc%2Bd&name=Jos%C3%A9");
+        assert_eq!(
+            append_query("https://example.com/?v=1", "x=2"),
+            "https://example.com/?v=1&x=2"
+        );
+    }
+
+    #[test]
+    fn test_path_params() {
+        let url = "https://example.com:8080/users/:userId/posts/:post_id?expand=1";
+        assert_eq!(path_param_names(url), vec!["userId", "post_id"]);
+
+        let params = vec![
+            KeyValue::new("userId", "a b/c"),
+            KeyValue::new("post_id", "7"),
+        ];
+        assert_eq!(
+            substitute_path_params(url, &params),
+            "https://example.com:8080/users/a%20b%2Fc/posts/7?expand=1"
+        );
+
+        // The port is not mistaken for a parameter
+        assert!(path_param_names("http://localhost:3000").is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How requests reach the network
+///
+/// Serialized with a `mode` tag, e.g.:
+///
+/// ```yaml
+/// proxy:
+///   mode: manual
+///   url: socks5h://proxy.corp.example:1080
+///   username: alice
+///   bypass: [localhost, .internal.example]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ProxyConfig {
+    /// Connect directly, ignoring proxy environment variables
+    None,
+
+    /// Use the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`
+    /// environment variables (upper or lower case)
+    #[default]
+    System,
+
+    /// Send every request through the given proxy
+    Manual(ManualProxy),
+}
+
+/// An explicitly configured proxy
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManualProxy {
+    /// Proxy URL with an `http`, `https`, `socks5` or `socks5h` scheme
+    ///
+    /// With `socks5h` host names are resolved by the proxy.
+    pub url: String,
+
+    /// Username for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Password for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Hosts that bypass the proxy, using `NO_PROXY` syntax (`example.com`,
+    /// `.example.com`, `192.168.0.0/16`, `*`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bypass: Vec<String>,
+}
+
+/// Proxy URL schemes supported by the HTTP client
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+impl ManualProxy {
+    /// Creates a proxy without credentials or bypass rules
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            bypass: Vec::new(),
+        }
+    }
+
+    /// Sets the proxy credentials
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Adds a host pattern that bypasses the proxy
+    pub fn with_bypass(mut self, pattern: impl Into<String>) -> Self {
+        self.bypass.push(pattern.into());
+        self
+    }
+}
+
+impl ProxyConfig {
+    /// Validates the proxy configuration
+    pub fn validate(&self) -> ModelResult<()> {
+        let ProxyConfig::Manual(proxy) = self else {
+            return Ok(());
+        };
+
+        if proxy.url.trim().is_empty() {
+            return Err(ModelError::EmptyField("proxy.url".to_string()));
+        }
+
+        let url = url::Url::parse(&proxy.url)
+            .map_err(|e| ModelError::InvalidUrl(format!("{}: {}", e, proxy.url)))?;
+        if !PROXY_SCHEMES.contains(&url.scheme()) {
+            return Err(ModelError::InvalidUrl(format!(
+                "Proxy URL must use http, https, socks5 or socks5h: {}",
+                proxy.url
+            )));
+        }
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(ModelError::InvalidUrl(format!(
+                "Proxy URL must contain a host: {}",
+                proxy.url
+            )));
+        }
+
+        if proxy.password.is_some() && proxy.username.is_none() {
+            return Err(ModelError::ValidationError(
+                "Proxy password requires a username".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyConfig::None => write!(f, "no proxy"),
+            ProxyConfig::System => write!(f, "system proxy"),
+            // Never show credentials
+            ProxyConfig::Manual(proxy) => match url::Url::parse(&proxy.url) {
+                Ok(url) => write!(
+                    f,
+                    "proxy {}://{}",
+                    url.scheme(),
+                    &url[url::Position::BeforeHost..url::Position::AfterPort]
+                ),
+                Err(_) => write!(f, "proxy (invalid URL)"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_yaml_forms() {
+        let proxy = ProxyConfig::Manual(
+            ManualProxy::new("socks5h://proxy.corp:1080")
+                .with_credentials("alice", "s3cret")
+                .with_bypass("localhost")
+                .with_bypass(".internal.corp"),
+        );
+
+        let yaml = serde_yaml::to_string(&proxy).unwrap();
+        assert_eq!(
+            yaml,
+            "mode: manual\nurl: socks5h://proxy.corp:1080\nusername: alice\npassword: s3cret\nbypass:\n- localhost\n- .internal.corp\n"
+        );
+        assert_eq!(serde_yaml::from_str::<ProxyConfig>(&yaml).unwrap(), proxy);
+
+        let system: ProxyConfig = serde_yaml::from_str("mode: system").unwrap();
+        assert_eq!(system, ProxyConfig::System);
+        assert_eq!(ProxyConfig::default(), ProxyConfig::System);
+    }
+
+    #[test]
+    fn test_proxy_validation() {
+        assert!(ProxyConfig::None.validate().is_ok());
+        assert!(ProxyConfig::Manual(ManualProxy::new("http://proxy:3128"))
+            .validate()
+            .is_ok());
+
+        assert!(matches!(
+            ProxyConfig::Manual(ManualProxy::new("ftp://proxy:21")).validate(),
+            Err(ModelError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            ProxyConfig::Manual(ManualProxy::new("")).validate(),
+            Err(ModelError::EmptyField(_))
+        ));
+
+        let mut proxy = ManualProxy::new("http://proxy:3128");
+        proxy.password = Some("orphan".to_string());
+        assert!(matches!(
+            ProxyConfig::Manual(proxy).validate(),
+            Err(ModelError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_proxy_display_hides_credentials() {
+        let proxy = ProxyConfig::Manual(ManualProxy::new("http://bob:pw@proxy.corp:3128/"));
+        assert_eq!(proxy.to_string(), "proxy http://proxy.corp:3128");
+    }
+}
@@ -1,11 +1,20 @@
+use crate::models::assertion::Assertion;
+use crate::models::auth::Auth;
+use crate::models::body::{deserialize_body, KeyValue, RequestBody};
 use crate::models::error::{ModelError, ModelResult};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::models::extraction::Extraction;
+use crate::models::headers::Headers;
+use crate::models::params;
+use crate::models::proxy::ProxyConfig;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 /// HTTP methods supported by Arcanine
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
+///
+/// Serialized as the method string. Any other method (e.g. `PROPFIND`,
+/// `PURGE`) is kept as `Custom` and sent verbatim; method names are
+/// case-sensitive, so `get` is a custom method distinct from `GET`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum HttpMethod {
     #[default]
     Get,
@@ -15,19 +24,129 @@ pub enum HttpMethod {
     Delete,
     Head,
     Options,
+    /// Any other method, which must be a valid RFC 7230 token
+    Custom(String),
+}
+
+impl HttpMethod {
+    /// Returns the method as sent on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Custom(method) => method,
+        }
+    }
+
+    /// Validates that a custom method is a valid token
+    ///
+    /// `token = 1*tchar`, where `tchar` is an ASCII letter, digit or one of
+    /// ``!#$%&'*+-.^_`|~`` (RFC 7230, section 3.2.6).
+    pub fn validate(&self) -> ModelResult<()> {
+        let HttpMethod::Custom(method) = self else {
+            return Ok(());
+        };
+
+        let is_tchar = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+        if method.is_empty() || !method.chars().all(is_tchar) {
+            return Err(ModelError::InvalidMethod(method.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&str> for HttpMethod {
+    fn from(method: &str) -> Self {
+        match method {
+            "GET" => HttpMethod::Get,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "PATCH" => HttpMethod::Patch,
+            "DELETE" => HttpMethod::Delete,
+            "HEAD" => HttpMethod::Head,
+            "OPTIONS" => HttpMethod::Options,
+            other => HttpMethod::Custom(other.to_string()),
+        }
+    }
 }
 
 impl fmt::Display for HttpMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            HttpMethod::Get => write!(f, "GET"),
-            HttpMethod::Post => write!(f, "POST"),
-            HttpMethod::Put => write!(f, "PUT"),
-            HttpMethod::Patch => write!(f, "PATCH"),
-            HttpMethod::Delete => write!(f, "DELETE"),
-            HttpMethod::Head => write!(f, "HEAD"),
-            HttpMethod::Options => write!(f, "OPTIONS"),
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for HttpMethod {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for HttpMethod {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        Ok(HttpMethod::from(method.as_str()))
+    }
+}
+
+/// Per-request connection settings
+///
+/// Every field is optional; unset fields fall back to the collection or
+/// application defaults (30 second timeout, follow up to 10 redirects, verify
+/// TLS, system proxy).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RequestSettings {
+    /// Time allowed to establish a connection, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_ms: Option<u64>,
+
+    /// Total time allowed for the request, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+
+    /// Whether redirects are followed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+
+    /// Maximum number of redirects to follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<u32>,
+
+    /// Whether TLS certificates are verified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_tls: Option<bool>,
+
+    /// Proxy used for this request, overriding the collection and app proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl RequestSettings {
+    /// Validates the settings
+    pub fn validate(&self) -> ModelResult<()> {
+        if self.connect_timeout_ms == Some(0) {
+            return Err(ModelError::ValidationError(
+                "Connect timeout must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.timeout_ms == Some(0) {
+            return Err(ModelError::ValidationError(
+                "Timeout must be greater than 0".to_string(),
+            ));
+        }
+
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
         }
+
+        Ok(())
     }
 }
 
@@ -37,19 +156,49 @@ pub struct Request {
     /// HTTP method (GET, POST, PUT, etc.)
     pub method: HttpMethod,
 
-    /// Request URL
+    /// Request URL, without the query parameters listed in `query_params`
+    ///
+    /// Path segments written as `:name` are filled in from `path_params`.
     pub url: String,
 
-    /// Request headers as key-value pairs
+    /// Query parameters appended to the URL, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query_params: Vec<KeyValue>,
+
+    /// Values for the `:name` path segments of the URL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_params: Vec<KeyValue>,
+
+    /// Request headers in order, allowing repeated names
     #[serde(default)]
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
 
-    /// Optional request body
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub body: Option<String>,
+    /// Request body
+    #[serde(
+        default,
+        skip_serializing_if = "RequestBody::is_none",
+        deserialize_with = "deserialize_body"
+    )]
+    pub body: RequestBody,
 
     /// Request name for identification
     pub name: String,
+
+    /// Credentials applied when the request is sent
+    #[serde(default, skip_serializing_if = "Auth::is_inherit")]
+    pub auth: Auth,
+
+    /// Optional connection settings overriding the defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<RequestSettings>,
+
+    /// Checks run against the response, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+
+    /// Values of the response stored in runtime variables, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extractions: Vec<Extraction>,
 }
 
 impl Request {
@@ -58,9 +207,15 @@ impl Request {
         Self {
             method: HttpMethod::default(),
             url: url.into(),
-            headers: HashMap::new(),
-            body: None,
+            query_params: Vec::new(),
+            path_params: Vec::new(),
+            headers: Headers::new(),
+            body: RequestBody::None,
             name: name.into(),
+            auth: Auth::Inherit,
+            settings: None,
+            assertions: Vec::new(),
+            extractions: Vec::new(),
         }
     }
 
@@ -70,15 +225,106 @@ impl Request {
         self
     }
 
-    /// Adds a header to the request
+    /// Sets the URL, moving its query string into `query_params`
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.set_url(url);
+        self
+    }
+
+    /// Sets the URL, keeping the parameter lists in sync with it
+    ///
+    /// The query string replaces the enabled query parameters (disabled ones
+    /// are kept, as they never appear in the URL). Path parameters follow the
+    /// `:name` segments of the new URL, keeping known values.
+    pub fn set_url(&mut self, url: impl Into<String>) {
+        let url = url.into();
+        let (base, query) = params::split_query(&url);
+
+        let disabled = self.query_params.iter().filter(|param| !param.enabled);
+        self.query_params = query
+            .map(params::parse_query)
+            .unwrap_or_default()
+            .into_iter()
+            .chain(disabled.cloned())
+            .collect();
+
+        self.path_params = params::path_param_names(&base)
+            .into_iter()
+            .map(|name| {
+                self.path_params
+                    .iter()
+                    .find(|param| param.key == name)
+                    .cloned()
+                    .unwrap_or_else(|| KeyValue::new(name, ""))
+            })
+            .collect();
+
+        self.url = base;
+    }
+
+    /// Appends a query parameter
+    pub fn with_query_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query_params.push(KeyValue::new(key, value));
+        self
+    }
+
+    /// Sets the value of a `:name` path parameter
+    pub fn with_path_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+        match self.path_params.iter_mut().find(|param| param.key == key) {
+            Some(param) => param.value = value,
+            None => self.path_params.push(KeyValue::new(key, value)),
+        }
+        self
+    }
+
+    /// Returns the URL as shown in the editor: enabled query parameters are
+    /// appended, path parameters stay as `:name` placeholders
+    pub fn url_with_query(&self) -> String {
+        params::append_query(&self.url, &params::encode_query(&self.query_params))
+    }
+
+    /// Returns the URL that is sent, with path parameters filled in and
+    /// query parameters percent-encoded
+    pub fn build_url(&self) -> String {
+        let url = params::substitute_path_params(&self.url, &self.path_params);
+        params::append_query(&url, &params::encode_query(&self.query_params))
+    }
+
+    /// Adds a header to the request, keeping existing headers of the same name
     pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(key.into(), value.into());
+        self.headers.append(key, value);
+        self
+    }
+
+    /// Sets the request body (strings become a raw body)
+    pub fn with_body(mut self, body: impl Into<RequestBody>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sets the auth
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sets the connection settings
+    pub fn with_settings(mut self, settings: RequestSettings) -> Self {
+        self.settings = Some(settings);
         self
     }
 
-    /// Sets the request body
-    pub fn with_body(mut self, body: impl Into<String>) -> Self {
-        self.body = Some(body.into());
+    /// Adds an assertion
+    pub fn with_assertion(mut self, assertion: Assertion) -> Self {
+        self.assertions.push(assertion);
+        self
+    }
+
+    /// Adds an extraction rule
+    pub fn with_extraction(mut self, extraction: Extraction) -> Self {
+        self.extractions.push(extraction);
         self
     }
 
@@ -89,6 +335,8 @@ impl Request {
             return Err(ModelError::EmptyField("name".to_string()));
         }
 
+        self.method.validate()?;
+
         // Validate URL is not empty and has valid scheme
         if self.url.trim().is_empty() {
             return Err(ModelError::EmptyField("url".to_string()));
@@ -116,6 +364,41 @@ impl Request {
             )));
         }
 
+        if self.query_params.iter().any(|param| param.key.is_empty()) {
+            return Err(ModelError::EmptyField("query_params.key".to_string()));
+        }
+
+        if self.path_params.iter().any(|param| param.key.is_empty()) {
+            return Err(ModelError::EmptyField("path_params.key".to_string()));
+        }
+
+        self.auth.validate()?;
+
+        // Check the URL that will actually be sent parses
+        let url = self.build_url();
+        let parsed =
+            url::Url::parse(&url).map_err(|e| ModelError::InvalidUrl(format!("{}: {}", e, url)))?;
+        if parsed.host_str().is_none_or(str::is_empty) {
+            return Err(ModelError::InvalidUrl(format!(
+                "URL must contain a domain: {}",
+                url
+            )));
+        }
+
+        self.body.validate()?;
+
+        if let Some(settings) = &self.settings {
+            settings.validate()?;
+        }
+
+        for assertion in &self.assertions {
+            assertion.validate()?;
+        }
+
+        for extraction in &self.extractions {
+            extraction.validate()?;
+        }
+
         Ok(())
     }
 }
@@ -126,7 +409,7 @@ impl fmt::Display for Request {
         if !self.headers.is_empty() {
             write!(f, " with {} header(s)", self.headers.len())?;
         }
-        if self.body.is_some() {
+        if !self.body.is_none() {
             write!(f, " with body")?;
         }
         Ok(())
@@ -160,9 +443,9 @@ mod tests {
         assert_eq!(request.headers.len(), 2);
         assert_eq!(
             request.headers.get("Content-Type"),
-            Some(&"application/json".to_string())
+            Some("application/json")
         );
-        assert_eq!(request.body, Some(r#"{"name": "John Doe"}"#.to_string()));
+        assert_eq!(request.body, RequestBody::from(r#"{"name": "John Doe"}"#));
     }
 
     #[test]
@@ -188,6 +471,123 @@ mod tests {
         assert!(json.contains(r#""name":"Test""#));
     }
 
+    #[test]
+    fn test_request_repeated_headers_round_trip() {
+        let request = Request::new("Test", "https://example.com")
+            .with_header("Accept", "application/json")
+            .with_header("Cookie", "a=1")
+            .with_header("Cookie", "b=2");
+
+        let yaml = serde_yaml::to_string(&request).unwrap();
+        let loaded: Request = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(loaded.headers, request.headers);
+        assert_eq!(loaded.headers.get_all("cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(yaml, serde_yaml::to_string(&loaded).unwrap());
+    }
+
+    #[test]
+    fn test_request_legacy_string_body() {
+        let yaml = "method: POST\nurl: https://example.com\nname: Legacy\nbody: hello\n";
+        let request: Request = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(request.body, RequestBody::from("hello"));
+
+        let yaml = "method: GET\nurl: https://example.com\nname: Empty\nbody: null\n";
+        let request: Request = serde_yaml::from_str(yaml).unwrap();
+        assert!(request.body.is_none());
+
+        let request =
+            Request::new("Json", "https://example.com").with_body(RequestBody::json("{}"));
+        let yaml = serde_yaml::to_string(&request).unwrap();
+        assert!(yaml.contains("body:\n  type: json\n  content: '{}'\n"));
+        assert_eq!(serde_yaml::from_str::<Request>(&yaml).unwrap(), request);
+    }
+
+    #[test]
+    fn test_set_url_syncs_params() {
+        let mut request = Request::new("Test", "https://example.com")
+            .with_query_param("debug", "1")
+            .with_path_param("id", "42");
+        request.query_params[0].enabled = false;
+
+        request.set_url("https://example.com/users/:id/posts/:postId?q=a%20b&page=2");
+
+        assert_eq!(request.url, "https://example.com/users/:id/posts/:postId");
+        assert_eq!(
+            request.query_params,
+            vec![
+                KeyValue::new("q", "a b"),
+                KeyValue::new("page", "2"),
+                KeyValue::new("debug", "1").disabled(),
+            ]
+        );
+        assert_eq!(
+            request.path_params,
+            vec![KeyValue::new("id", "42"), KeyValue::new("postId", "")]
+        );
+        assert_eq!(
+            request.url_with_query(),
+            "https://example.com/users/:id/posts/:postId?q=a%20b&page=2"
+        );
+    }
+
+    #[test]
+    fn test_build_url() {
+        let request = Request::new("Test", "https://example.com/users/:id")
+            .with_path_param("id", "a/b")
+            .with_query_param("filter", "name=x&y")
+            .with_query_param("page", "1");
+
+        assert_eq!(
+            request.build_url(),
+            "https://example.com/users/a%2Fb?filter=name%3Dx%26y&page=1"
+        );
+
+        // Legacy requests keep their query string in the URL
+        let request = Request::new("Test", "https://example.com/?a=1").with_query_param("b", "2");
+        assert_eq!(request.build_url(), "https://example.com/?a=1&b=2");
+    }
+
+    #[test]
+    fn test_params_serialization() {
+        let request =
+            Request::new("Test", "https://example.com").with_url("https://example.com/a?x=1");
+        let yaml = serde_yaml::to_string(&request).unwrap();
+        assert!(
+            yaml.contains("url: https://example.com/a\nquery_params:\n- key: x\n  value: '1'\n")
+        );
+        assert!(!yaml.contains("path_params"));
+        assert_eq!(serde_yaml::from_str::<Request>(&yaml).unwrap(), request);
+    }
+
+    #[test]
+    fn test_custom_methods() {
+        let method = HttpMethod::from("PROPFIND");
+        assert_eq!(method, HttpMethod::Custom("PROPFIND".to_string()));
+        assert_eq!(method.to_string(), "PROPFIND");
+        assert!(method.validate().is_ok());
+        assert_eq!(HttpMethod::from("DELETE"), HttpMethod::Delete);
+
+        for invalid in ["", "GET POST", "PURGE\n", "M(KCOL)", "CAFÉ"] {
+            assert_eq!(
+                HttpMethod::Custom(invalid.to_string()).validate(),
+                Err(ModelError::InvalidMethod(invalid.to_string()))
+            );
+        }
+
+        let request = Request::new("Purge", "https://cdn.example.com/")
+            .with_method(HttpMethod::Custom("PURGE".to_string()));
+        let yaml = serde_yaml::to_string(&request).unwrap();
+        assert!(yaml.starts_with("method: PURGE\n"));
+        assert_eq!(serde_yaml::from_str::<Request>(&yaml).unwrap(), request);
+
+        let request = request.with_method(HttpMethod::Custom("BAD METHOD".to_string()));
+        assert!(matches!(
+            request.validate(),
+            Err(ModelError::InvalidMethod(_))
+        ));
+    }
+
     #[test]
     fn test_request_validation_success() {
         let request = Request::new("Valid Request", "https://api.example.com/users");
@@ -236,6 +636,18 @@ mod tests {
         assert!(request.validate().is_err());
     }
 
+    #[test]
+    fn test_request_validation_unparseable_url() {
+        let request = Request::new("Test", "https://exa mple.com/");
+        assert!(matches!(request.validate(), Err(ModelError::InvalidUrl(_))));
+
+        let request = Request::new("Test", "https://example.com").with_query_param("", "x");
+        assert_eq!(
+            request.validate(),
+            Err(ModelError::EmptyField("query_params.key".to_string()))
+        );
+    }
+
     #[test]
     fn test_request_validation_no_domain() {
         let request = Request::new("Test", "https://");
@@ -247,6 +659,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_request_settings_serialization() {
+        let request = Request::new("Test", "https://example.com").with_settings(RequestSettings {
+            timeout_ms: Some(5000),
+            follow_redirects: Some(false),
+            ..Default::default()
+        });
+
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains(r#""settings":{"timeout_ms":5000,"follow_redirects":false}"#));
+
+        let deserialized: Request = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.settings, request.settings);
+
+        // Settings are omitted entirely when not set
+        let json = serde_json::to_string(&Request::new("Test", "https://example.com")).unwrap();
+        assert!(!json.contains("settings"));
+    }
+
+    #[test]
+    fn test_request_validation_zero_timeout() {
+        let request = Request::new("Test", "https://example.com").with_settings(RequestSettings {
+            timeout_ms: Some(0),
+            ..Default::default()
+        });
+        assert!(matches!(
+            request.validate(),
+            Err(ModelError::ValidationError(msg)) if msg.contains("Timeout")
+        ));
+
+        let request = Request::new("Test", "https://example.com").with_settings(RequestSettings {
+            connect_timeout_ms: Some(0),
+            ..Default::default()
+        });
+        assert!(request.validate().is_err());
+    }
+
     #[test]
     fn test_http_method_display() {
         assert_eq!(HttpMethod::Get.to_string(), "GET");
@@ -1,7 +1,12 @@
+use crate::models::assertion::AssertionResult;
 use crate::models::error::{ModelError, ModelResult};
+use crate::models::extraction::ExtractionResult;
+use crate::models::headers::Headers;
+use crate::models::variable::DynamicVariable;
+use base64::prelude::{Engine, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fmt;
+use std::net::SocketAddr;
 use std::time::Duration;
 
 /// Represents an HTTP response
@@ -10,41 +15,327 @@ pub struct Response {
     /// HTTP status code (e.g., 200, 404, 500)
     pub status: u16,
 
-    /// Response headers as key-value pairs
+    /// Response headers in the order received, including repeated names
     #[serde(default)]
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
 
-    /// Response body as a string
+    /// Response body decoded as text (empty for binary payloads)
     pub body: String,
 
-    /// Time taken to receive the response
+    /// Raw response body, serialized as a base64 string
+    #[serde(default, with = "base64_serde")]
+    pub body_bytes: Vec<u8>,
+
+    /// Detected MIME type without parameters (e.g. `application/json`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// Charset used to decode the body into text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+
+    /// Whether the body is binary and should be previewed or saved as bytes
+    #[serde(default)]
+    pub is_binary: bool,
+
+    /// Total time from sending the request to receiving the full body
+    #[serde(with = "duration_serde")]
+    pub response_time: Duration,
+
+    /// Breakdown of where the response time was spent
+    #[serde(default)]
+    pub timings: ResponseTimings,
+
+    /// Details of the connection the response arrived on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionInfo>,
+
+    /// Redirects followed before this response, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<Redirect>,
+
+    /// Values generated for `{{$...}}` placeholders of the request that was
+    /// sent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_variables: Vec<DynamicVariable>,
+
+    /// Outcomes of the request's assertions, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<AssertionResult>,
+
+    /// Outcomes of the request's extraction rules, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extractions: Vec<ExtractionResult>,
+}
+
+/// An intermediate response that redirected the request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redirect {
+    /// Method the hop was sent with
+    pub method: String,
+
+    /// URL that answered with the redirect
+    pub url: String,
+
+    /// Redirect status code (301, 302, 303, 307 or 308)
+    pub status: u16,
+
+    /// `Location` header as sent by the server
+    pub location: String,
+
+    /// Response headers of the hop
+    #[serde(default)]
+    pub headers: Headers,
+
+    /// Time from sending the hop to reading its (discarded) body
     #[serde(with = "duration_serde")]
     pub response_time: Duration,
+
+    /// Breakdown of where the hop's time was spent
+    #[serde(default)]
+    pub timings: ResponseTimings,
+
+    /// Details of the connection the hop used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionInfo>,
+}
+
+/// Time spent in each phase of a request
+///
+/// Connection phases are only present when a new connection was opened for
+/// the request; `dns` is also absent when the URL host is an IP address.
+/// Durations are serialized as fractional milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseTimings {
+    /// DNS lookup
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_fractional_millis_serde"
+    )]
+    pub dns: Option<Duration>,
+
+    /// TCP connect (including any proxy tunnel setup)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_fractional_millis_serde"
+    )]
+    pub tcp: Option<Duration>,
+
+    /// TLS handshake
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_fractional_millis_serde"
+    )]
+    pub tls: Option<Duration>,
+
+    /// Time to first byte: from the connection being ready to the response
+    /// headers arriving
+    #[serde(default, with = "fractional_millis_serde")]
+    pub ttfb: Duration,
+
+    /// Reading the response body
+    #[serde(default, with = "fractional_millis_serde")]
+    pub download: Duration,
+}
+
+/// Details of the connection a response was received on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    /// Negotiated HTTP version (e.g. `HTTP/1.1`, `HTTP/2.0`)
+    pub http_version: String,
+
+    /// IP address and port of the server (or proxy) connected to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_addr: Option<SocketAddr>,
+
+    /// Whether an idle pooled connection was reused instead of opening one
+    pub reused: bool,
+
+    /// TLS details, for HTTPS responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsInfo>,
+}
+
+/// Negotiated TLS parameters and the certificate chain sent by the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsInfo {
+    /// Protocol version (e.g. `TLSv1.3`)
+    pub protocol_version: String,
+
+    /// Cipher suite (e.g. `TLS13_AES_256_GCM_SHA384`)
+    pub cipher_suite: String,
+
+    /// Certificates in the order sent, starting with the server's own
+    #[serde(default)]
+    pub certificates: Vec<CertificateInfo>,
+}
+
+/// Summary of an X.509 certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// Subject distinguished name (e.g. `CN=example.com, O=Example`)
+    pub subject: String,
+
+    /// Issuer distinguished name
+    pub issuer: String,
+
+    /// Subject alternative names (e.g. `DNS:example.com`, `IP:127.0.0.1`)
+    #[serde(default)]
+    pub subject_alt_names: Vec<String>,
+
+    /// Start of the validity period (RFC 3339)
+    pub not_before: String,
+
+    /// End of the validity period (RFC 3339)
+    pub not_after: String,
+
+    /// Serial number as colon separated hex
+    pub serial_number: String,
+
+    /// SHA-256 fingerprint of the DER encoding as colon separated hex
+    pub fingerprint_sha256: String,
 }
 
 impl Response {
-    /// Creates a new response
+    /// Creates a new response with a text body
     pub fn new(status: u16, body: impl Into<String>, response_time: Duration) -> Self {
+        let body = body.into();
         Self {
             status,
-            headers: HashMap::new(),
-            body: body.into(),
+            headers: Headers::new(),
+            body_bytes: body.clone().into_bytes(),
+            body,
+            content_type: None,
+            charset: None,
+            is_binary: false,
             response_time,
+            timings: ResponseTimings::default(),
+            connection: None,
+            redirects: Vec::new(),
+            dynamic_variables: Vec::new(),
+            assertions: Vec::new(),
+            extractions: Vec::new(),
         }
     }
 
-    /// Adds a header to the response
+    /// Creates a new response from raw body bytes
+    ///
+    /// The MIME type comes from the `Content-Type` header, or is sniffed from
+    /// the leading bytes when the header is missing. Textual bodies are
+    /// decoded using the declared charset, a byte order mark, or UTF-8 (in
+    /// that order); binary bodies keep an empty `body` and are only available
+    /// through `body_bytes`.
+    pub fn from_bytes(
+        status: u16,
+        body_bytes: Vec<u8>,
+        content_type: Option<&str>,
+        response_time: Duration,
+    ) -> Self {
+        let (mime, declared_charset) = match content_type {
+            Some(value) => parse_content_type(value),
+            None => (None, None),
+        };
+        let mime = mime.or_else(|| sniff_mime(&body_bytes).map(str::to_string));
+
+        let is_binary = match &mime {
+            Some(mime) => !is_textual_mime(mime),
+            None => std::str::from_utf8(&body_bytes).is_err() || body_bytes.contains(&0),
+        };
+
+        let (body, charset) = if is_binary {
+            (String::new(), None)
+        } else {
+            let encoding = declared_charset
+                .as_deref()
+                .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+                .or_else(|| encoding_rs::Encoding::for_bom(&body_bytes).map(|(enc, _)| enc))
+                .unwrap_or(encoding_rs::UTF_8);
+            let (text, used_encoding, _) = encoding.decode(&body_bytes);
+            (text.into_owned(), Some(used_encoding.name().to_string()))
+        };
+
+        Self {
+            status,
+            headers: Headers::new(),
+            body,
+            body_bytes,
+            content_type: mime,
+            charset,
+            is_binary,
+            response_time,
+            timings: ResponseTimings::default(),
+            connection: None,
+            redirects: Vec::new(),
+            dynamic_variables: Vec::new(),
+            assertions: Vec::new(),
+            extractions: Vec::new(),
+        }
+    }
+
+    /// Adds a header to the response, keeping existing headers of the same name
     pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(key.into(), value.into());
+        self.headers.append(key, value);
         self
     }
 
     /// Sets multiple headers at once
-    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+    pub fn with_headers(mut self, headers: Headers) -> Self {
         self.headers = headers;
         self
     }
 
+    /// Sets the timing breakdown
+    pub fn with_timings(mut self, timings: ResponseTimings) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Sets the connection details
+    pub fn with_connection(mut self, connection: ConnectionInfo) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    /// Sets the redirects followed before this response
+    pub fn with_redirects(mut self, redirects: Vec<Redirect>) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    /// Sets the values generated for dynamic variables
+    pub fn with_dynamic_variables(mut self, dynamic_variables: Vec<DynamicVariable>) -> Self {
+        self.dynamic_variables = dynamic_variables;
+        self
+    }
+
+    /// Sets the outcomes of the request's assertions
+    pub fn with_assertions(mut self, assertions: Vec<AssertionResult>) -> Self {
+        self.assertions = assertions;
+        self
+    }
+
+    /// Sets the outcomes of the request's extraction rules
+    pub fn with_extractions(mut self, extractions: Vec<ExtractionResult>) -> Self {
+        self.extractions = extractions;
+        self
+    }
+
+    /// Returns the raw body encoded as base64
+    pub fn body_base64(&self) -> String {
+        BASE64_STANDARD.encode(&self.body_bytes)
+    }
+
+    /// Returns the raw body encoded as lowercase hex
+    pub fn body_hex(&self) -> String {
+        self.body_bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
     /// Checks if the response status is successful (2xx)
     pub fn is_success(&self) -> bool {
         (200..300).contains(&self.status)
@@ -82,13 +373,93 @@ impl fmt::Display for Response {
         if !self.headers.is_empty() {
             write!(f, " with {} header(s)", self.headers.len())?;
         }
-        if !self.body.is_empty() {
-            write!(f, ", {} bytes", self.body.len())?;
+        if !self.body_bytes.is_empty() {
+            write!(f, ", {} bytes", self.body_bytes.len())?;
         }
         Ok(())
     }
 }
 
+/// Split a `Content-Type` value into its lowercase MIME type and charset
+fn parse_content_type(value: &str) -> (Option<String>, Option<String>) {
+    let mut parts = value.split(';');
+
+    let mime = parts
+        .next()
+        .map(|essence| essence.trim().to_ascii_lowercase())
+        .filter(|essence| !essence.is_empty());
+
+    let charset = parts.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    });
+
+    (mime, charset)
+}
+
+/// Guess the MIME type of a body from well-known magic numbers
+fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, mime)| *mime)
+}
+
+/// Check whether a MIME type describes text that can be decoded and shown
+fn is_textual_mime(mime: &str) -> bool {
+    const TEXTUAL: &[&str] = &[
+        "application/json",
+        "application/xml",
+        "application/javascript",
+        "application/ecmascript",
+        "application/graphql",
+        "application/x-www-form-urlencoded",
+        "application/yaml",
+        "application/x-yaml",
+        "image/svg+xml",
+    ];
+
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || TEXTUAL.contains(&mime)
+}
+
+// Helper module for serializing/deserializing body bytes as base64
+mod base64_serde {
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64_STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 // Helper module for serializing/deserializing Duration as milliseconds
 mod duration_serde {
     use serde::{Deserialize, Deserializer, Serializer};
@@ -110,6 +481,54 @@ mod duration_serde {
     }
 }
 
+// Helper module for serializing/deserializing Duration as fractional
+// milliseconds, keeping sub-millisecond phases visible
+mod fractional_millis_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(millis / 1000.0).map_err(serde::de::Error::custom)
+    }
+}
+
+// Same as `fractional_millis_serde` for optional durations
+mod option_fractional_millis_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => super::fractional_millis_serde::serialize(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<f64>::deserialize(deserializer)?
+            .map(|millis| Duration::try_from_secs_f64(millis / 1000.0))
+            .transpose()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,7 +552,7 @@ mod tests {
         assert_eq!(response.headers.len(), 2);
         assert_eq!(
             response.headers.get("Content-Type"),
-            Some(&"application/json".to_string())
+            Some("application/json")
         );
     }
 
@@ -171,6 +590,98 @@ mod tests {
         assert_eq!(deserialized.response_time, Duration::from_millis(250));
     }
 
+    #[test]
+    fn test_response_new_keeps_bytes_in_sync() {
+        let response = Response::new(200, "hello", Duration::from_millis(10));
+        assert_eq!(response.body_bytes, b"hello");
+        assert!(!response.is_binary);
+        assert_eq!(response.body_base64(), "aGVsbG8=");
+        assert_eq!(response.body_hex(), "68656c6c6f");
+    }
+
+    #[test]
+    fn test_from_bytes_decodes_text() {
+        let response = Response::from_bytes(
+            200,
+            br#"{"ok":true}"#.to_vec(),
+            Some("application/json; charset=utf-8"),
+            Duration::from_millis(10),
+        );
+
+        assert_eq!(response.body, r#"{"ok":true}"#);
+        assert_eq!(response.content_type.as_deref(), Some("application/json"));
+        assert_eq!(response.charset.as_deref(), Some("UTF-8"));
+        assert!(!response.is_binary);
+    }
+
+    #[test]
+    fn test_from_bytes_uses_declared_charset() {
+        // "café" in ISO-8859-1
+        let response = Response::from_bytes(
+            200,
+            vec![0x63, 0x61, 0x66, 0xe9],
+            Some("text/plain; charset=\"ISO-8859-1\""),
+            Duration::from_millis(10),
+        );
+
+        assert_eq!(response.body, "café");
+        assert_eq!(response.charset.as_deref(), Some("windows-1252"));
+        assert_eq!(response.body_bytes, vec![0x63, 0x61, 0x66, 0xe9]);
+    }
+
+    #[test]
+    fn test_from_bytes_keeps_binary_intact() {
+        let png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff];
+        let response = Response::from_bytes(
+            200,
+            png.clone(),
+            Some("image/png"),
+            Duration::from_millis(10),
+        );
+
+        assert!(response.is_binary);
+        assert!(response.body.is_empty());
+        assert!(response.charset.is_none());
+        assert_eq!(response.body_bytes, png);
+        assert_eq!(response.to_string(), "HTTP 200 (10 ms), 10 bytes");
+    }
+
+    #[test]
+    fn test_from_bytes_sniffs_missing_content_type() {
+        let pdf = b"%PDF-1.7\n\x00\x01".to_vec();
+        let response = Response::from_bytes(200, pdf, None, Duration::from_millis(10));
+        assert_eq!(response.content_type.as_deref(), Some("application/pdf"));
+        assert!(response.is_binary);
+
+        let response =
+            Response::from_bytes(200, vec![0xc3, 0x28, 0xa0], None, Duration::from_millis(10));
+        assert!(response.content_type.is_none());
+        assert!(response.is_binary);
+
+        let response =
+            Response::from_bytes(200, b"plain".to_vec(), None, Duration::from_millis(10));
+        assert!(!response.is_binary);
+        assert_eq!(response.body, "plain");
+    }
+
+    #[test]
+    fn test_body_bytes_serialized_as_base64() {
+        let response = Response::from_bytes(
+            200,
+            vec![0x00, 0xff, 0x10],
+            Some("application/octet-stream"),
+            Duration::from_millis(5),
+        );
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""body_bytes":"AP8Q""#));
+        assert!(json.contains(r#""content_type":"application/octet-stream""#));
+        assert!(json.contains(r#""is_binary":true"#));
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, response);
+    }
+
     #[test]
     fn test_response_validation_success() {
         let response = Response::new(200, "OK", Duration::from_millis(100));
@@ -208,4 +719,59 @@ mod tests {
             .with_header("Content-Type", "text/html");
         assert_eq!(response.to_string(), "HTTP 404 (50 ms) with 1 header(s)");
     }
+
+    #[test]
+    fn test_timings_and_connection_serialization() {
+        let response = Response::new(200, "", Duration::from_millis(12))
+            .with_timings(ResponseTimings {
+                dns: Some(Duration::from_micros(1500)),
+                tcp: None,
+                tls: None,
+                ttfb: Duration::from_millis(8),
+                download: Duration::from_micros(250),
+            })
+            .with_connection(ConnectionInfo {
+                http_version: "HTTP/1.1".to_string(),
+                remote_addr: Some("127.0.0.1:8080".parse().unwrap()),
+                reused: false,
+                tls: None,
+            });
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["timings"]["dns"], 1.5);
+        assert_eq!(json["timings"]["download"], 0.25);
+        assert!(json["timings"].get("tcp").is_none());
+        assert_eq!(json["connection"]["remote_addr"], "127.0.0.1:8080");
+        assert_eq!(json["connection"]["http_version"], "HTTP/1.1");
+        assert!(json["connection"].get("tls").is_none());
+
+        let deserialized: Response = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.timings, response.timings);
+        assert_eq!(deserialized.connection, response.connection);
+    }
+
+    #[test]
+    fn test_redirects_serialization() {
+        let response = Response::new(200, "", Duration::from_millis(30));
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("redirects").is_none());
+
+        let response = response.with_redirects(vec![Redirect {
+            method: "POST".to_string(),
+            url: "https://example.com/login".to_string(),
+            status: 302,
+            location: "/home".to_string(),
+            headers: Headers::from_iter([("location", "/home")]),
+            response_time: Duration::from_millis(10),
+            timings: ResponseTimings::default(),
+            connection: None,
+        }]);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["redirects"][0]["status"], 302);
+        assert_eq!(json["redirects"][0]["location"], "/home");
+        assert_eq!(json["redirects"][0]["response_time"], 10);
+
+        let deserialized: Response = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.redirects, response.redirects);
+    }
 }
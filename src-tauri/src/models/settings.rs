@@ -0,0 +1,44 @@
+use crate::models::error::ModelResult;
+use crate::models::proxy::ProxyConfig;
+use serde::{Deserialize, Serialize};
+
+/// Application-wide settings
+///
+/// These are the lowest-priority defaults: collection settings override
+/// them, and request settings override both.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AppSettings {
+    /// Default proxy configuration
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+
+    /// Path of the active environment file, whose variables apply to every
+    /// request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_environment: Option<String>,
+}
+
+impl AppSettings {
+    /// Validates the settings
+    pub fn validate(&self) -> ModelResult<()> {
+        self.proxy.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ManualProxy;
+
+    #[test]
+    fn test_app_settings_defaults() {
+        let settings: AppSettings = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(settings.proxy, ProxyConfig::System);
+
+        let settings = AppSettings {
+            proxy: ProxyConfig::Manual(ManualProxy::new("not a url")),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::models::error::{ModelError, ModelResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Certificates used when talking to a collection's hosts
+///
+/// Relative paths are resolved against the directory of the collection file.
+/// Passphrases are never stored here; they are supplied at runtime.
+///
+/// ```yaml
+/// settings:
+///   tls:
+///     ca_certificates:
+///       - path: certs/internal-ca.pem
+///         host: "*.internal.example"
+///     client_certificates:
+///       - host: api.internal.example
+///         type: pem
+///         cert: certs/client.pem
+///         key: certs/client.key
+///       - host: "*.corp.example"
+///         type: pkcs12
+///         path: certs/me.p12
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TlsSettings {
+    /// Extra root certificates trusted on top of the bundled ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certificates: Vec<CaCertificate>,
+
+    /// Client identities presented to servers that ask for one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_certificates: Vec<ClientCertificate>,
+}
+
+/// A PEM (or DER) file of trusted root certificates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaCertificate {
+    /// Path of the certificate file; may contain several certificates
+    pub path: String,
+
+    /// Host pattern the certificates are trusted for (all hosts when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+/// A client identity used for hosts matching a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientCertificate {
+    /// Host pattern: `api.example.com`, `*.example.com` or `*`
+    pub host: String,
+
+    #[serde(flatten)]
+    pub identity: ClientIdentity,
+}
+
+/// Files holding a client certificate and its private key
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientIdentity {
+    /// PEM certificate chain and private key (PKCS#8, PKCS#1 or SEC1; the
+    /// PKCS#8 key may be encrypted)
+    Pem {
+        cert: String,
+
+        /// Key file (defaults to `cert` when both are in one file)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+    },
+
+    /// PKCS#12 archive (`.p12` / `.pfx`)
+    Pkcs12 { path: String },
+}
+
+impl CaCertificate {
+    /// Creates a CA certificate trusted for all hosts
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            host: None,
+        }
+    }
+
+    /// Restricts the certificate to hosts matching a pattern
+    pub fn for_host(mut self, pattern: impl Into<String>) -> Self {
+        self.host = Some(pattern.into());
+        self
+    }
+}
+
+impl ClientCertificate {
+    /// Creates a client certificate for hosts matching a pattern
+    pub fn new(host: impl Into<String>, identity: ClientIdentity) -> Self {
+        Self {
+            host: host.into(),
+            identity,
+        }
+    }
+}
+
+impl ClientIdentity {
+    /// Creates a PEM identity
+    pub fn pem(cert: impl Into<String>, key: impl Into<String>) -> Self {
+        ClientIdentity::Pem {
+            cert: cert.into(),
+            key: Some(key.into()),
+        }
+    }
+
+    /// Creates a PKCS#12 identity
+    pub fn pkcs12(path: impl Into<String>) -> Self {
+        ClientIdentity::Pkcs12 { path: path.into() }
+    }
+
+    /// Path of the file holding the private key
+    ///
+    /// Passphrases are looked up by this path.
+    pub fn key_path(&self) -> &str {
+        match self {
+            ClientIdentity::Pem { cert, key } => key.as_deref().unwrap_or(cert),
+            ClientIdentity::Pkcs12 { path } => path,
+        }
+    }
+
+    /// Resolve relative paths against a base directory
+    pub fn resolve(&self, base: Option<&Path>) -> Self {
+        match self {
+            ClientIdentity::Pem { cert, key } => ClientIdentity::Pem {
+                cert: resolve_path(cert, base),
+                key: key.as_deref().map(|key| resolve_path(key, base)),
+            },
+            ClientIdentity::Pkcs12 { path } => ClientIdentity::Pkcs12 {
+                path: resolve_path(path, base),
+            },
+        }
+    }
+}
+
+impl TlsSettings {
+    /// Checks if no certificate is configured
+    pub fn is_empty(&self) -> bool {
+        self.ca_certificates.is_empty() && self.client_certificates.is_empty()
+    }
+
+    /// Get the paths of the CA certificates trusted for a host, resolved
+    /// against a base directory
+    pub fn ca_certificates_for(&self, host: &str, base: Option<&Path>) -> Vec<String> {
+        self.ca_certificates
+            .iter()
+            .filter(|ca| {
+                ca.host
+                    .as_deref()
+                    .is_none_or(|pattern| host_matches(pattern, host))
+            })
+            .map(|ca| resolve_path(&ca.path, base))
+            .collect()
+    }
+
+    /// Get the identity for a host, resolved against a base directory
+    ///
+    /// The first client certificate whose pattern matches wins.
+    pub fn client_identity_for(&self, host: &str, base: Option<&Path>) -> Option<ClientIdentity> {
+        self.client_certificates
+            .iter()
+            .find(|client| host_matches(&client.host, host))
+            .map(|client| client.identity.resolve(base))
+    }
+
+    /// Validates the settings
+    pub fn validate(&self) -> ModelResult<()> {
+        for ca in &self.ca_certificates {
+            if ca.path.trim().is_empty() {
+                return Err(ModelError::EmptyField(
+                    "tls.ca_certificates.path".to_string(),
+                ));
+            }
+            if let Some(pattern) = &ca.host {
+                validate_host_pattern(pattern)?;
+            }
+        }
+
+        for client in &self.client_certificates {
+            validate_host_pattern(&client.host)?;
+            let paths = match &client.identity {
+                ClientIdentity::Pem { cert, key } => vec![Some(cert), key.as_ref()],
+                ClientIdentity::Pkcs12 { path } => vec![Some(path)],
+            };
+            if paths
+                .into_iter()
+                .flatten()
+                .any(|path| path.trim().is_empty())
+            {
+                return Err(ModelError::EmptyField(
+                    "tls.client_certificates.path".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check whether a host matches a pattern
+///
+/// `*` matches every host, `*.example.com` matches any subdomain of
+/// `example.com` (but not `example.com` itself), and anything else must
+/// match exactly. Comparison is case-insensitive.
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+    match pattern.strip_prefix('*') {
+        Some("") => true,
+        Some(suffix) if suffix.starts_with('.') => {
+            host.len() > suffix.len() && host.ends_with(suffix)
+        }
+        _ => pattern == host,
+    }
+}
+
+fn validate_host_pattern(pattern: &str) -> ModelResult<()> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err(ModelError::EmptyField("tls.host".to_string()));
+    }
+
+    let rest = pattern.strip_prefix("*.").unwrap_or(pattern);
+    if pattern != "*" && rest.contains('*') {
+        return Err(ModelError::ValidationError(format!(
+            "Invalid host pattern '{}': only a leading '*.' wildcard is supported",
+            pattern
+        )));
+    }
+
+    Ok(())
+}
+
+fn resolve_path(path: &str, base: Option<&Path>) -> String {
+    match base {
+        Some(base) if Path::new(path).is_relative() => {
+            base.join(path).to_string_lossy().into_owned()
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_matches() {
+        assert!(host_matches("*", "anything.example"));
+        assert!(host_matches("api.example.com", "API.example.com"));
+        assert!(!host_matches("api.example.com", "www.example.com"));
+        assert!(host_matches("*.example.com", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+    }
+
+    #[test]
+    fn test_identity_selection_and_paths() {
+        let settings = TlsSettings {
+            ca_certificates: vec![
+                CaCertificate::new("/etc/ca/global.pem"),
+                CaCertificate::new("certs/internal.pem").for_host("*.internal.example"),
+            ],
+            client_certificates: vec![
+                ClientCertificate::new(
+                    "api.internal.example",
+                    ClientIdentity::pem("certs/api.pem", "certs/api.key"),
+                ),
+                ClientCertificate::new("*.internal.example", ClientIdentity::pkcs12("me.p12")),
+            ],
+        };
+        let base = Path::new("/collections");
+
+        assert_eq!(
+            settings.ca_certificates_for("db.internal.example", Some(base)),
+            vec!["/etc/ca/global.pem", "/collections/certs/internal.pem"]
+        );
+        assert_eq!(
+            settings.ca_certificates_for("example.com", Some(base)),
+            vec!["/etc/ca/global.pem"]
+        );
+
+        let identity = settings
+            .client_identity_for("api.internal.example", Some(base))
+            .unwrap();
+        assert_eq!(
+            identity,
+            ClientIdentity::pem("/collections/certs/api.pem", "/collections/certs/api.key")
+        );
+        assert_eq!(identity.key_path(), "/collections/certs/api.key");
+
+        let identity = settings.client_identity_for("db.internal.example", None);
+        assert_eq!(identity, Some(ClientIdentity::pkcs12("me.p12")));
+        assert!(settings.client_identity_for("example.com", None).is_none());
+    }
+
+    #[test]
+    fn test_yaml_and_validation() {
+        let yaml = "ca_certificates:\n- path: ca.pem\nclient_certificates:\n- host: '*.corp'\n  type: pkcs12\n  path: me.p12\n";
+        let settings: TlsSettings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            settings.client_certificates[0].identity,
+            ClientIdentity::pkcs12("me.p12")
+        );
+        assert_eq!(serde_yaml::to_string(&settings).unwrap(), yaml);
+        assert!(settings.validate().is_ok());
+
+        let invalid = TlsSettings {
+            client_certificates: vec![ClientCertificate::new(
+                "api.*.corp",
+                ClientIdentity::pkcs12("me.p12"),
+            )],
+            ..Default::default()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(ModelError::ValidationError(_))
+        ));
+
+        let invalid = TlsSettings {
+            client_certificates: vec![ClientCertificate::new("*", ClientIdentity::pem("a", ""))],
+            ..Default::default()
+        };
+        assert!(matches!(invalid.validate(), Err(ModelError::EmptyField(_))));
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+/// A variable defined by a collection or an environment
+///
+/// A secret variable names an entry of the local secret vault instead of
+/// holding its value, so the file it is defined in can be committed.
+///
+/// ```yaml
+/// variables:
+///   - key: base_url
+///     value: https://api.example.com
+///   - key: token
+///     secret: prod-api-token
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Variable {
+    pub key: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub value: String,
+
+    /// Name of the vault secret holding the value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Disabled variables are kept in the file but not used
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+}
+
+/// The value generated for a `{{$...}}` placeholder during one execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DynamicVariable {
+    /// Expression inside the braces, with placeholders in its arguments
+    /// resolved (`$uuid`, `$timestamp +1h`, ...)
+    pub expression: String,
+
+    pub value: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl Variable {
+    /// Creates a variable with a plain value
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            secret: None,
+            enabled: true,
+        }
+    }
+
+    /// Creates a variable whose value is the vault secret `secret`
+    pub fn secret(key: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: String::new(),
+            secret: Some(secret.into()),
+            enabled: true,
+        }
+    }
+
+    /// Marks the variable as disabled
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_yaml() {
+        let variables = vec![
+            Variable::new("base_url", "https://api.example.com"),
+            Variable::secret("token", "prod-api-token"),
+            Variable::new("debug", "1").disabled(),
+        ];
+        let yaml = serde_yaml::to_string(&variables).unwrap();
+        assert_eq!(
+            yaml,
+            "- key: base_url\n  value: https://api.example.com\n- key: token\n  secret: prod-api-token\n- key: debug\n  value: '1'\n  enabled: false\n"
+        );
+
+        let loaded: Vec<Variable> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded, variables);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::models::{Assertion, AssertionResult, Response};
+use regex::Regex;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+/// Characters of the body reported by a `body_matches` assertion that failed
+const MAX_ACTUAL_BODY: usize = 200;
+
+/// Evaluate a request's assertions against its response
+///
+/// Every assertion is evaluated, in order; one that cannot be checked (e.g. a
+/// JSON path on a body that is not JSON) fails with an error.
+pub fn evaluate(assertions: &[Assertion], response: &Response) -> Vec<AssertionResult> {
+    // The body is parsed once, and only if a JSON assertion needs it
+    let mut json: Option<Result<Value, String>> = None;
+
+    assertions
+        .iter()
+        .map(|assertion| {
+            let (passed, actual, error) = match check(assertion, response, &mut json) {
+                Ok((passed, actual)) => (passed, actual, None),
+                Err(error) => (false, None, Some(error)),
+            };
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed,
+                actual,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// Check one assertion, returning whether it passed and the actual value
+fn check(
+    assertion: &Assertion,
+    response: &Response,
+    json: &mut Option<Result<Value, String>>,
+) -> Result<(bool, Option<Value>), String> {
+    let status = Value::from(response.status);
+    let header = |name: &str| {
+        let values = response.headers.get_all(name);
+        (!values.is_empty()).then(|| values.join(", "))
+    };
+
+    Ok(match assertion {
+        Assertion::Status { equals } => (response.status == *equals, Some(status)),
+        Assertion::StatusRange { min, max } => {
+            ((*min..=*max).contains(&response.status), Some(status))
+        }
+        Assertion::HeaderPresent { name } => {
+            let value = header(name);
+            (value.is_some(), value.map(Value::from))
+        }
+        Assertion::HeaderMatches { name, pattern } => {
+            let pattern = regex(pattern)?;
+            let passed = response
+                .headers
+                .get_all(name)
+                .iter()
+                .any(|value| pattern.is_match(value));
+            (passed, header(name).map(Value::from))
+        }
+        Assertion::JsonEquals { path, value } => {
+            let actual = select(json, response, path)?;
+            (actual.as_ref() == Some(value), actual)
+        }
+        Assertion::JsonContains { path, value } => {
+            let actual = select(json, response, path)?;
+            let passed = match (&actual, value) {
+                (Some(Value::Array(items)), value) => items.contains(value),
+                (Some(Value::String(text)), Value::String(part)) => text.contains(part.as_str()),
+                _ => false,
+            };
+            (passed, actual)
+        }
+        Assertion::JsonType { path, json_type } => {
+            let actual = select(json, response, path)?;
+            let passed = actual
+                .as_ref()
+                .is_some_and(|value| json_type.matches(value));
+            (passed, actual)
+        }
+        // The first match is reported, or the start of the body without one
+        Assertion::BodyMatches { pattern } => match regex(pattern)?.find(&response.body) {
+            Some(found) => (true, Some(Value::from(found.as_str()))),
+            None => (false, Some(Value::from(truncate(&response.body)))),
+        },
+        Assertion::ResponseTime { under_ms } => {
+            let elapsed = response.response_time.as_millis();
+            (
+                elapsed < u128::from(*under_ms),
+                Some(Value::from(elapsed as u64)),
+            )
+        }
+    })
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_ACTUAL_BODY) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))
+}
+
+/// Select the value at a JSON path of the body
+///
+/// A path that matches several nodes yields them as an array; one that
+/// matches nothing yields `None`. `json` caches the parsed body across calls.
+pub(crate) fn select(
+    json: &mut Option<Result<Value, String>>,
+    response: &Response,
+    path: &str,
+) -> Result<Option<Value>, String> {
+    let path = JsonPath::parse(path).map_err(|e| format!("Invalid JSON path '{}': {}", path, e))?;
+    let body = json
+        .get_or_insert_with(|| {
+            serde_json::from_str(&response.body).map_err(|e| format!("Body is not JSON: {}", e))
+        })
+        .as_ref()
+        .map_err(Clone::clone)?;
+
+    let nodes = path.query(body).all();
+    Ok(match nodes[..] {
+        [] => None,
+        [node] => Some(node.clone()),
+        _ => Some(Value::Array(nodes.into_iter().cloned().collect())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Headers, JsonType};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn response() -> Response {
+        let mut headers = Headers::new();
+        headers.append("Content-Type", "application/json; charset=utf-8");
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Set-Cookie", "b=2");
+        let body = r#"{"items":[{"id":42,"name":"Widget"}],"tags":["new","sale"],"total":1.5}"#;
+        Response::new(201, body, Duration::from_millis(120)).with_headers(headers)
+    }
+
+    fn outcome(assertion: Assertion) -> (bool, Option<Value>) {
+        let result = evaluate(&[assertion], &response()).remove(0);
+        assert_eq!(result.error, None);
+        (result.passed, result.actual)
+    }
+
+    #[test]
+    fn test_status_headers_body_and_time() {
+        assert_eq!(
+            outcome(Assertion::Status { equals: 200 }),
+            (false, Some(json!(201)))
+        );
+        assert!(outcome(Assertion::StatusRange { min: 200, max: 299 }).0);
+
+        assert_eq!(
+            outcome(Assertion::HeaderPresent {
+                name: "set-cookie".to_string()
+            }),
+            (true, Some(json!("a=1, b=2")))
+        );
+        assert_eq!(
+            outcome(Assertion::HeaderPresent {
+                name: "ETag".to_string()
+            }),
+            (false, None)
+        );
+        assert!(
+            outcome(Assertion::HeaderMatches {
+                name: "Set-Cookie".to_string(),
+                pattern: "^b=".to_string(),
+            })
+            .0
+        );
+
+        assert_eq!(
+            outcome(Assertion::BodyMatches {
+                pattern: r#""name":\s*"\w+""#.to_string()
+            }),
+            (true, Some(json!(r#""name":"Widget""#)))
+        );
+        assert_eq!(
+            outcome(Assertion::BodyMatches {
+                pattern: "Gadget".to_string()
+            }),
+            (false, Some(json!(response().body)))
+        );
+        assert_eq!(
+            outcome(Assertion::ResponseTime { under_ms: 100 }),
+            (false, Some(json!(120)))
+        );
+    }
+
+    #[test]
+    fn test_json_path_assertions() {
+        let json_equals = |path: &str, value: Value| Assertion::JsonEquals {
+            path: path.to_string(),
+            value,
+        };
+        assert_eq!(
+            outcome(json_equals("$.items[0].id", json!(42))),
+            (true, Some(json!(42)))
+        );
+        assert_eq!(
+            outcome(json_equals("$.items[*].name", json!(["Widget"]))),
+            (false, Some(json!("Widget")))
+        );
+        assert_eq!(
+            outcome(json_equals("$.missing", Value::Null)),
+            (false, None)
+        );
+
+        let contains = |path: &str, value: Value| Assertion::JsonContains {
+            path: path.to_string(),
+            value,
+        };
+        assert!(outcome(contains("$.tags", json!("sale"))).0);
+        assert!(outcome(contains("$.items[0].name", json!("idg"))).0);
+        assert!(!outcome(contains("$.tags", json!("old"))).0);
+
+        let json_type = |path: &str, json_type| Assertion::JsonType {
+            path: path.to_string(),
+            json_type,
+        };
+        assert!(outcome(json_type("$.items", JsonType::Array)).0);
+        assert!(!outcome(json_type("$.total", JsonType::Integer)).0);
+        assert!(outcome(json_type("$.total", JsonType::Number)).0);
+    }
+
+    #[test]
+    fn test_assertions_that_cannot_be_checked() {
+        let text = Response::new(200, "plain text", Duration::from_millis(5));
+        let results = evaluate(
+            &[
+                Assertion::JsonEquals {
+                    path: "$.id".to_string(),
+                    value: json!(1),
+                },
+                Assertion::BodyMatches {
+                    pattern: "(".to_string(),
+                },
+                Assertion::Status { equals: 200 },
+            ],
+            &text,
+        );
+
+        assert!(!results[0].passed);
+        assert!(results[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("Body is not JSON"));
+        assert!(results[1]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("Invalid pattern"));
+        // The other assertions are still evaluated
+        assert!(results[2].passed);
+
+        // Long bodies are cut short when nothing matched
+        let long = Response::new(200, "é".repeat(500), Duration::from_millis(5));
+        let pattern = Assertion::BodyMatches {
+            pattern: "missing".to_string(),
+        };
+        let actual = evaluate(&[pattern], &long).remove(0).actual.unwrap();
+        assert_eq!(actual, json!(format!("{}…", "é".repeat(MAX_ACTUAL_BODY))));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::models::Headers;
+use md5::Md5;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Hash function of a Digest challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(self, data: &str) -> String {
+        match self {
+            Self::Md5 => hex::encode(Md5::digest(data.as_bytes())),
+            Self::Sha256 => hex::encode(Sha256::digest(data.as_bytes())),
+        }
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge (RFC 7616)
+///
+/// Only `qop=auth` is supported; challenges that offer nothing but
+/// `auth-int`, or use another algorithm, are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: DigestAlgorithm,
+
+    /// Whether the session variant (`-sess`) is used
+    pub session: bool,
+
+    /// Whether the server asked for `qop=auth`; old servers send no qop
+    pub qop: bool,
+}
+
+impl DigestChallenge {
+    /// Find the strongest supported challenge in response headers
+    pub fn from_headers(headers: &Headers) -> Option<Self> {
+        headers
+            .get_all("www-authenticate")
+            .into_iter()
+            .filter_map(Self::parse)
+            .max_by_key(|challenge| challenge.algorithm)
+    }
+
+    /// Parse one challenge
+    pub fn parse(value: &str) -> Option<Self> {
+        let (scheme, params) = value.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+
+        let params = parse_params(params);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+
+        let algorithm = param("algorithm").unwrap_or_else(|| "MD5".to_string());
+        let (algorithm, session) = match algorithm.to_ascii_uppercase().as_str() {
+            "MD5" => (DigestAlgorithm::Md5, false),
+            "MD5-SESS" => (DigestAlgorithm::Md5, true),
+            "SHA-256" => (DigestAlgorithm::Sha256, false),
+            "SHA-256-SESS" => (DigestAlgorithm::Sha256, true),
+            _ => return None,
+        };
+        let qop = match param("qop") {
+            Some(qop) if qop.split(',').any(|qop| qop.trim() == "auth") => true,
+            Some(_) => return None,
+            None => false,
+        };
+
+        Some(Self {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm,
+            session,
+            qop,
+        })
+    }
+
+    /// Build the `Authorization` header answering the challenge
+    ///
+    /// # Arguments
+    /// * `method` - Request method
+    /// * `uri` - Request target, i.e. the path and query
+    pub fn authorization(&self, username: &str, password: &str, method: &str, uri: &str) -> String {
+        let cnonce = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+        self.authorization_with_cnonce(username, password, method, uri, &cnonce)
+    }
+
+    fn authorization_with_cnonce(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        // Each challenge is answered once, so the nonce count is always 1
+        let nc = "00000001";
+        let hash = |data: String| self.algorithm.hash(&data);
+
+        let mut ha1 = hash(format!("{}:{}:{}", username, self.realm, password));
+        if self.session {
+            ha1 = hash(format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = hash(format!("{}:{}", method, uri));
+        let response = if self.qop {
+            hash(format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            ))
+        } else {
+            hash(format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let algorithm = match (self.algorithm, self.session) {
+            (DigestAlgorithm::Md5, false) => "MD5",
+            (DigestAlgorithm::Md5, true) => "MD5-sess",
+            (DigestAlgorithm::Sha256, false) => "SHA-256",
+            (DigestAlgorithm::Sha256, true) => "SHA-256-sess",
+        };
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", uri=\"{}\", algorithm={}, nonce=\"{}\"",
+            quote(username),
+            quote(&self.realm),
+            quote(uri),
+            algorithm,
+            quote(&self.nonce)
+        );
+        if self.qop {
+            header.push_str(&format!(", nc={}, cnonce=\"{}\", qop=auth", nc, cnonce));
+        }
+        header.push_str(&format!(", response=\"{}\"", response));
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        header
+    }
+}
+
+/// Escape a value for a quoted string
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parse comma-separated `name=value` and `name="quoted value"` parameters
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut chars = params.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+        let name: String =
+            std::iter::from_fn(|| chars.next_if(|c| *c != '=' && *c != ',')).collect();
+        if name.is_empty() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.next_if_eq(&'"').is_some() {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(chars.next()),
+                        c => value.push(c),
+                    }
+                }
+            } else {
+                value = std::iter::from_fn(|| chars.next_if(|c| *c != ',')).collect();
+            }
+        }
+        parsed.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc_examples() {
+        // RFC 7616 section 3.9.1
+        let challenge = |algorithm: &str| {
+            DigestChallenge::parse(&format!(
+                "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", \
+                 algorithm={}, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+                 opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+                algorithm
+            ))
+            .unwrap()
+        };
+        let answer = |challenge: DigestChallenge| {
+            challenge.authorization_with_cnonce(
+                "Mufasa",
+                "Circle of Life",
+                "GET",
+                "/dir/index.html",
+                "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+            )
+        };
+
+        let sha256 = challenge("SHA-256");
+        assert_eq!(sha256.algorithm, DigestAlgorithm::Sha256);
+        assert!(sha256.qop);
+        assert_eq!(
+            answer(sha256),
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             uri=\"/dir/index.html\", algorithm=SHA-256, \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", nc=00000001, \
+             cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", qop=auth, \
+             response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+        assert!(answer(challenge("MD5")).contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""));
+
+        // RFC 2617 section 3.5, where MD5 is implied
+        let rfc2617 = DigestChallenge::parse(
+            "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", \
+             opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"",
+        )
+        .unwrap();
+        assert_eq!(rfc2617.algorithm, DigestAlgorithm::Md5);
+        let header = rfc2617.authorization_with_cnonce(
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        );
+        assert!(header.contains("response=\"6629fae49393a05397450978507c4ef1\""));
+    }
+
+    #[test]
+    fn test_challenge_selection() {
+        let mut headers = Headers::new();
+        headers.append("WWW-Authenticate", "Basic realm=\"api\"");
+        headers.append(
+            "WWW-Authenticate",
+            "Digest realm=\"api\", nonce=\"n1\", algorithm=MD5",
+        );
+        headers.append(
+            "WWW-Authenticate",
+            "Digest realm=\"api\", nonce=\"n2\", algorithm=SHA-256-sess, qop=auth",
+        );
+        headers.append(
+            "WWW-Authenticate",
+            "Digest realm=\"api\", nonce=\"n3\", algorithm=SHA-512-256",
+        );
+
+        let challenge = DigestChallenge::from_headers(&headers).unwrap();
+        assert_eq!(challenge.nonce, "n2");
+        assert!(challenge.session);
+        let header = challenge.authorization("alice", "pw", "GET", "/");
+        assert!(header.contains("algorithm=SHA-256-sess"));
+        assert!(!header.contains("opaque"));
+
+        // Only auth-int is offered
+        assert!(DigestChallenge::parse("Digest nonce=\"n\", qop=\"auth-int\"").is_none());
+        // Quoted values may contain commas and escapes
+        let quoted = DigestChallenge::parse("Digest realm=\"a, \\\"b\\\"\", nonce=n4").unwrap();
+        assert_eq!(quoted.realm, "a, \"b\"");
+        assert_eq!(quoted.nonce, "n4");
+        assert!(!quoted.qop);
+    }
+}
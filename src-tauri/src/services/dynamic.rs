@@ -0,0 +1,277 @@
+use crate::models::DynamicVariable;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::distributions::Alphanumeric;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use uuid::Uuid;
+
+/// Characters left as they are by `$urlEncode`, as in `encodeURIComponent`
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// Default upper bound of `$randomInt`
+const DEFAULT_RANDOM_INT_MAX: i64 = 1000;
+
+/// Default length of `$randomString`
+const DEFAULT_RANDOM_STRING_LENGTH: usize = 16;
+
+/// Longest string `$randomString` generates
+const MAX_RANDOM_STRING_LENGTH: usize = 4096;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bob", "Carol", "David", "Emma", "Frank", "Grace", "Henry", "Isla", "Jack", "Kara",
+    "Liam", "Maya", "Noah", "Olivia", "Paul", "Rosa", "Sam", "Tara", "Victor",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Anderson", "Brown", "Clark", "Davis", "Evans", "Fischer", "Garcia", "Harris", "Ito", "Jones",
+    "Kim", "Lopez", "Martin", "Nguyen", "Olsen", "Patel", "Rossi", "Smith", "Taylor", "Walker",
+];
+
+/// Values of the `{{$...}}` placeholders of one execution
+///
+/// Each expression is evaluated the first time it is used and keeps its
+/// value for the rest of the execution, so `{{$uuid}}` is the same in a
+/// header and in the body. All time expressions use the same instant.
+///
+/// Supported expressions:
+/// - `$uuid` (or `$uuidv4`), `$uuidv7`
+/// - `$timestamp`, `$timestampMs` (Unix time), `$isoTimestamp`, `$isoDate`,
+///   each with an optional offset such as `+1h`, `-30m` or `+7d`
+/// - `$randomInt` (0 to 1000) or `$randomInt min max`
+/// - `$randomString` (16 characters) or `$randomString length`
+/// - `$base64 text`, `$urlEncode text`
+/// - `$randomFirstName`, `$randomLastName`, `$randomFullName`, `$randomEmail`
+#[derive(Debug, Clone)]
+pub struct DynamicValues {
+    now: DateTime<Utc>,
+    values: Vec<DynamicVariable>,
+}
+
+impl Default for DynamicValues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicValues {
+    /// Start a new execution at the current time
+    pub fn new() -> Self {
+        Self::at(Utc::now())
+    }
+
+    /// Start a new execution at a given time
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            values: Vec::new(),
+        }
+    }
+
+    /// Get the value of an expression, evaluating it on first use
+    ///
+    /// # Returns
+    /// `None` if the expression is unknown or its arguments are invalid
+    pub fn get(&mut self, expression: &str) -> Option<String> {
+        if let Some(known) = self
+            .values
+            .iter()
+            .find(|known| known.expression == expression)
+        {
+            return Some(known.value.clone());
+        }
+
+        let value = evaluate(expression, self.now)?;
+        self.values.push(DynamicVariable {
+            expression: expression.to_string(),
+            value: value.clone(),
+        });
+        Some(value)
+    }
+
+    /// The values generated so far, in the order they were first used
+    pub fn into_values(self) -> Vec<DynamicVariable> {
+        self.values
+    }
+}
+
+fn evaluate(expression: &str, now: DateTime<Utc>) -> Option<String> {
+    let (name, argument) = match expression.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (expression, ""),
+    };
+    let no_argument = || argument.is_empty().then_some(());
+    let mut rng = rand::thread_rng();
+
+    let value = match name {
+        "$uuid" | "$uuidv4" => no_argument().map(|_| Uuid::new_v4().to_string())?,
+        "$uuidv7" => no_argument().map(|_| Uuid::now_v7().to_string())?,
+        "$timestamp" => now
+            .checked_add_signed(parse_offset(argument)?)?
+            .timestamp()
+            .to_string(),
+        "$timestampMs" => now
+            .checked_add_signed(parse_offset(argument)?)?
+            .timestamp_millis()
+            .to_string(),
+        "$isoTimestamp" => now
+            .checked_add_signed(parse_offset(argument)?)?
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        "$isoDate" => now
+            .checked_add_signed(parse_offset(argument)?)?
+            .format("%Y-%m-%d")
+            .to_string(),
+        "$randomInt" => {
+            let (min, max) = match argument.split_whitespace().collect::<Vec<_>>()[..] {
+                [] => (0, DEFAULT_RANDOM_INT_MAX),
+                [min, max] => (min.parse::<i64>().ok()?, max.parse::<i64>().ok()?),
+                _ => return None,
+            };
+            if min > max {
+                return None;
+            }
+            rng.gen_range(min..=max).to_string()
+        }
+        "$randomString" => {
+            let length = match argument {
+                "" => DEFAULT_RANDOM_STRING_LENGTH,
+                length => length.parse().ok()?,
+            };
+            if length > MAX_RANDOM_STRING_LENGTH {
+                return None;
+            }
+            (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(length)
+                .map(char::from)
+                .collect()
+        }
+        "$base64" => BASE64_STANDARD.encode(argument),
+        "$urlEncode" => utf8_percent_encode(argument, URL_COMPONENT).to_string(),
+        "$randomFirstName" => no_argument().map(|_| pick(FIRST_NAMES, &mut rng))?,
+        "$randomLastName" => no_argument().map(|_| pick(LAST_NAMES, &mut rng))?,
+        "$randomFullName" => no_argument().map(|_| {
+            format!(
+                "{} {}",
+                pick(FIRST_NAMES, &mut rng),
+                pick(LAST_NAMES, &mut rng)
+            )
+        })?,
+        "$randomEmail" => no_argument().map(|_| {
+            format!(
+                "{}.{}{}@example.com",
+                pick(FIRST_NAMES, &mut rng).to_lowercase(),
+                pick(LAST_NAMES, &mut rng).to_lowercase(),
+                rng.gen_range(1..1000)
+            )
+        })?,
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn pick(names: &[&str], rng: &mut impl Rng) -> String {
+    names.choose(rng).copied().unwrap_or_default().to_string()
+}
+
+/// Parse a time offset such as `+1h`, `-30m` or `15s`
+///
+/// Units are `s`, `m`, `h`, `d` and `w`; an empty offset is zero.
+fn parse_offset(offset: &str) -> Option<Duration> {
+    if offset.is_empty() {
+        return Some(Duration::zero());
+    }
+
+    let (sign, rest) = match offset.as_bytes()[0] {
+        b'-' => (-1, &offset[1..]),
+        b'+' => (1, &offset[1..]),
+        _ => (1, offset),
+    };
+    let unit_start = rest.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = rest[..unit_start].parse().ok()?;
+    let seconds = match &rest[unit_start..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return None,
+    };
+
+    Duration::try_seconds(amount.checked_mul(seconds)?.checked_mul(sign)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_dynamic_values() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let mut values = DynamicValues::at(now);
+
+        let uuid = values.get("$uuid").unwrap();
+        assert_eq!(Uuid::parse_str(&uuid).unwrap().get_version_num(), 4);
+        // An expression keeps its value for the whole execution
+        assert_eq!(values.get("$uuid").unwrap(), uuid);
+        let uuid_v7 = values.get("$uuidv7").unwrap();
+        assert_eq!(Uuid::parse_str(&uuid_v7).unwrap().get_version_num(), 7);
+
+        assert_eq!(values.get("$timestamp").unwrap(), "1709294400");
+        assert_eq!(values.get("$timestamp +1h").unwrap(), "1709298000");
+        assert_eq!(values.get("$timestampMs -1s").unwrap(), "1709294399000");
+        assert_eq!(
+            values.get("$isoTimestamp +30m").unwrap(),
+            "2024-03-01T12:30:00.000Z"
+        );
+        assert_eq!(values.get("$isoDate -1d").unwrap(), "2024-02-29");
+
+        let int: i64 = values.get("$randomInt 5 7").unwrap().parse().unwrap();
+        assert!((5..=7).contains(&int));
+        let string = values.get("$randomString 24").unwrap();
+        assert_eq!(string.len(), 24);
+        assert!(string.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        assert_eq!(
+            values.get("$base64 alice:s3cret").unwrap(),
+            "YWxpY2U6czNjcmV0"
+        );
+        assert_eq!(
+            values.get("$urlEncode a b&c=d/é").unwrap(),
+            "a%20b%26c%3Dd%2F%C3%A9"
+        );
+
+        let email = values.get("$randomEmail").unwrap();
+        assert!(email.ends_with("@example.com"));
+        assert!(values.get("$randomFullName").unwrap().contains(' '));
+
+        for invalid in [
+            "$unknown",
+            "$uuid 4",
+            "$timestamp +1y",
+            "$randomInt 9 1",
+            "$randomInt 1",
+            "$randomString many",
+            "$isoDate +99999999999w",
+        ] {
+            assert_eq!(values.get(invalid), None, "{}", invalid);
+        }
+
+        let recorded = values.into_values();
+        assert_eq!(recorded[0].expression, "$uuid");
+        assert_eq!(recorded[0].value, uuid);
+        assert_eq!(recorded.len(), 13);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::models::{Extraction, ExtractionResult, ExtractionSource, Response, Variable};
+use crate::services::assertions::select;
+use cookie_store::RawCookie;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Extract values from a response with a request's extraction rules
+///
+/// Every rule is applied, in order; one that cannot be applied (e.g. a JSON
+/// path on a body that is not JSON) yields an error and no value.
+pub fn extract(extractions: &[Extraction], response: &Response) -> Vec<ExtractionResult> {
+    // The body is parsed once, and only if a JSON path needs it
+    let mut json: Option<Result<Value, String>> = None;
+
+    extractions
+        .iter()
+        .map(|extraction| {
+            let (value, error) = match value_of(&extraction.source, response, &mut json) {
+                Ok(value) => (value, None),
+                Err(error) => (None, Some(error)),
+            };
+            ExtractionResult {
+                variable: extraction.variable.trim().to_string(),
+                value,
+                error,
+            }
+        })
+        .collect()
+}
+
+fn value_of(
+    source: &ExtractionSource,
+    response: &Response,
+    json: &mut Option<Result<Value, String>>,
+) -> Result<Option<String>, String> {
+    Ok(match source {
+        ExtractionSource::JsonPath { path } => {
+            select(json, response, path)?.map(|value| match value {
+                Value::String(text) => text,
+                value => value.to_string(),
+            })
+        }
+        ExtractionSource::Header { name } => response.headers.get(name).map(str::to_string),
+        ExtractionSource::Regex { pattern, group } => {
+            let regex =
+                Regex::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+            let group = group.unwrap_or(if regex.captures_len() > 1 { 1 } else { 0 });
+            regex
+                .captures(&response.body)
+                .and_then(|captures| captures.get(group))
+                .map(|value| value.as_str().to_string())
+        }
+        // A later cookie of the same name replaces an earlier one
+        ExtractionSource::Cookie { name } => response
+            .headers
+            .get_all("Set-Cookie")
+            .into_iter()
+            .rev()
+            .filter_map(|value| RawCookie::parse(value).ok())
+            .find(|cookie| cookie.name() == name)
+            .map(|cookie| cookie.value().to_string()),
+    })
+}
+
+/// Runtime variables of the session
+///
+/// Values extracted from responses are kept in memory only, separately for
+/// each collection; requests outside collections share their own scope.
+/// They take precedence over environment and collection variables, and are
+/// used as they are: placeholders in them are never resolved.
+#[derive(Debug, Default)]
+pub struct RuntimeVariables {
+    scopes: RwLock<HashMap<Option<PathBuf>, BTreeMap<String, String>>>,
+}
+
+impl RuntimeVariables {
+    /// Create an empty set of runtime variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the variables of a collection, sorted by name
+    ///
+    /// # Arguments
+    /// * `collection` - Path of the collection file, or `None` for requests
+    ///   outside collections
+    pub fn get(&self, collection: Option<&Path>) -> Vec<Variable> {
+        self.scopes
+            .read()
+            .ok()
+            .and_then(|scopes| {
+                scopes.get(&collection.map(Path::to_path_buf)).map(|scope| {
+                    scope
+                        .iter()
+                        .map(|(name, value)| Variable::new(name, value))
+                        .collect()
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    /// Set a variable of a collection, replacing any previous value
+    pub fn set(
+        &self,
+        collection: Option<&Path>,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) {
+        if let Ok(mut scopes) = self.scopes.write() {
+            scopes
+                .entry(collection.map(Path::to_path_buf))
+                .or_default()
+                .insert(name.into(), value.into());
+        }
+    }
+
+    /// Forget the variables of a collection
+    pub fn clear(&self, collection: Option<&Path>) {
+        if let Ok(mut scopes) = self.scopes.write() {
+            scopes.remove(&collection.map(Path::to_path_buf));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Headers;
+    use std::time::Duration;
+
+    fn rule(variable: &str, source: ExtractionSource) -> Extraction {
+        Extraction::new(variable, source)
+    }
+
+    #[test]
+    fn test_extract() {
+        let mut headers = Headers::new();
+        headers.append("X-Request-Id", "req-1");
+        headers.append("Set-Cookie", "SESSION=old; Path=/");
+        headers.append("Set-Cookie", "theme=dark");
+        headers.append("Set-Cookie", "SESSION=abc123; Path=/; HttpOnly");
+        let body = r#"{"access_token":"t0ken","user":{"id":7,"roles":["admin"]},"note":"order-42 shipped"}"#;
+        let response = Response::new(200, body, Duration::from_millis(10)).with_headers(headers);
+
+        let json_path = |path: &str| ExtractionSource::JsonPath {
+            path: path.to_string(),
+        };
+        let regex = |pattern: &str, group| ExtractionSource::Regex {
+            pattern: pattern.to_string(),
+            group,
+        };
+        let results = extract(
+            &[
+                rule("token", json_path("$.access_token")),
+                rule("user", json_path("$.user")),
+                rule("user_id", json_path("$.user.id")),
+                rule("missing", json_path("$.refresh_token")),
+                rule(
+                    "request_id",
+                    ExtractionSource::Header {
+                        name: "x-request-id".to_string(),
+                    },
+                ),
+                rule("order", regex(r"order-(\d+)", None)),
+                rule("shipped", regex(r"order-\d+ \w+", None)),
+                rule(
+                    "session",
+                    ExtractionSource::Cookie {
+                        name: "SESSION".to_string(),
+                    },
+                ),
+            ],
+            &response,
+        );
+
+        let values: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|result| {
+                assert_eq!(result.error, None);
+                (result.variable.as_str(), result.value.as_deref())
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("token", Some("t0ken")),
+                ("user", Some(r#"{"id":7,"roles":["admin"]}"#)),
+                ("user_id", Some("7")),
+                ("missing", None),
+                ("request_id", Some("req-1")),
+                ("order", Some("42")),
+                ("shipped", Some("order-42 shipped")),
+                ("session", Some("abc123")),
+            ]
+        );
+
+        // Rules that cannot be applied report why
+        let text = Response::new(200, "plain text", Duration::from_millis(10));
+        let results = extract(&[rule("token", json_path("$.access_token"))], &text);
+        assert_eq!(results[0].value, None);
+        assert!(results[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("Body is not JSON"));
+    }
+
+    #[test]
+    fn test_runtime_variables_are_scoped() {
+        let variables = RuntimeVariables::new();
+        let collection = Path::new("/collections/api.collection.yaml");
+
+        variables.set(Some(collection), "token", "a");
+        variables.set(Some(collection), "token", "b");
+        variables.set(Some(collection), "id", "7");
+        variables.set(None, "token", "standalone");
+
+        assert_eq!(
+            variables.get(Some(collection)),
+            vec![Variable::new("id", "7"), Variable::new("token", "b")]
+        );
+        assert_eq!(
+            variables.get(None),
+            vec![Variable::new("token", "standalone")]
+        );
+
+        variables.clear(Some(collection));
+        assert!(variables.get(Some(collection)).is_empty());
+        assert_eq!(variables.get(None).len(), 1);
+    }
+}
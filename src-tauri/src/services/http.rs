@@ -1,71 +1,1111 @@
-use crate::models::{HttpMethod, Request, Response};
-use std::collections::HashMap;
+use crate::models::body::guess_mime;
+use crate::models::params;
+use crate::models::{
+    AppSettings, Auth, ClientIdentity, CollectionSettings, ConnectionInfo, Headers, MultipartPart,
+    ProxyConfig, Redirect, Request, RequestBody, RequestSettings, Response, TlsInfo,
+};
+use crate::services::digest::DigestChallenge;
+use crate::services::extraction::RuntimeVariables;
+use crate::services::jwt;
+use crate::services::oauth2::{OAuth2Error, OAuth2Tokens};
+use crate::services::proxy;
+use crate::services::signature;
+use crate::services::sigv4;
+use crate::services::template::{UnresolvedVariable, Variables};
+use crate::services::timing::{PhaseRecorder, TimingLayer, TimingResolver};
+use crate::services::tls::{self, TlsConfigError, TlsFailure};
+use crate::storage::CookieJar;
+use chrono::Utc;
+use futures::future::{AbortHandle, Abortable};
+use http_body_util::BodyExt;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use std::time::{Duration, Instant};
 
+/// Error type for HTTP execution
+///
+/// Serialized as `{ "kind": ..., "message": ... }` so the frontend can tell a
+/// cancelled request apart from a failed one.
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum HTTPServiceError {
+    /// The request failed validation before being sent
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// The request could not be completed (network, timeout, decoding, ...)
+    #[error("{0}")]
+    RequestFailed(String),
+
+    /// The request was cancelled by the user (contains the execution ID)
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+
+    /// Another in-flight request already uses this execution ID
+    #[error("Execution ID already in use: {0}")]
+    DuplicateExecutionId(String),
+
+    /// The client certificate key is encrypted and no passphrase has been
+    /// set for it (contains the key file path)
+    #[error("Passphrase required for client certificate: {0}")]
+    PassphraseRequired(String),
+
+    /// The TLS handshake failed; the message is an object giving the reason
+    /// and the certificates the server presented
+    #[error("TLS handshake failed: {0}")]
+    Tls(TlsFailure),
+
+    /// The request uses `{{name}}` placeholders no variable defines; the
+    /// message lists each one with where it appears
+    #[error(
+        "Unresolved variables: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    UnresolvedVariables(Vec<UnresolvedVariable>),
+
+    /// The request uses secret variables and the secret vault has not been
+    /// unlocked in this session
+    #[error("Secret vault is locked")]
+    VaultLocked,
+
+    /// The request uses the OAuth 2.0 authorization code grant and has no
+    /// token yet; the user has to authorize in the browser
+    #[error("OAuth 2.0 authorization required")]
+    AuthorizationRequired,
+
+    /// An OAuth 2.0 token could not be obtained
+    #[error("{0}")]
+    OAuth2(String),
+}
+
+impl From<OAuth2Error> for HTTPServiceError {
+    fn from(error: OAuth2Error) -> Self {
+        match error {
+            OAuth2Error::AuthorizationRequired => HTTPServiceError::AuthorizationRequired,
+            error => HTTPServiceError::OAuth2(error.to_string()),
+        }
+    }
+}
+
+impl From<TlsConfigError> for HTTPServiceError {
+    fn from(error: TlsConfigError) -> Self {
+        match error {
+            TlsConfigError::PassphraseRequired(path) => HTTPServiceError::PassphraseRequired(path),
+            error => {
+                HTTPServiceError::InvalidRequest(format!("Failed to configure TLS: {}", error))
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for HTTPServiceError {
+    fn from(error: reqwest::Error) -> Self {
+        HTTPServiceError::RequestFailed(error.to_string())
+    }
+}
+
+pub type HTTPServiceResult<T> = Result<T, HTTPServiceError>;
+
+fn client_cache_poisoned<T>(error: PoisonError<T>) -> HTTPServiceError {
+    HTTPServiceError::RequestFailed(format!("Failed to lock client cache: {}", error))
+}
+
+/// Default total timeout applied to requests
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default maximum number of redirects followed
+pub const DEFAULT_MAX_REDIRECTS: u32 = 10;
+
+/// Most clients kept at once; the least recently used one is dropped to
+/// make room for another
+const MAX_CLIENTS: usize = 16;
+
+/// Headers describing the body, dropped when a redirect discards it
+const BODY_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-encoding",
+    "transfer-encoding",
+];
+
+/// Headers carrying credentials, dropped when a redirect leaves the host
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "cookie2",
+    "proxy-authorization",
+    "www-authenticate",
+];
+
+/// Settings a request inherits from its surroundings
+///
+/// Request settings take precedence over collection settings, which take
+/// precedence over the application settings.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionContext {
+    /// Application-wide settings
+    pub app: AppSettings,
+
+    /// Settings of the collection the request belongs to
+    pub collection: CollectionSettings,
+
+    /// Path of the collection file, which scopes cached OAuth 2.0 tokens
+    pub collection_path: Option<PathBuf>,
+
+    /// Directory of the collection file, against which relative certificate
+    /// paths are resolved
+    pub collection_dir: Option<PathBuf>,
+
+    /// Cookie jar of the collection; requests outside a collection send no
+    /// stored cookies
+    pub cookie_jar: Option<Arc<CookieJar>>,
+
+    /// Values for the `{{name}}` placeholders of the request
+    pub variables: Variables,
+
+    /// Auth of the collection, used by requests that inherit it
+    pub auth: Auth,
+}
+
+impl ExecutionContext {
+    /// Get the proxy configuration that applies to a request
+    pub fn proxy_for(&self, request: &Request) -> ProxyConfig {
+        request
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.proxy.clone())
+            .or_else(|| self.collection.proxy.clone())
+            .unwrap_or_else(|| self.app.proxy.clone())
+    }
+}
+
+/// Client-level settings that require a dedicated `reqwest::Client`
+///
+/// Each distinct configuration gets its own client (and connection pool),
+/// which is built on first use and shared by every later request with the
+/// same configuration, until it is dropped to keep at most `MAX_CLIENTS`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientConfig {
+    /// Total time allowed for a request
+    pub timeout: Duration,
+
+    /// Time allowed to establish a connection
+    pub connect_timeout: Option<Duration>,
+
+    /// Whether redirects are followed
+    pub follow_redirects: bool,
+
+    /// Maximum number of redirects to follow
+    pub max_redirects: u32,
+
+    /// Whether TLS certificates are verified
+    pub verify_tls: bool,
+
+    /// How the client reaches the network
+    pub proxy: ProxyConfig,
+
+    /// Paths of extra trusted root certificates
+    pub ca_certificates: Vec<String>,
+
+    /// Client certificate presented to the server
+    pub client_identity: Option<ClientIdentity>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: None,
+            follow_redirects: true,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            verify_tls: true,
+            proxy: ProxyConfig::System,
+            ca_certificates: Vec::new(),
+            client_identity: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Create a configuration from optional request settings, using the
+    /// defaults for anything not set
+    pub fn from_settings(settings: Option<&RequestSettings>) -> Self {
+        let defaults = Self::default();
+        let Some(settings) = settings else {
+            return defaults;
+        };
+
+        Self {
+            timeout: settings
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.timeout),
+            connect_timeout: settings.connect_timeout_ms.map(Duration::from_millis),
+            follow_redirects: settings
+                .follow_redirects
+                .unwrap_or(defaults.follow_redirects),
+            max_redirects: settings.max_redirects.unwrap_or(defaults.max_redirects),
+            verify_tls: settings.verify_tls.unwrap_or(defaults.verify_tls),
+            proxy: settings.proxy.clone().unwrap_or(defaults.proxy),
+            ..defaults
+        }
+    }
+
+    /// Create the configuration for a request, resolving inherited settings
+    /// from its context
+    ///
+    /// Certificates are chosen by the host of the request URL.
+    pub fn for_request(request: &Request, context: &ExecutionContext) -> Self {
+        Self::for_url(&request.build_url(), request, context)
+    }
+
+    /// Create the configuration for sending a request to a URL, e.g. the
+    /// target of a redirect
+    ///
+    /// Certificates are chosen by the host of `url`.
+    pub fn for_url(url: &str, request: &Request, context: &ExecutionContext) -> Self {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let tls = &context.collection.tls;
+        let base = context.collection_dir.as_deref();
+
+        Self {
+            proxy: context.proxy_for(request),
+            ca_certificates: tls.ca_certificates_for(&host, base),
+            client_identity: tls.client_identity_for(&host, base),
+            ..Self::from_settings(request.settings.as_ref())
+        }
+    }
+
+    /// Build a `reqwest::Client` for this configuration
+    ///
+    /// Every client is instrumented to report DNS, connect and TLS phases
+    /// for the timing breakdown. Certificate files are read here, so changes
+    /// to them apply to clients built afterwards.
+    ///
+    /// # Arguments
+    /// * `passphrase` - Passphrase of the client certificate key, if any
+    fn build_client(&self, passphrase: Option<&str>) -> HTTPServiceResult<reqwest::Client> {
+        let tls_config = tls::client_config(
+            self.verify_tls,
+            &self.ca_certificates,
+            self.client_identity.as_ref(),
+            passphrase,
+        )?;
+
+        let builder = reqwest::Client::builder()
+            .timeout(self.timeout)
+            // Redirects are followed by the service, hop by hop
+            .redirect(reqwest::redirect::Policy::none())
+            .use_preconfigured_tls(tls_config)
+            .dns_resolver(Arc::new(TimingResolver))
+            .connector_layer(TimingLayer);
+        let mut builder =
+            proxy::configure(builder, &self.proxy, |name| std::env::var(name).ok())
+                .map_err(|e| HTTPServiceError::InvalidRequest(format!("Invalid proxy: {}", e)))?;
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
 /// HTTP service for executing HTTP requests
+///
+/// The service is safe to share between tasks without a lock: requests run
+/// concurrently and reuse pooled connections of the client matching their
+/// configuration.
 pub struct HTTPService {
+    /// Clients keyed by their configuration
+    clients: RwLock<HashMap<ClientConfig, CachedClient>>,
+
+    /// Source of the ticks that order clients by last use
+    client_uses: AtomicU64,
+
+    /// Abort handles of cancellable requests, keyed by execution ID
+    in_flight: Mutex<HashMap<String, InFlight>>,
+
+    /// Source of the registrations that tell executions reusing an ID apart
+    next_registration: AtomicU64,
+
+    /// Client certificate passphrases, keyed by key file path
+    ///
+    /// Kept in memory only so they never end up in collection files.
+    passphrases: RwLock<HashMap<String, String>>,
+
+    /// TLS details of the latest handshake with each origin, reported for
+    /// requests that reuse a pooled connection
+    tls_sessions: RwLock<HashMap<String, TlsInfo>>,
+
+    /// OAuth 2.0 tokens obtained during the session
+    oauth2_tokens: OAuth2Tokens,
+
+    /// Values extracted from responses during the session
+    runtime_variables: RuntimeVariables,
+}
+
+/// A client in the cache
+struct CachedClient {
     client: reqwest::Client,
+
+    /// Tick of the latest request that used the client
+    last_used: AtomicU64,
+}
+
+/// A cancellable execution in the in-flight registry
+struct InFlight {
+    /// Distinguishes the execution from a later one that reuses its ID
+    registration: u64,
+    handle: AbortHandle,
+}
+
+/// Removes an execution from the in-flight registry when it finishes or is
+/// dropped
+///
+/// A cancelled execution is removed right away, and a new execution may
+/// register the same ID before the aborted one is dropped; the entry is only
+/// removed if it is still this execution's.
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<HashMap<String, InFlight>>,
+    execution_id: &'a str,
+    registration: u64,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            if in_flight
+                .get(self.execution_id)
+                .is_some_and(|entry| entry.registration == self.registration)
+            {
+                in_flight.remove(self.execution_id);
+            }
+        }
+    }
 }
 
 impl HTTPService {
     /// Create a new HTTPService instance
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
+        let config = ClientConfig::default();
+        let client = config.build_client(None)?;
+
+        let mut clients = HashMap::new();
+        clients.insert(
+            config,
+            CachedClient {
+                client,
+                last_used: AtomicU64::new(0),
+            },
+        );
+
+        Ok(Self {
+            clients: RwLock::new(clients),
+            client_uses: AtomicU64::new(1),
+            in_flight: Mutex::new(HashMap::new()),
+            next_registration: AtomicU64::new(0),
+            passphrases: RwLock::new(HashMap::new()),
+            tls_sessions: RwLock::new(HashMap::new()),
+            oauth2_tokens: OAuth2Tokens::new(),
+            runtime_variables: RuntimeVariables::new(),
+        })
+    }
+
+    /// Get the client for a configuration, building it on first use
+    ///
+    /// `reqwest::Client` is reference counted, so the returned handle shares
+    /// its connection pool with every other request using the same config.
+    /// Once `MAX_CLIENTS` are cached, the least recently used one is dropped;
+    /// requests still using it keep their handle.
+    pub fn client_for(&self, config: &ClientConfig) -> HTTPServiceResult<reqwest::Client> {
+        let tick = self.client_uses.fetch_add(1, Ordering::Relaxed);
+        let cached = |cached: &CachedClient| {
+            cached.last_used.fetch_max(tick, Ordering::Relaxed);
+            cached.client.clone()
+        };
+
+        if let Some(client) = self
+            .clients
+            .read()
+            .map_err(client_cache_poisoned)?
+            .get(config)
+            .map(cached)
+        {
+            return Ok(client);
+        }
+
+        let passphrase = config.client_identity.as_ref().and_then(|identity| {
+            self.passphrases
+                .read()
+                .ok()
+                .and_then(|passphrases| passphrases.get(identity.key_path()).cloned())
+        });
+
+        let client = config.build_client(passphrase.as_deref())?;
+        let mut clients = self.clients.write().map_err(client_cache_poisoned)?;
+        // Another task may have raced us here; keep the first client so its
+        // pool stays shared
+        if let Some(client) = clients.get(config).map(cached) {
+            return Ok(client);
+        }
+
+        if clients.len() >= MAX_CLIENTS {
+            let least_recent = clients
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used.load(Ordering::Relaxed))
+                .map(|(config, _)| config.clone());
+            if let Some(least_recent) = least_recent {
+                clients.remove(&least_recent);
+            }
+        }
+        clients.insert(
+            config.clone(),
+            CachedClient {
+                client: client.clone(),
+                last_used: AtomicU64::new(tick),
+            },
+        );
+
+        Ok(client)
+    }
+
+    /// Set the passphrase of an encrypted client certificate key
+    ///
+    /// Clients already built for the key are dropped so the next request
+    /// uses the new passphrase.
+    ///
+    /// # Arguments
+    /// * `key_path` - Path of the key file, as reported by
+    ///   `HTTPServiceError::PassphraseRequired`
+    /// * `passphrase` - The passphrase
+    pub fn set_certificate_passphrase(&self, key_path: &str, passphrase: &str) {
+        if let Ok(mut passphrases) = self.passphrases.write() {
+            passphrases.insert(key_path.to_string(), passphrase.to_string());
+        }
+        if let Ok(mut clients) = self.clients.write() {
+            clients.retain(|config, _| {
+                config
+                    .client_identity
+                    .as_ref()
+                    .is_none_or(|identity| identity.key_path() != key_path)
+            });
+        }
+    }
+
+    /// Get the number of clients in the cache
+    pub fn client_count(&self) -> usize {
+        self.clients
+            .read()
+            .map(|clients| clients.len())
+            .unwrap_or(0)
+    }
+
+    /// Get the OAuth 2.0 tokens of the session
+    pub fn oauth2_tokens(&self) -> &OAuth2Tokens {
+        &self.oauth2_tokens
+    }
+
+    /// Get the runtime variables of the session
+    pub fn runtime_variables(&self) -> &RuntimeVariables {
+        &self.runtime_variables
+    }
+
+    /// Get the TLS details for a response
+    ///
+    /// A new connection reports its own handshake and remembers it for the
+    /// origin; a reused one reports the handshake remembered for the origin.
+    fn tls_info(
+        &self,
+        url: &reqwest::Url,
+        handshake: Option<&tls::TlsHandshake>,
+    ) -> Option<TlsInfo> {
+        if url.scheme() != "https" {
+            return None;
+        }
+
+        let origin = url.origin().ascii_serialization();
+        match handshake.and_then(|handshake| handshake.info()) {
+            Some(info) => {
+                if let Ok(mut sessions) = self.tls_sessions.write() {
+                    sessions.insert(origin, info.clone());
+                }
+                Some(info)
+            }
+            None => self
+                .tls_sessions
+                .read()
+                .ok()
+                .and_then(|sessions| sessions.get(&origin).cloned()),
+        }
+    }
+
+    /// Execute an HTTP request that can be cancelled with `cancel_request`
+    ///
+    /// # Arguments
+    /// * `request` - The request to execute
+    /// * `execution_id` - Caller-chosen ID identifying this execution
+    /// * `context` - Settings inherited from the collection and application
+    ///
+    /// # Returns
+    /// The response, or `HTTPServiceError::Cancelled` if the execution was
+    /// cancelled before it completed
+    pub async fn execute_cancellable(
+        &self,
+        request: &Request,
+        execution_id: &str,
+        context: &ExecutionContext,
+    ) -> HTTPServiceResult<Response> {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let registration = self.next_registration.fetch_add(1, Ordering::Relaxed);
 
-        Ok(Self { client })
+        {
+            let mut in_flight = self.in_flight.lock().map_err(|e| {
+                HTTPServiceError::RequestFailed(format!("Failed to lock in-flight registry: {}", e))
+            })?;
+            if in_flight.contains_key(execution_id) {
+                return Err(HTTPServiceError::DuplicateExecutionId(
+                    execution_id.to_string(),
+                ));
+            }
+            in_flight.insert(
+                execution_id.to_string(),
+                InFlight {
+                    registration,
+                    handle: abort_handle,
+                },
+            );
+        }
+
+        let _guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            execution_id,
+            registration,
+        };
+
+        // Aborting drops the reqwest future, which closes the connection
+        Abortable::new(
+            self.execute_request_with_context(request, context),
+            abort_registration,
+        )
+        .await
+        .unwrap_or_else(|_| Err(HTTPServiceError::Cancelled(execution_id.to_string())))
+    }
+
+    /// Cancel an in-flight request
+    ///
+    /// # Returns
+    /// `true` if a request with this execution ID was running and has been
+    /// aborted, `false` if no such request is in flight
+    pub fn cancel_request(&self, execution_id: &str) -> bool {
+        let entry = self
+            .in_flight
+            .lock()
+            .ok()
+            .and_then(|mut in_flight| in_flight.remove(execution_id));
+
+        match entry {
+            Some(entry) => {
+                entry.handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether a request with this execution ID is in flight
+    pub fn is_in_flight(&self, execution_id: &str) -> bool {
+        self.in_flight
+            .lock()
+            .map(|in_flight| in_flight.contains_key(execution_id))
+            .unwrap_or(false)
+    }
+
+    /// Execute an HTTP request with the default context and return the
+    /// response
+    pub async fn execute_request(&self, request: &Request) -> HTTPServiceResult<Response> {
+        self.execute_request_with_context(request, &ExecutionContext::default())
+            .await
     }
 
     /// Execute an HTTP request and return the response
-    pub async fn execute_request(
+    ///
+    /// Redirects are followed here rather than by reqwest, so that every hop
+    /// can be reported with its headers and timings. Like reqwest, a 301, 302
+    /// or 303 turns the request into a body-less `GET` (`HEAD` stays `HEAD`),
+    /// 307 and 308 resend it unchanged, and credentials are dropped once the
+    /// request leaves its original host.
+    ///
+    /// The request's auth is applied here. An OAuth 2.0 token is obtained or
+    /// renewed as needed, and renewed once more if the server answers 401. A
+    /// JWT is signed for each execution, and a Digest challenge is answered
+    /// with a second request.
+    ///
+    /// # Arguments
+    /// * `request` - The request to execute
+    /// * `context` - Settings inherited from the collection and application
+    pub async fn execute_request_with_context(
         &self,
         request: &Request,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
-        let start_time = Instant::now();
-
-        // Build the request based on HTTP method
-        let mut req_builder = match request.method {
-            HttpMethod::Get => self.client.get(&request.url),
-            HttpMethod::Post => self.client.post(&request.url),
-            HttpMethod::Put => self.client.put(&request.url),
-            HttpMethod::Patch => self.client.patch(&request.url),
-            HttpMethod::Delete => self.client.delete(&request.url),
-            HttpMethod::Head => self.client.head(&request.url),
-            HttpMethod::Options => self.client.request(reqwest::Method::OPTIONS, &request.url),
+        context: &ExecutionContext,
+    ) -> HTTPServiceResult<Response> {
+        let config = ClientConfig::for_request(request, context);
+        let client = self.client_for(&config)?;
+        let auth = request.auth.inherit_from(&context.auth);
+
+        let oauth2 = match auth {
+            Auth::OAuth2(oauth2) => oauth2,
+            Auth::Jwt(jwt) => {
+                let token = jwt::encode(jwt, Utc::now())
+                    .map_err(|e| HTTPServiceError::InvalidRequest(e.to_string()))?;
+                return self
+                    .send(
+                        &client,
+                        &config,
+                        request,
+                        context,
+                        auth,
+                        Some(bearer(&token)),
+                    )
+                    .await;
+            }
+            auth => {
+                return self
+                    .send(&client, &config, request, context, auth, auth.header())
+                    .await;
+            }
         };
 
-        // Add headers
-        for (key, value) in &request.headers {
-            req_builder = req_builder.header(key, value);
+        let collection = context.collection_path.as_deref();
+        let token = self
+            .oauth2_tokens
+            .access_token(&client, oauth2, collection, false)
+            .await?;
+        let response = self
+            .send(
+                &client,
+                &config,
+                request,
+                context,
+                auth,
+                Some(bearer(&token)),
+            )
+            .await?;
+        if response.status != 401 {
+            return Ok(response);
+        }
+
+        // The token may have been revoked; the 401 stands if it cannot be
+        // renewed
+        match self
+            .oauth2_tokens
+            .access_token(&client, oauth2, collection, true)
+            .await
+        {
+            Ok(token) => {
+                self.send(
+                    &client,
+                    &config,
+                    request,
+                    context,
+                    auth,
+                    Some(bearer(&token)),
+                )
+                .await
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to renew OAuth 2.0 token: {}", e);
+                Ok(response)
+            }
+        }
+    }
+
+    /// Send a request, following redirects
+    ///
+    /// With Digest auth, a hop is sent without credentials first; a 401 with
+    /// a Digest challenge is answered by sending that hop again, so the digest
+    /// covers the URL and method that were challenged. Each realm is answered
+    /// once per URL, so a redirect into another protection space is answered
+    /// too, while rejected credentials end with the 401. A user-set
+    /// `Authorization` header is sent as it is.
+    ///
+    /// # Arguments
+    /// * `auth` - Auth that applies to the request
+    /// * `auth_header` - Header carrying the credentials
+    async fn send(
+        &self,
+        client: &reqwest::Client,
+        config: &ClientConfig,
+        request: &Request,
+        context: &ExecutionContext,
+        auth: &Auth,
+        auth_header: Option<(String, String)>,
+    ) -> HTTPServiceResult<Response> {
+        // Custom methods are sent verbatim
+        let mut method =
+            reqwest::Method::from_bytes(request.method.as_str().as_bytes()).map_err(|_| {
+                HTTPServiceError::InvalidRequest(format!("Invalid HTTP method: {}", request.method))
+            })?;
+        let mut url = request.build_url();
+        if let Some(param) = auth.query_param() {
+            url = params::append_query(&url, &params::encode_query(&[param]));
         }
+        let mut with_body = true;
+        let mut same_host = true;
+        let mut redirects = Vec::new();
+        // Answers to Digest challenges by the URL they were computed for, and
+        // the URL and realm of each challenge answered
+        let mut digests: HashMap<String, String> = HashMap::new();
+        let mut challenges: HashSet<(String, String)> = HashSet::new();
+
+        let sent_at = Instant::now();
+        loop {
+            // Certificates are chosen by host, so a redirect to another host
+            // gets the client for that host
+            let hop_config = ClientConfig::for_url(&url, request, context);
+            let hop_client = if hop_config == *config {
+                client.clone()
+            } else {
+                self.client_for(&hop_config)?
+            };
+            let mut req_builder = hop_client.request(method.clone(), &url);
+
+            // Add headers; repeated names are sent as separate header lines. A
+            // multipart body sets its own Content-Type carrying the boundary.
+            let is_multipart = with_body && matches!(request.body, RequestBody::Multipart { .. });
+            for header in &request.headers {
+                let name = header.key.to_ascii_lowercase();
+                if (is_multipart && name == "content-type")
+                    || (!with_body && BODY_HEADERS.contains(&name.as_str()))
+                    || (!same_host && SENSITIVE_HEADERS.contains(&name.as_str()))
+                {
+                    continue;
+                }
+                req_builder = req_builder.header(&header.key, &header.value);
+            }
+
+            // Credentials stay with the original host, and a header the user
+            // set explicitly takes precedence
+            if let Some((name, value)) = auth_header
+                .as_ref()
+                .filter(|(name, _)| same_host && !request.headers.contains_key(name))
+            {
+                req_builder = req_builder.header(name, value);
+            }
+            if let Some(authorization) = digests.get(&url).filter(|_| same_host) {
+                req_builder = req_builder.header(reqwest::header::AUTHORIZATION, authorization);
+            }
+
+            // Stored cookies are added unless the user sends their own
+            let user_cookie = same_host && request.headers.contains_key("cookie");
+            let cookie_header = context
+                .cookie_jar
+                .as_ref()
+                .filter(|_| !user_cookie)
+                .zip(url::Url::parse(&url).ok())
+                .and_then(|(jar, url)| jar.cookie_header(&url));
+            if let Some(cookie_header) = cookie_header {
+                req_builder = req_builder.header(reqwest::header::COOKIE, cookie_header);
+            }
+
+            if with_body {
+                // Default the Content-Type from the body unless the user set one
+                if !request.headers.contains_key("content-type") {
+                    if let Some(content_type) = request.body.content_type() {
+                        req_builder =
+                            req_builder.header(reqwest::header::CONTENT_TYPE, content_type);
+                    }
+                }
+                req_builder = attach_body(req_builder, &request.body).await?;
+            }
+
+            // The timeout covers the whole redirect chain
+            req_builder = req_builder.timeout(config.timeout.saturating_sub(sent_at.elapsed()));
+
+            // Execute the request, recording connection phases as it goes
+            let recorder = PhaseRecorder::default();
+            let hop_sent_at = Instant::now();
+            let mut hop = req_builder.build()?;
+
+            // Signatures cover the final URL, headers and body, so they are
+            // added last
+            match auth {
+                Auth::AwsSigV4(aws)
+                    if same_host && !request.headers.contains_key("authorization") =>
+                {
+                    buffer_body(&mut hop).await?;
+                    sigv4::sign(&mut hop, aws, Utc::now())
+                        .map_err(|e| HTTPServiceError::InvalidRequest(e.to_string()))?;
+                }
+                Auth::Hmac(hmac) if same_host && !request.headers.contains_key(&hmac.header) => {
+                    buffer_body(&mut hop).await?;
+                    signature::sign(&mut hop, hmac, Utc::now()).map_err(|e| {
+                        HTTPServiceError::InvalidRequest(format!("Failed to sign request: {}", e))
+                    })?;
+                }
+                _ => {}
+            }
+
+            let response = recorder.scope(hop_client.execute(hop)).await.map_err(|e| {
+                match TlsFailure::from_error(&e, recorder.phases().handshake.as_ref()) {
+                    Some(failure) => HTTPServiceError::Tls(failure),
+                    None => e.into(),
+                }
+            })?;
+            let headers_at = Instant::now();
+
+            let status = response.status();
+            let headers = response_headers(&response);
+            if let Some(jar) = &context.cookie_jar {
+                let set_cookie = response
+                    .headers()
+                    .get_all(reqwest::header::SET_COOKIE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok());
+                // A jar that cannot be saved must not fail the request
+                if let Err(e) = jar.store_response_cookies(response.url(), set_cookie) {
+                    eprintln!("Warning: Failed to save cookies: {}", e);
+                }
+            }
+            let phases = recorder.phases();
+            let connection = ConnectionInfo {
+                http_version: format!("{:?}", response.version()),
+                remote_addr: response.remote_addr(),
+                reused: phases.reused(),
+                tls: self.tls_info(response.url(), phases.handshake.as_ref()),
+            };
+
+            if let Auth::Digest { username, password } = auth {
+                let challenge = DigestChallenge::from_headers(&headers).filter(|challenge| {
+                    status == reqwest::StatusCode::UNAUTHORIZED
+                        && same_host
+                        && !request.headers.contains_key("authorization")
+                        && challenges.insert((url.clone(), challenge.realm.clone()))
+                });
+                if let Some(challenge) = challenge {
+                    // The digest covers the request target, i.e. the path and
+                    // query of the hop that was challenged
+                    let hop_url = response.url();
+                    let uri = match hop_url.query() {
+                        Some(query) => format!("{}?{}", hop_url.path(), query),
+                        None => hop_url.path().to_string(),
+                    };
+                    let authorization =
+                        challenge.authorization(username, password, method.as_str(), &uri);
+                    digests.insert(url.clone(), authorization);
+
+                    // Read the body so the connection can be reused
+                    response.bytes().await?;
+                    continue;
+                }
+            }
 
-        // Add body if present
-        if let Some(body) = &request.body {
-            req_builder = req_builder.body(body.clone());
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .map(header_value_to_string)
+                .filter(|_| config.follow_redirects && is_followed_redirect(status));
+
+            if let Some(location) = location {
+                if redirects.len() >= config.max_redirects as usize {
+                    return Err(HTTPServiceError::RequestFailed(format!(
+                        "Too many redirects (limit {}) at {}",
+                        config.max_redirects,
+                        response.url()
+                    )));
+                }
+
+                let hop_url = response.url().clone();
+                let next = hop_url.join(&location).map_err(|e| {
+                    HTTPServiceError::RequestFailed(format!(
+                        "Invalid redirect location '{}': {}",
+                        location, e
+                    ))
+                })?;
+
+                // Read the body so the connection can be reused
+                response.bytes().await?;
+                let finished_at = Instant::now();
+
+                redirects.push(Redirect {
+                    method: method.to_string(),
+                    url: hop_url.to_string(),
+                    status: status.as_u16(),
+                    location,
+                    headers,
+                    response_time: finished_at.duration_since(hop_sent_at),
+                    timings: phases.timings(hop_sent_at, headers_at, finished_at),
+                    connection: Some(connection),
+                });
+
+                if matches!(status.as_u16(), 301..=303) {
+                    with_body = false;
+                    if method != reqwest::Method::GET && method != reqwest::Method::HEAD {
+                        method = reqwest::Method::GET;
+                    }
+                }
+                same_host &= next.host_str() == hop_url.host_str()
+                    && next.port_or_known_default() == hop_url.port_or_known_default();
+                url = next.to_string();
+                continue;
+            }
+
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+
+            // Extract the raw body; decoding is left to the model so binary
+            // payloads survive byte-for-byte
+            let body = response.bytes().await?;
+            let finished_at = Instant::now();
+
+            return Ok(Response::from_bytes(
+                status.as_u16(),
+                body.to_vec(),
+                content_type.as_deref(),
+                finished_at.duration_since(sent_at),
+            )
+            .with_headers(headers)
+            .with_timings(phases.timings(hop_sent_at, headers_at, finished_at))
+            .with_connection(connection)
+            .with_redirects(redirects));
         }
+    }
+}
 
-        // Execute the request
-        let response = req_builder.send().await?;
+/// Build the `Authorization` header for an OAuth 2.0 access token or a JWT
+fn bearer(token: &str) -> (String, String) {
+    ("Authorization".to_string(), format!("Bearer {}", token))
+}
 
-        // Capture response time
-        let response_time = start_time.elapsed();
+/// Check whether a status code is a redirect the service follows
+fn is_followed_redirect(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 301 | 302 | 303 | 307 | 308)
+}
 
-        // Extract status code
-        let status = response.status().as_u16();
+/// Extract response headers, keeping repeated names and values that are not
+/// valid UTF-8
+fn response_headers(response: &reqwest::Response) -> Headers {
+    response
+        .headers()
+        .iter()
+        .map(|(key, value)| (key.as_str(), header_value_to_string(value)))
+        .collect()
+}
 
-        // Extract headers
-        let mut headers = HashMap::new();
-        for (key, value) in response.headers() {
-            if let Ok(value_str) = value.to_str() {
-                headers.insert(key.to_string(), value_str.to_string());
+/// Attach a request body to a request builder
+///
+/// Files referenced by binary and multipart bodies are read when the request
+/// is sent, so the latest contents are uploaded.
+async fn attach_body(
+    builder: reqwest::RequestBuilder,
+    body: &RequestBody,
+) -> HTTPServiceResult<reqwest::RequestBuilder> {
+    let builder = match body {
+        RequestBody::None => builder,
+        RequestBody::Raw { content, .. } | RequestBody::Json { content } => {
+            builder.body(content.clone())
+        }
+        RequestBody::FormUrlencoded { fields } => {
+            let encoded = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(
+                    fields
+                        .iter()
+                        .filter(|field| field.enabled)
+                        .map(|field| (&field.key, &field.value)),
+                )
+                .finish();
+            builder.body(encoded)
+        }
+        RequestBody::Multipart { parts } => {
+            let mut form = reqwest::multipart::Form::new();
+            for part in parts.iter().filter(|part| part.is_enabled()) {
+                form = match part {
+                    MultipartPart::Text {
+                        name,
+                        value,
+                        content_type,
+                        ..
+                    } => {
+                        let mut text = reqwest::multipart::Part::text(value.clone());
+                        if let Some(content_type) = content_type {
+                            text = with_mime(text, content_type)?;
+                        }
+                        form.part(name.clone(), text)
+                    }
+                    MultipartPart::File {
+                        name,
+                        path,
+                        file_name,
+                        content_type,
+                        ..
+                    } => {
+                        let file_name = file_name.clone().or_else(|| {
+                            std::path::Path::new(path)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                        });
+                        let mut file = reqwest::multipart::Part::bytes(read_file(path).await?);
+                        if let Some(file_name) = file_name {
+                            file = file.file_name(file_name);
+                        }
+                        let content_type =
+                            content_type.as_deref().unwrap_or_else(|| guess_mime(path));
+                        form.part(name.clone(), with_mime(file, content_type)?)
+                    }
+                };
             }
+            builder.multipart(form)
         }
+        RequestBody::Binary { path, .. } => builder.body(read_file(path).await?),
+    };
+
+    Ok(builder)
+}
+
+/// Read a streamed body, such as a multipart form, into memory so it can be
+/// hashed
+async fn buffer_body(request: &mut reqwest::Request) -> HTTPServiceResult<()> {
+    if let Some(body) = request.body_mut().take_if(|body| body.as_bytes().is_none()) {
+        let bytes = body.collect().await?.to_bytes();
+        *request.body_mut() = Some(bytes.into());
+    }
+    Ok(())
+}
+
+/// Set the content type of a multipart part
+fn with_mime(
+    part: reqwest::multipart::Part,
+    content_type: &str,
+) -> HTTPServiceResult<reqwest::multipart::Part> {
+    part.mime_str(content_type).map_err(|e| {
+        HTTPServiceError::InvalidRequest(format!("Invalid content type '{}': {}", content_type, e))
+    })
+}
 
-        // Extract body
-        let body = response.text().await?;
+/// Read a file referenced by a request body
+async fn read_file(path: &str) -> HTTPServiceResult<Vec<u8>> {
+    tokio::fs::read(path).await.map_err(|e| {
+        HTTPServiceError::InvalidRequest(format!("Failed to read file '{}': {}", path, e))
+    })
+}
 
-        Ok(Response::new(status, body, response_time).with_headers(headers))
+/// Convert a header value to a string without dropping non-UTF-8 values
+///
+/// Bytes outside ASCII are decoded as ISO-8859-1, the historical header
+/// charset, which maps every byte to exactly one character.
+fn header_value_to_string(value: &reqwest::header::HeaderValue) -> String {
+    match value.to_str() {
+        Ok(value) => value.to_string(),
+        Err(_) => value.as_bytes().iter().map(|&byte| byte as char).collect(),
     }
 }
 
@@ -78,7 +1118,14 @@ impl Default for HTTPService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{body_string, header, method, path};
+    use crate::models::{
+        ApiKeyLocation, AwsSigV4Config, HmacConfig, HttpMethod, JwtAlgorithm, JwtConfig, KeyValue,
+        ManualProxy,
+    };
+    use wiremock::matchers::{
+        body_bytes, body_string, body_string_contains, header, header_exists, header_regex,
+        headers, method, path, query_param, query_param_is_missing,
+    };
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -87,6 +1134,108 @@ mod tests {
         assert!(service.is_ok());
     }
 
+    #[test]
+    fn test_client_for_reuses_clients() {
+        let service = HTTPService::new().unwrap();
+        assert_eq!(service.client_count(), 1);
+
+        service.client_for(&ClientConfig::default()).unwrap();
+        assert_eq!(service.client_count(), 1);
+
+        let custom = ClientConfig {
+            timeout: Duration::from_secs(5),
+            ..Default::default()
+        };
+        service.client_for(&custom).unwrap();
+        service.client_for(&custom).unwrap();
+        assert_eq!(service.client_count(), 2);
+
+        // The least recently used clients make room for new ones
+        for seconds in 10..10 + MAX_CLIENTS as u64 {
+            service.client_for(&ClientConfig::default()).unwrap();
+            service
+                .client_for(&ClientConfig {
+                    timeout: Duration::from_secs(seconds),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert_eq!(service.client_count(), MAX_CLIENTS);
+        let clients = service.clients.read().unwrap();
+        assert!(clients.contains_key(&ClientConfig::default()));
+        assert!(!clients.contains_key(&custom));
+    }
+
+    #[test]
+    fn test_client_for_reports_poisoned_cache() {
+        let service = HTTPService::new().unwrap();
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _clients = service.clients.write().unwrap();
+            panic!("poison the client cache");
+        }));
+
+        assert!(matches!(
+            service.client_for(&ClientConfig::default()),
+            Err(HTTPServiceError::RequestFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_config_from_settings() {
+        assert_eq!(ClientConfig::from_settings(None), ClientConfig::default());
+
+        let settings = RequestSettings {
+            connect_timeout_ms: Some(250),
+            timeout_ms: Some(1500),
+            follow_redirects: Some(false),
+            max_redirects: Some(3),
+            verify_tls: Some(false),
+            proxy: Some(ProxyConfig::None),
+        };
+        let config = ClientConfig::from_settings(Some(&settings));
+        assert_eq!(config.connect_timeout, Some(Duration::from_millis(250)));
+        assert_eq!(config.timeout, Duration::from_millis(1500));
+        assert!(!config.follow_redirects);
+        assert_eq!(config.max_redirects, 3);
+        assert!(!config.verify_tls);
+        assert_eq!(config.proxy, ProxyConfig::None);
+
+        // Unset fields keep their defaults
+        let settings = RequestSettings {
+            timeout_ms: Some(1000),
+            ..Default::default()
+        };
+        let config = ClientConfig::from_settings(Some(&settings));
+        assert!(config.follow_redirects);
+        assert_eq!(config.max_redirects, DEFAULT_MAX_REDIRECTS);
+        assert!(config.verify_tls);
+    }
+
+    #[test]
+    fn test_proxy_precedence() {
+        let request = Request::new("Test", "https://example.com");
+        let mut context = ExecutionContext::default();
+        assert_eq!(context.proxy_for(&request), ProxyConfig::System);
+
+        let app_proxy = ProxyConfig::Manual(ManualProxy::new("http://app-proxy:3128"));
+        context.app.proxy = app_proxy.clone();
+        assert_eq!(context.proxy_for(&request), app_proxy);
+
+        context.collection.proxy = Some(ProxyConfig::None);
+        assert_eq!(context.proxy_for(&request), ProxyConfig::None);
+
+        let request_proxy = ProxyConfig::Manual(ManualProxy::new("socks5h://request-proxy:1080"));
+        let request = request.with_settings(RequestSettings {
+            proxy: Some(request_proxy.clone()),
+            ..Default::default()
+        });
+        assert_eq!(context.proxy_for(&request), request_proxy);
+        assert_eq!(
+            ClientConfig::for_request(&request, &context).proxy,
+            request_proxy
+        );
+    }
+
     #[tokio::test]
     async fn test_get_request() {
         let mock_server = MockServer::start().await;
@@ -264,26 +1413,1368 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_response_timing() {
+    async fn test_binary_response_preserved() {
         let mock_server = MockServer::start().await;
+        let payload: Vec<u8> = (0..=255).collect();
 
         Mock::given(method("GET"))
-            .and(path("/delay"))
-            .respond_with(
-                ResponseTemplate::new(200)
-                    .set_body_string(r#"{"delayed":true}"#)
-                    .set_delay(std::time::Duration::from_secs(1)),
-            )
+            .and(path("/download"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(payload.clone(), "image/png"))
             .mount(&mock_server)
             .await;
 
         let service = HTTPService::new().unwrap();
-        let request = Request::new("Test Timing", format!("{}/delay", mock_server.uri()))
-            .with_method(HttpMethod::Get);
+        let request = Request::new("Download", format!("{}/download", mock_server.uri()));
 
         let response = service.execute_request(&request).await.unwrap();
 
-        // Should take at least 1 second
-        assert!(response.response_time.as_secs() >= 1);
+        assert!(response.is_binary);
+        assert_eq!(response.content_type.as_deref(), Some("image/png"));
+        assert_eq!(response.body_bytes, payload);
+    }
+
+    #[tokio::test]
+    async fn test_text_response_charset_decoding() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/latin1"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                vec![0x6e, 0x61, 0xef, 0x76, 0x65],
+                "text/plain; charset=latin1",
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Latin-1", format!("{}/latin1", mock_server.uri()));
+
+        let response = service.execute_request(&request).await.unwrap();
+
+        assert!(!response.is_binary);
+        assert_eq!(response.body, "naïve");
+        assert_eq!(response.content_type.as_deref(), Some("text/plain"));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_response_headers_preserved() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cookies"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .append_header("set-cookie", "a=1")
+                    .append_header("set-cookie", "b=2")
+                    .append_header("x-legacy", b"caf\xe9".as_slice()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Cookies", format!("{}/cookies", mock_server.uri()));
+
+        let response = service.execute_request(&request).await.unwrap();
+
+        assert_eq!(response.headers.get_all("Set-Cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(response.headers.get("x-legacy"), Some("café"));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_request_headers_sent() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/multi"))
+            .and(headers("x-tag", vec!["one", "two"]))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Multi", format!("{}/multi", mock_server.uri()))
+            .with_header("X-Tag", "one")
+            .with_header("X-Tag", "two");
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_json_body_sets_content_type() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/json"))
+            .and(header("content-type", "application/json"))
+            .and(body_string(r#"{"a":1}"#))
+            .respond_with(ResponseTemplate::new(201))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/json"))
+            .and(header("content-type", "application/vnd.api+json"))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Json", format!("{}/json", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_body(RequestBody::json(r#"{"a":1}"#));
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 201);
+
+        // An explicit header wins over the body default
+        let request = request.with_header("Content-Type", "application/vnd.api+json");
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 202);
+    }
+
+    #[tokio::test]
+    async fn test_form_urlencoded_body() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/form"))
+            .and(header("content-type", "application/x-www-form-urlencoded"))
+            .and(body_string("q=rust+lang&sym=%26%3D"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Form", format!("{}/form", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_body(RequestBody::FormUrlencoded {
+                fields: vec![
+                    KeyValue::new("q", "rust lang"),
+                    KeyValue::new("page", "2").disabled(),
+                    KeyValue::new("sym", "&="),
+                ],
+            });
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_body_with_file() {
+        let mock_server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "file contents").unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/upload"))
+            .and(header_regex(
+                "content-type",
+                "^multipart/form-data; boundary=.+",
+            ))
+            .and(body_string_contains("name=\"title\"\r\n\r\nHoliday"))
+            .and(body_string_contains("filename=\"notes.txt\""))
+            .and(body_string_contains("Content-Type: text/plain"))
+            .and(body_string_contains("file contents"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Upload", format!("{}/upload", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            // Must not clash with the generated boundary header
+            .with_header("Content-Type", "multipart/form-data")
+            .with_body(RequestBody::Multipart {
+                parts: vec![
+                    MultipartPart::text("title", "Holiday"),
+                    MultipartPart::file("attachment", file_path.to_string_lossy()),
+                ],
+            });
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_binary_file_body() {
+        let mock_server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("image.png");
+        let bytes = vec![0x89, b'P', b'N', b'G', 0x00, 0xff];
+        std::fs::write(&file_path, &bytes).unwrap();
+
+        Mock::given(method("PUT"))
+            .and(path("/blob"))
+            .and(header("content-type", "image/png"))
+            .and(body_bytes(bytes))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let body = RequestBody::Binary {
+            path: file_path.to_string_lossy().into_owned(),
+            content_type: None,
+        };
+        let request = Request::new("Blob", format!("{}/blob", mock_server.uri()))
+            .with_method(HttpMethod::Put)
+            .with_body(body);
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 204);
+
+        let missing = request.with_body(RequestBody::Binary {
+            path: dir
+                .path()
+                .join("missing.bin")
+                .to_string_lossy()
+                .into_owned(),
+            content_type: None,
+        });
+        let result = service.execute_request(&missing).await;
+        assert!(matches!(result, Err(HTTPServiceError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_query_and_path_params_sent() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/users/a%20b"))
+            .and(query_param("q", "x&y"))
+            .and(query_param("page", "2"))
+            .and(query_param_is_missing("debug"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let mut request = Request::new("Params", format!("{}/users/:id", mock_server.uri()))
+            .with_path_param("id", "a b")
+            .with_query_param("q", "x&y")
+            .with_query_param("debug", "1")
+            .with_query_param("page", "2");
+        request.query_params[1].enabled = false;
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_custom_methods_sent_verbatim() {
+        let mock_server = MockServer::start().await;
+
+        for verb in ["PROPFIND", "MKCOL", "TRACE", "PURGE"] {
+            Mock::given(method(verb))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(207).insert_header("x-verb", verb))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let service = HTTPService::new().unwrap();
+        for verb in ["PROPFIND", "MKCOL", "TRACE", "PURGE"] {
+            let request = Request::new(verb, format!("{}/resource", mock_server.uri()))
+                .with_method(HttpMethod::from(verb));
+
+            let response = service.execute_request(&request).await.unwrap();
+            assert_eq!(response.status, 207);
+            assert_eq!(response.headers.get("x-verb"), Some(verb));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_timing() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/delay"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"delayed":true}"#)
+                    .set_delay(std::time::Duration::from_secs(1)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Test Timing", format!("{}/delay", mock_server.uri()))
+            .with_method(HttpMethod::Get);
+
+        let response = service.execute_request(&request).await.unwrap();
+
+        // Should take at least 1 second, spent waiting for the first byte
+        assert!(response.response_time.as_secs() >= 1);
+        assert!(response.timings.ttfb >= Duration::from_secs(1));
+        assert!(response.timings.ttfb <= response.response_time);
+    }
+
+    #[tokio::test]
+    async fn test_timing_breakdown_and_connection_reuse() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/timed"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        // Use a host name so the lookup goes through the resolver
+        let url = format!("http://localhost:{}/timed", mock_server.address().port());
+        let request = Request::new("Timed", url);
+
+        let first = service.execute_request(&request).await.unwrap();
+        let connection = first.connection.clone().unwrap();
+        assert!(!connection.reused);
+        assert_eq!(connection.http_version, "HTTP/1.1");
+        assert_eq!(connection.remote_addr, Some(*mock_server.address()));
+        assert!(first.timings.dns.is_some());
+        assert!(first.timings.tcp.is_some());
+        assert!(first.timings.tls.is_none());
+
+        let second = service.execute_request(&request).await.unwrap();
+        assert!(second.connection.unwrap().reused);
+        assert!(second.timings.dns.is_none());
+        assert!(second.timings.tcp.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_does_not_block_others() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/fast"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = std::sync::Arc::new(HTTPService::new().unwrap());
+        let slow = Request::new("Slow", format!("{}/slow", mock_server.uri()));
+        let fast = Request::new("Fast", format!("{}/fast", mock_server.uri()));
+
+        let slow_service = std::sync::Arc::clone(&service);
+        let slow_handle =
+            tokio::spawn(async move { slow_service.execute_request(&slow).await.is_ok() });
+
+        // Give the slow request a head start so it is in flight first
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let start = Instant::now();
+        let response = service.execute_request(&fast).await.unwrap();
+        assert!(response.is_success());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!slow_handle.is_finished());
+
+        assert!(slow_handle.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_setting() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Slow", format!("{}/slow", mock_server.uri())).with_settings(
+            RequestSettings {
+                timeout_ms: Some(200),
+                ..Default::default()
+            },
+        );
+
+        let start = Instant::now();
+        let result = service.execute_request(&request).await;
+        assert!(matches!(result, Err(HTTPServiceError::RequestFailed(_))));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_settings() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/old"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", "/new"))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/new"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("moved"))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let url = format!("{}/old", mock_server.uri());
+
+        // Redirects are followed by default
+        let response = service
+            .execute_request(&Request::new("Follow", &url))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "moved");
+
+        // ...and not followed when disabled
+        let request = Request::new("No Follow", &url).with_settings(RequestSettings {
+            follow_redirects: Some(false),
+            ..Default::default()
+        });
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 302);
+        assert!(response.redirects.is_empty());
+
+        // Exceeding the redirect limit is an error
+        let request = Request::new("Limited", &url).with_settings(RequestSettings {
+            max_redirects: Some(0),
+            ..Default::default()
+        });
+        assert!(service.execute_request(&request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_chain_is_recorded() {
+        let mock_server = MockServer::start().await;
+        let port = mock_server.address().port();
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .and(body_string(r#"{"user":"alice"}"#))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", "/step"))
+            .mount(&mock_server)
+            .await;
+
+        // A 302 turns the POST into a GET without a body, keeping
+        // credentials on the same host
+        Mock::given(method("GET"))
+            .and(path("/step"))
+            .and(header("authorization", "Bearer t0ken"))
+            .and(|request: &wiremock::Request| {
+                request.body.is_empty() && !request.headers.contains_key("content-type")
+            })
+            .respond_with(
+                ResponseTemplate::new(307)
+                    .insert_header("location", format!("http://localhost:{}/final", port)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // Credentials are dropped once the request leaves the host
+        Mock::given(method("GET"))
+            .and(path("/final"))
+            .and(|request: &wiremock::Request| !request.headers.contains_key("authorization"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("welcome"))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Login", format!("{}/login", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_header("Authorization", "Bearer t0ken")
+            .with_body(RequestBody::json(r#"{"user":"alice"}"#));
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "welcome");
+        assert_eq!(response.redirects.len(), 2);
+
+        let first = &response.redirects[0];
+        assert_eq!(first.method, "POST");
+        assert_eq!(first.url, format!("{}/login", mock_server.uri()));
+        assert_eq!(first.status, 302);
+        assert_eq!(first.location, "/step");
+        assert_eq!(first.headers.get("location"), Some("/step"));
+        assert!(!first.connection.as_ref().unwrap().reused);
+
+        let second = &response.redirects[1];
+        assert_eq!(second.method, "GET");
+        assert_eq!(second.status, 307);
+        assert_eq!(second.location, format!("http://localhost:{}/final", port));
+        // The second hop goes out on the pooled connection
+        assert!(second.connection.as_ref().unwrap().reused);
+        assert!(second.response_time <= response.response_time);
+    }
+
+    #[tokio::test]
+    async fn test_auth_applied_at_send_time() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/basic"))
+            .and(header("authorization", "Basic YWxpY2U6cHc="))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/bearer"))
+            .and(header("authorization", "Bearer t0ken"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/key"))
+            .and(query_param("api_key", "k3y"))
+            .and(|request: &wiremock::Request| !request.headers.contains_key("authorization"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/header-key"))
+            .and(header("x-api-key", "k3y"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/explicit"))
+            .and(header("authorization", "Bearer mine"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let context = ExecutionContext {
+            auth: Auth::bearer("t0ken"),
+            ..Default::default()
+        };
+        let url = |path: &str| format!("{}{}", mock_server.uri(), path);
+        let status = |request: Request| {
+            let service = &service;
+            let context = &context;
+            async move {
+                service
+                    .execute_request_with_context(&request, context)
+                    .await
+                    .unwrap()
+                    .status
+            }
+        };
+
+        let basic = Request::new("Basic", url("/basic")).with_auth(Auth::basic("alice", "pw"));
+        assert_eq!(status(basic.clone()).await, 200);
+        // Credentials are not written into the request's headers
+        assert!(basic.headers.is_empty());
+
+        // Requests inherit the collection's auth by default
+        assert_eq!(status(Request::new("Bearer", url("/bearer"))).await, 200);
+        let none = Request::new("None", url("/bearer")).with_auth(Auth::None);
+        assert_eq!(status(none).await, 404);
+
+        let query = Request::new("Key", url("/key")).with_auth(Auth::api_key(
+            "api_key",
+            "k3y",
+            ApiKeyLocation::Query,
+        ));
+        assert_eq!(status(query).await, 200);
+        let header_key = Request::new("Key", url("/header-key")).with_auth(Auth::api_key(
+            "X-Api-Key",
+            "k3y",
+            ApiKeyLocation::Header,
+        ));
+        assert_eq!(status(header_key).await, 200);
+
+        // A header set explicitly takes precedence
+        let explicit =
+            Request::new("Explicit", url("/explicit")).with_header("Authorization", "Bearer mine");
+        assert_eq!(status(explicit).await, 200);
+    }
+
+    #[tokio::test]
+    async fn test_aws_sigv4_signed_at_send_time() {
+        let mock_server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("object.bin");
+        std::fs::write(&file_path, b"hello").unwrap();
+
+        Mock::given(method("PUT"))
+            .and(path("/bucket/object.bin"))
+            // SHA-256 of the file contents
+            .and(header(
+                "x-amz-content-sha256",
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+            ))
+            .and(header("x-amz-security-token", "t0ken"))
+            .and(|request: &wiremock::Request| {
+                request.headers["authorization"]
+                    .to_str()
+                    .unwrap()
+                    .starts_with("AWS4-HMAC-SHA256 Credential=AKID/")
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let aws =
+            AwsSigV4Config::new("AKID", "s3cret", "us-east-1", "s3").with_session_token("t0ken");
+        let request = Request::new("Upload", format!("{}/bucket/object.bin", mock_server.uri()))
+            .with_method(HttpMethod::Put)
+            .with_body(RequestBody::Binary {
+                path: file_path.to_string_lossy().into_owned(),
+                content_type: None,
+            })
+            .with_auth(Auth::AwsSigV4(aws));
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+        // The signature is not written into the request's headers
+        assert!(request.headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_aws_sigv4_hashes_multipart_body() {
+        use sha2::{Digest, Sha256};
+
+        let mock_server = MockServer::start().await;
+        // The payload hash covers the body as it was sent
+        Mock::given(path("/s3"))
+            .and(|request: &wiremock::Request| {
+                request.headers["x-amz-content-sha256"]
+                    == hex::encode(Sha256::digest(&request.body)).as_str()
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/api"))
+            .and(|request: &wiremock::Request| {
+                request.headers.contains_key("authorization")
+                    && String::from_utf8_lossy(&request.body).contains("Holiday")
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let upload = |path: &str, service_name: &str| {
+            Request::new("Upload", format!("{}{}", mock_server.uri(), path))
+                .with_method(HttpMethod::Post)
+                .with_body(RequestBody::Multipart {
+                    parts: vec![MultipartPart::text("title", "Holiday")],
+                })
+                .with_auth(Auth::AwsSigV4(AwsSigV4Config::new(
+                    "AKID",
+                    "s3cret",
+                    "us-east-1",
+                    service_name,
+                )))
+        };
+
+        let response = service.execute_request(&upload("/s3", "s3")).await.unwrap();
+        assert_eq!(response.status, 200);
+        let response = service
+            .execute_request(&upload("/api", "execute-api"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_digest_challenge_answered() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/protected"))
+            .and(|request: &wiremock::Request| {
+                request.headers.get("authorization").is_some_and(|value| {
+                    let value = value.to_str().unwrap();
+                    value.starts_with("Digest username=\"alice\", realm=\"api\"")
+                        && value.contains("uri=\"/protected?page=2\"")
+                        && value.contains("algorithm=SHA-256")
+                        && value.contains("opaque=\"0p4que\"")
+                })
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/protected"))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "www-authenticate",
+                "Digest realm=\"api\", qop=\"auth\", algorithm=SHA-256, nonce=\"n0nce\", opaque=\"0p4que\"",
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/open"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let digest = Auth::Digest {
+            username: "alice".to_string(),
+            password: "pw".to_string(),
+        };
+        let request = Request::new("Digest", format!("{}/protected", mock_server.uri()))
+            .with_query_param("page", "2")
+            .with_auth(digest.clone());
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+
+        // No challenge, no second request
+        let open = Request::new("Open", format!("{}/open", mock_server.uri())).with_auth(digest);
+        let response = service.execute_request(&open).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_digest_challenge_answered_after_redirect() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/start"))
+            .respond_with(ResponseTemplate::new(303).insert_header("location", "/protected"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/protected"))
+            .and(|request: &wiremock::Request| {
+                request.headers.get("authorization").is_some_and(|value| {
+                    let value = value.to_str().unwrap();
+                    value.starts_with("Digest username=\"alice\"")
+                        && value.contains("uri=\"/protected\"")
+                })
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/protected"))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "www-authenticate",
+                "Digest realm=\"api\", qop=\"auth\", nonce=\"n0nce\"",
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // The challenged hop is answered; the request is not sent again
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Digest", format!("{}/start", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_auth(Auth::Digest {
+                username: "alice".to_string(),
+                password: "pw".to_string(),
+            });
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.redirects.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_digest_challenge_of_each_realm_answered() {
+        fn answers(uri: &'static str, realm: &'static str) -> impl wiremock::Match {
+            move |request: &wiremock::Request| {
+                request.headers.get("authorization").is_some_and(|value| {
+                    let value = value.to_str().unwrap();
+                    value.contains(&format!("realm=\"{}\"", realm))
+                        && value.contains(&format!("uri=\"{}\"", uri))
+                })
+            }
+        }
+        fn challenge(realm: &str) -> ResponseTemplate {
+            ResponseTemplate::new(401).insert_header(
+                "www-authenticate",
+                format!("Digest realm=\"{}\", qop=\"auth\", nonce=\"n0nce\"", realm),
+            )
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/start"))
+            .and(answers("/start", "api"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", "/admin"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/start"))
+            .respond_with(challenge("api"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/admin"))
+            .and(answers("/admin", "admin"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/admin"))
+            .respond_with(challenge("admin"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/denied"))
+            .respond_with(challenge("api"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // The redirect leads into another realm, which is answered as well
+        let service = HTTPService::new().unwrap();
+        let digest = Auth::Digest {
+            username: "alice".to_string(),
+            password: "pw".to_string(),
+        };
+        let request = Request::new("Digest", format!("{}/start", mock_server.uri()))
+            .with_auth(digest.clone());
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.redirects.len(), 1);
+
+        // Rejected credentials are not sent again
+        let denied =
+            Request::new("Denied", format!("{}/denied", mock_server.uri())).with_auth(digest);
+        let response = service.execute_request(&denied).await.unwrap();
+        assert_eq!(response.status, 401);
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_added_at_send_time() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/orders"))
+            .and(header_exists("date"))
+            .and(|request: &wiremock::Request| {
+                request.headers.get("authorization").is_some_and(|value| {
+                    let value = value.to_str().unwrap();
+                    value.starts_with("HMAC partner-1:") && value.len() == 15 + 64
+                })
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let hmac =
+            HmacConfig::new("s3cret").with_header("Authorization", "HMAC partner-1:{signature}");
+        let request = Request::new("Order", format!("{}/orders", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_body(RequestBody::Json {
+                content: "{\"id\":1}".to_string(),
+            })
+            .with_auth(Auth::Hmac(hmac));
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert!(request.headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_hashes_multipart_body() {
+        use hmac::{Hmac, Mac};
+        use sha2::{Digest, Sha256};
+
+        let mock_server = MockServer::start().await;
+        // The signature covers the hash of the body as it was sent
+        Mock::given(path("/upload"))
+            .and(|request: &wiremock::Request| {
+                let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+                mac.update(hex::encode(Sha256::digest(&request.body)).as_bytes());
+                request.headers["x-signature"] == hex::encode(mac.finalize().into_bytes()).as_str()
+            })
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let hmac = HmacConfig::new("s3cret").with_canonical("{body_hash}");
+        let request = Request::new("Upload", format!("{}/upload", mock_server.uri()))
+            .with_method(HttpMethod::Post)
+            .with_body(RequestBody::Multipart {
+                parts: vec![MultipartPart::text("title", "Holiday")],
+            })
+            .with_auth(Auth::Hmac(hmac));
+
+        let response = service.execute_request(&request).await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_signed_at_send_time() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/me"))
+            .and(header_regex(
+                "authorization",
+                r"^Bearer eyJ[\w-]+\.eyJ[\w-]+\.[\w-]+$",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let jwt = JwtConfig::new(JwtAlgorithm::Hs256, "s3cret").with_claim("sub", "alice");
+        let request =
+            Request::new("Me", format!("{}/me", mock_server.uri())).with_auth(Auth::Jwt(jwt));
+        for _ in 0..2 {
+            let response = service.execute_request(&request).await.unwrap();
+            assert_eq!(response.status, 200);
+        }
+
+        let received = mock_server.received_requests().await.unwrap();
+        let token = |index: usize| {
+            received[index].headers["authorization"].to_str().unwrap()["Bearer ".len()..]
+                .to_string()
+        };
+        let claims = jwt::decode(&token(0), Utc::now()).unwrap().claims;
+        assert_eq!(claims["sub"], "alice");
+        assert_eq!(
+            claims["exp"].as_i64().unwrap() - claims["iat"].as_i64().unwrap(),
+            300
+        );
+
+        let invalid = request.with_auth(Auth::Jwt(JwtConfig::new(JwtAlgorithm::Es256, "nope")));
+        let result = service.execute_request(&invalid).await;
+        assert!(matches!(result, Err(HTTPServiceError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cookie_jar_sends_and_stores_cookies() {
+        let mock_server = MockServer::start().await;
+
+        // Cookies set on a redirect are sent on the next hop
+        Mock::given(method("GET"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("location", "/home")
+                    .append_header("set-cookie", "session=abc; Path=/; HttpOnly")
+                    .append_header("set-cookie", "theme=dark; Path=/settings"),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/home"))
+            .and(header("cookie", "session=abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("welcome"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/settings"))
+            .respond_with(|request: &wiremock::Request| {
+                let cookie = request
+                    .headers
+                    .get("cookie")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                ResponseTemplate::new(200).set_body_string(cookie)
+            })
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let jar = Arc::new(CookieJar::load(temp_dir.path().join("api.cookies.yaml")).unwrap());
+        let context = ExecutionContext {
+            cookie_jar: Some(Arc::clone(&jar)),
+            ..Default::default()
+        };
+        let service = HTTPService::new().unwrap();
+
+        let login = Request::new("Login", format!("{}/login", mock_server.uri()));
+        let response = service
+            .execute_request_with_context(&login, &context)
+            .await
+            .unwrap();
+        assert_eq!(response.body, "welcome");
+        assert_eq!(jar.list().cookies.len(), 2);
+
+        // The jar is persisted and matched by path on later requests
+        let settings = Request::new("Settings", format!("{}/settings", mock_server.uri()));
+        let response = service
+            .execute_request_with_context(&settings, &context)
+            .await
+            .unwrap();
+        assert!(response.body.contains("session=abc"));
+        assert!(response.body.contains("theme=dark"));
+        assert!(temp_dir.path().join("api.cookies.yaml").exists());
+
+        // A Cookie header set by the user replaces the jar
+        let response = service
+            .execute_request_with_context(
+                &settings.clone().with_header("Cookie", "own=1"),
+                &context,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.body, "own=1");
+
+        // A disabled jar sends nothing
+        jar.set_enabled(false).unwrap();
+        let response = service
+            .execute_request_with_context(&settings, &context)
+            .await
+            .unwrap();
+        assert_eq!(response.body, "");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_request() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/hang"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+            .mount(&mock_server)
+            .await;
+
+        let service = std::sync::Arc::new(HTTPService::new().unwrap());
+        let request = Request::new("Hang", format!("{}/hang", mock_server.uri()));
+
+        let task_service = std::sync::Arc::clone(&service);
+        let handle = tokio::spawn(async move {
+            task_service
+                .execute_cancellable(&request, "exec-1", &ExecutionContext::default())
+                .await
+        });
+
+        // Wait until the execution is registered
+        while !service.is_in_flight("exec-1") {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let start = Instant::now();
+        assert!(service.cancel_request("exec-1"));
+
+        let result = handle.await.unwrap();
+        assert_eq!(
+            result.unwrap_err(),
+            HTTPServiceError::Cancelled("exec-1".to_string())
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!service.is_in_flight("exec-1"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_unknown_execution() {
+        let service = HTTPService::new().unwrap();
+        assert!(!service.cancel_request("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_execution_is_unregistered() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/get"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("Get", format!("{}/get", mock_server.uri()));
+
+        let response = service
+            .execute_cancellable(&request, "exec-done", &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert!(response.is_success());
+        assert!(!service.is_in_flight("exec-done"));
+        assert!(!service.cancel_request("exec-done"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_execution_id_rejected() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/hang"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+            .mount(&mock_server)
+            .await;
+
+        let service = std::sync::Arc::new(HTTPService::new().unwrap());
+        let request = Request::new("Hang", format!("{}/hang", mock_server.uri()));
+
+        let task_service = std::sync::Arc::clone(&service);
+        let task_request = request.clone();
+        let handle = tokio::spawn(async move {
+            task_service
+                .execute_cancellable(&task_request, "same-id", &ExecutionContext::default())
+                .await
+        });
+
+        while !service.is_in_flight("same-id") {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let result = service
+            .execute_cancellable(&request, "same-id", &ExecutionContext::default())
+            .await;
+        assert_eq!(
+            result.unwrap_err(),
+            HTTPServiceError::DuplicateExecutionId("same-id".to_string())
+        );
+
+        service.cancel_request("same-id");
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_execution_keeps_reused_id_registered() {
+        let service = HTTPService::new().unwrap();
+        let register = |registration| {
+            let (handle, _) = AbortHandle::new_pair();
+            service.in_flight.lock().unwrap().insert(
+                "reused".to_string(),
+                InFlight {
+                    registration,
+                    handle,
+                },
+            );
+            InFlightGuard {
+                in_flight: &service.in_flight,
+                execution_id: "reused",
+                registration,
+            }
+        };
+
+        // The first execution is cancelled, and a second one reuses its ID
+        // before the first is dropped
+        let first = register(1);
+        assert!(service.cancel_request("reused"));
+        let second = register(2);
+
+        drop(first);
+        assert!(service.is_in_flight("reused"));
+        drop(second);
+        assert!(!service.is_in_flight("reused"));
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_with_collection_certificates() {
+        use crate::models::{CaCertificate, ClientCertificate, TlsSettings};
+        use crate::services::tls::testing::{https_server, pkcs12, TestCa};
+        use crate::services::tls::TlsFailureReason;
+
+        let server_ca = TestCa::new("Server CA");
+        let (server_cert, server_key) = server_ca.issue("localhost", &["localhost"]);
+        let client_ca = TestCa::new("Client CA");
+        let (client_cert, client_key) = client_ca.issue("tester", &["tester"]);
+        let port = https_server(&server_cert, &server_key, Some(&client_ca.cert)).await;
+
+        // Paths are stored relative to the collection directory
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("certs")).unwrap();
+        std::fs::write(dir.path().join("certs/ca.pem"), server_ca.cert.pem()).unwrap();
+        std::fs::write(
+            dir.path().join("certs/me.p12"),
+            pkcs12(&[&client_cert], &client_key, "s3cret"),
+        )
+        .unwrap();
+
+        let mut context = ExecutionContext {
+            collection_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        context.collection.tls = TlsSettings {
+            ca_certificates: vec![CaCertificate::new("certs/ca.pem").for_host("localhost")],
+            client_certificates: vec![
+                ClientCertificate::new("*.example.com", ClientIdentity::pem("a.pem", "a.key")),
+                ClientCertificate::new("localhost", ClientIdentity::pkcs12("certs/me.p12")),
+            ],
+        };
+
+        let service = HTTPService::new().unwrap();
+        let request = Request::new("mTLS", format!("https://localhost:{}/", port));
+
+        // The key is encrypted, so the passphrase is asked for first
+        let key_path = dir
+            .path()
+            .join("certs/me.p12")
+            .to_string_lossy()
+            .into_owned();
+        let result = service
+            .execute_request_with_context(&request, &context)
+            .await;
+        assert_eq!(
+            result.unwrap_err(),
+            HTTPServiceError::PassphraseRequired(key_path.clone())
+        );
+
+        service.set_certificate_passphrase(&key_path, "s3cret");
+        let response = service
+            .execute_request_with_context(&request, &context)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "1");
+
+        // Without a matching identity the server rejects the handshake
+        context.collection.tls.client_certificates.truncate(1);
+        let result = service
+            .execute_request_with_context(&request, &context)
+            .await;
+        assert!(matches!(
+            result,
+            Err(HTTPServiceError::Tls(TlsFailure {
+                reason: TlsFailureReason::ClientCertificateRejected,
+                ..
+            }))
+        ));
+
+        // Without the private CA the server certificate is not trusted
+        let result = service.execute_request(&request).await;
+        assert!(matches!(
+            result,
+            Err(HTTPServiceError::Tls(TlsFailure {
+                reason: TlsFailureReason::UnknownIssuer,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_to_other_host_uses_its_certificates() {
+        use crate::models::{CaCertificate, ClientCertificate, TlsSettings};
+        use crate::services::tls::testing::{https_server, TestCa};
+        use crate::services::tls::TlsFailureReason;
+
+        let server_ca = TestCa::new("Server CA");
+        let (server_cert, server_key) = server_ca.issue("127.0.0.1", &["127.0.0.1"]);
+        let client_ca = TestCa::new("Client CA");
+        let (client_cert, client_key) = client_ca.issue("tester", &["tester"]);
+        // The second host asks for a certificate from the client's CA
+        let port = https_server(&server_cert, &server_key, Some(&client_ca.cert)).await;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/start"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("Location", format!("https://127.0.0.1:{}/", port)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ca.pem"), server_ca.cert.pem()).unwrap();
+        std::fs::write(dir.path().join("me.pem"), client_cert.pem()).unwrap();
+        std::fs::write(dir.path().join("me.key"), client_key.serialize_pem()).unwrap();
+        let mut context = ExecutionContext {
+            collection_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        // The identity belongs to the first host, the CA to the second
+        context.collection.tls = TlsSettings {
+            ca_certificates: vec![CaCertificate::new("ca.pem").for_host("127.0.0.1")],
+            client_certificates: vec![ClientCertificate::new(
+                "localhost",
+                ClientIdentity::pem("me.pem", "me.key"),
+            )],
+        };
+
+        let service = HTTPService::new().unwrap();
+        let port = mock_server.address().port();
+        let request = Request::new("Start", format!("http://localhost:{}/start", port));
+
+        // The second host's CA is trusted, and it is not sent the identity
+        let result = service
+            .execute_request_with_context(&request, &context)
+            .await;
+        assert!(matches!(
+            result,
+            Err(HTTPServiceError::Tls(TlsFailure {
+                reason: TlsFailureReason::ClientCertificateRejected,
+                ..
+            }))
+        ));
+
+        // Scoped to the second host, the identity is presented there
+        context.collection.tls.client_certificates[0].host = "127.0.0.1".to_string();
+        let response = service
+            .execute_request_with_context(&request, &context)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "1");
+        assert_eq!(response.redirects.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tls_details_and_failures() {
+        use crate::models::{CaCertificate, TlsSettings};
+        use crate::services::tls::testing::{https_server, TestCa};
+        use crate::services::tls::TlsFailureReason;
+
+        let ca = TestCa::new("Server CA");
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ca.pem"), ca.cert.pem()).unwrap();
+        let mut context = ExecutionContext {
+            collection_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        context.collection.tls = TlsSettings {
+            ca_certificates: vec![CaCertificate::new("ca.pem")],
+            ..Default::default()
+        };
+
+        let service = HTTPService::new().unwrap();
+        let send = |port: u16, context: ExecutionContext| {
+            let service = &service;
+            async move {
+                let request = Request::new("TLS", format!("https://localhost:{}/", port));
+                service
+                    .execute_request_with_context(&request, &context)
+                    .await
+            }
+        };
+        let tls_failure = |result: HTTPServiceResult<Response>| match result {
+            Err(HTTPServiceError::Tls(failure)) => failure,
+            other => panic!("expected a TLS failure, got {:?}", other),
+        };
+
+        let (cert, key) = ca.issue("localhost", &["localhost"]);
+        let port = https_server(&cert, &key, None).await;
+        let response = send(port, context.clone()).await.unwrap();
+        let tls = response.connection.unwrap().tls.unwrap();
+        assert_eq!(tls.protocol_version, "TLSv1.3");
+        assert!(tls.cipher_suite.starts_with("TLS13_"));
+        assert_eq!(tls.certificates.len(), 1);
+        assert_eq!(tls.certificates[0].subject, "CN=localhost");
+        assert_eq!(tls.certificates[0].issuer, "CN=Server CA");
+        assert_eq!(tls.certificates[0].subject_alt_names, vec!["DNS:localhost"]);
+
+        // Untrusted without the collection's CA
+        let failure = tls_failure(send(port, ExecutionContext::default()).await);
+        assert_eq!(failure.reason, TlsFailureReason::UnknownIssuer);
+        assert_eq!(failure.certificates[0].subject, "CN=localhost");
+
+        let (cert, key) = ca.issue_with("localhost", &["localhost"], |params| {
+            params.not_before = rcgen::date_time_ymd(2020, 1, 1);
+            params.not_after = rcgen::date_time_ymd(2021, 1, 1);
+        });
+        let port = https_server(&cert, &key, None).await;
+        let failure = tls_failure(send(port, context.clone()).await);
+        assert_eq!(failure.reason, TlsFailureReason::Expired);
+        assert_eq!(
+            failure.certificates[0].not_after,
+            "2021-01-01T00:00:00+00:00"
+        );
+
+        let (cert, key) = ca.issue("api.example.com", &["api.example.com"]);
+        let port = https_server(&cert, &key, None).await;
+        let failure = tls_failure(send(port, context.clone()).await);
+        assert_eq!(failure.reason, TlsFailureReason::HostnameMismatch);
+
+        let json = serde_json::to_value(HTTPServiceError::Tls(failure)).unwrap();
+        assert_eq!(json["kind"], "tls");
+        assert_eq!(json["message"]["reason"], "hostname_mismatch");
+        assert_eq!(
+            json["message"]["certificates"][0]["subject_alt_names"][0],
+            "DNS:api.example.com"
+        );
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let json =
+            serde_json::to_string(&HTTPServiceError::Cancelled("exec-1".to_string())).unwrap();
+        assert_eq!(json, r#"{"kind":"cancelled","message":"exec-1"}"#);
+
+        let json =
+            serde_json::to_string(&HTTPServiceError::RequestFailed("boom".to_string())).unwrap();
+        assert_eq!(json, r#"{"kind":"request_failed","message":"boom"}"#);
     }
 }
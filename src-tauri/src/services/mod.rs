@@ -1,3 +1,15 @@
+pub mod assertions;
+pub mod digest;
+pub mod dynamic;
+pub mod extraction;
 pub mod http;
+pub mod jwt;
+pub mod oauth2;
+pub mod proxy;
+pub mod signature;
+pub mod sigv4;
+pub mod template;
+pub mod timing;
+pub mod tls;
 
 pub use http::*;
@@ -0,0 +1,887 @@
+use crate::models::params;
+use crate::models::{ClientAuthentication, KeyValue, OAuth2Config, OAuth2Grant};
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Tokens expiring within this margin are renewed before use
+const EXPIRY_MARGIN: chrono::Duration = chrono::Duration::seconds(30);
+
+/// Path of the loopback redirect URI
+const CALLBACK_PATH: &str = "/callback";
+
+/// Longest callback request read by the redirect listener
+const MAX_CALLBACK_SIZE: usize = 16 * 1024;
+
+/// Time allowed for a connection to the redirect listener to send its
+/// request
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Authorization flows not finished within this time are abandoned
+const PENDING_LIFETIME: Duration = Duration::from_secs(300);
+
+/// Error type for OAuth 2.0 token operations
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OAuth2Error {
+    /// The token endpoint could not be reached or sent an unreadable reply
+    #[error("Token request failed: {0}")]
+    TokenRequest(String),
+
+    /// The token endpoint refused the request
+    #[error("Token endpoint returned {error}{}", .description.as_deref().map(|d| format!(": {}", d)).unwrap_or_default())]
+    TokenEndpoint {
+        error: String,
+        description: Option<String>,
+    },
+
+    /// No usable token is cached for an authorization code grant; the user
+    /// has to authorize in the browser first
+    #[error("Authorization required")]
+    AuthorizationRequired,
+
+    /// The browser authorization failed or timed out
+    #[error("Authorization failed: {0}")]
+    Authorization(String),
+}
+
+pub type OAuth2Result<T> = Result<T, OAuth2Error>;
+
+/// An access token issued by a token endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuth2Token {
+    pub access_token: String,
+    pub token_type: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub scope: Option<String>,
+}
+
+/// What the frontend is told about a token; the token itself stays in the
+/// backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuth2TokenInfo {
+    pub token_type: String,
+
+    /// Expiry time (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Whether the token can be renewed without the user
+    pub refreshable: bool,
+}
+
+/// An authorization code flow waiting for the browser to come back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationStart {
+    /// Identifies the flow when finishing it
+    pub session_id: String,
+
+    /// URL to open in the user's browser
+    pub authorization_url: String,
+
+    /// Loopback URL the browser is redirected to
+    pub redirect_uri: String,
+}
+
+/// Successful token endpoint response (RFC 6749 section 5.1)
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+
+    #[serde(default)]
+    token_type: Option<String>,
+
+    /// Seconds; some servers send it as a string
+    #[serde(default)]
+    expires_in: Option<serde_json::Value>,
+
+    #[serde(default)]
+    refresh_token: Option<String>,
+
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Error response of the token endpoint (RFC 6749 section 5.2)
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Tokens are cached per collection and per auth configuration
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TokenKey {
+    collection: Option<PathBuf>,
+    config: OAuth2Config,
+}
+
+struct PendingAuthorization {
+    key: TokenKey,
+    client: reqwest::Client,
+    listener: TcpListener,
+    redirect_uri: String,
+    state: String,
+    verifier: String,
+    started_at: Instant,
+}
+
+/// OAuth 2.0 tokens of the session
+///
+/// Tokens are kept in memory only. A cached token is used until it expires,
+/// then renewed with its refresh token if it has one, or else with the
+/// configured grant. The authorization code grant needs the user, so it is
+/// only run through [`OAuth2Tokens::begin_authorization`].
+#[derive(Default)]
+pub struct OAuth2Tokens {
+    tokens: Mutex<HashMap<TokenKey, OAuth2Token>>,
+    pending: Mutex<HashMap<String, PendingAuthorization>>,
+}
+
+impl OAuth2Token {
+    /// Check whether the token expires within the renewal margin
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now() + EXPIRY_MARGIN)
+    }
+
+    /// Describe the token without revealing it
+    pub fn info(&self) -> OAuth2TokenInfo {
+        OAuth2TokenInfo {
+            token_type: self.token_type.clone(),
+            expires_at: self
+                .expires_at
+                .map(|expires_at| expires_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            scope: self.scope.clone(),
+            refreshable: self.refresh_token.is_some(),
+        }
+    }
+}
+
+impl OAuth2Tokens {
+    /// Create an empty token cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get an access token for a configuration
+    ///
+    /// # Arguments
+    /// * `client` - Client used to reach the token endpoint
+    /// * `config` - OAuth 2.0 settings, with placeholders resolved
+    /// * `collection` - Collection the request belongs to
+    /// * `renew` - Renew the cached token even if it has not expired, e.g.
+    ///   after the server answered 401
+    pub async fn access_token(
+        &self,
+        client: &reqwest::Client,
+        config: &OAuth2Config,
+        collection: Option<&Path>,
+        renew: bool,
+    ) -> OAuth2Result<String> {
+        let key = TokenKey {
+            collection: collection.map(Path::to_path_buf),
+            config: config.clone(),
+        };
+
+        let cached = self.get(&key);
+        if let Some(token) = cached
+            .as_ref()
+            .filter(|token| !renew && !token.is_expired())
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        if let Some(refresh_token) = cached.and_then(|token| token.refresh_token) {
+            let params = [
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.as_str()),
+            ];
+            match request_token(client, config, &params).await {
+                Ok(mut token) => {
+                    // The old refresh token stays valid unless a new one is issued
+                    token.refresh_token.get_or_insert(refresh_token);
+                    return Ok(self.store(key, token));
+                }
+                Err(e) => eprintln!("Warning: Failed to refresh OAuth 2.0 token: {}", e),
+            }
+        }
+        self.remove(&key);
+
+        let token = match config.grant {
+            OAuth2Grant::ClientCredentials => {
+                request_token(client, config, &[("grant_type", "client_credentials")]).await?
+            }
+            OAuth2Grant::Password => {
+                let params = [
+                    ("grant_type", "password"),
+                    ("username", config.username.as_str()),
+                    ("password", config.password.as_str()),
+                ];
+                request_token(client, config, &params).await?
+            }
+            OAuth2Grant::AuthorizationCode => return Err(OAuth2Error::AuthorizationRequired),
+        };
+
+        Ok(self.store(key, token))
+    }
+
+    /// Start an authorization code flow with PKCE
+    ///
+    /// Opens the loopback listener the browser is redirected to. The flow
+    /// completes when [`OAuth2Tokens::finish_authorization`] receives the
+    /// redirect. Flows that were never finished are dropped, with their
+    /// listeners, once they expire or when a new flow is started for the
+    /// same configuration.
+    pub async fn begin_authorization(
+        &self,
+        client: reqwest::Client,
+        config: &OAuth2Config,
+        collection: Option<&Path>,
+    ) -> OAuth2Result<AuthorizationStart> {
+        let key = TokenKey {
+            collection: collection.map(Path::to_path_buf),
+            config: config.clone(),
+        };
+        // Release the port of an abandoned flow before binding it again
+        self.pending
+            .lock()
+            .map_err(|e| OAuth2Error::Authorization(format!("Lock poisoned: {}", e)))?
+            .retain(|_, pending| {
+                pending.key != key && pending.started_at.elapsed() < PENDING_LIFETIME
+            });
+
+        let listener = TcpListener::bind(("127.0.0.1", config.redirect_port.unwrap_or(0)))
+            .await
+            .map_err(|e| OAuth2Error::Authorization(format!("Failed to listen: {}", e)))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| OAuth2Error::Authorization(e.to_string()))?
+            .port();
+        let redirect_uri = format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH);
+
+        let verifier = random_string(64);
+        let state = random_string(32);
+        let challenge = BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        let mut query = vec![
+            KeyValue::new("response_type", "code"),
+            KeyValue::new("client_id", &config.client_id),
+            KeyValue::new("redirect_uri", &redirect_uri),
+            KeyValue::new("state", &state),
+            KeyValue::new("code_challenge", challenge),
+            KeyValue::new("code_challenge_method", "S256"),
+        ];
+        if !config.scope.is_empty() {
+            query.push(KeyValue::new("scope", &config.scope));
+        }
+        let authorization_url =
+            params::append_query(&config.authorization_url, &params::encode_query(&query));
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let pending = PendingAuthorization {
+            key,
+            client,
+            listener,
+            redirect_uri: redirect_uri.clone(),
+            state,
+            verifier,
+            started_at: Instant::now(),
+        };
+        self.pending
+            .lock()
+            .map_err(|e| OAuth2Error::Authorization(format!("Lock poisoned: {}", e)))?
+            .insert(session_id.clone(), pending);
+
+        Ok(AuthorizationStart {
+            session_id,
+            authorization_url,
+            redirect_uri,
+        })
+    }
+
+    /// Wait for the browser redirect of a flow and exchange the code for a
+    /// token
+    ///
+    /// # Arguments
+    /// * `session_id` - ID returned by `begin_authorization`
+    /// * `timeout` - How long to wait for the user
+    pub async fn finish_authorization(
+        &self,
+        session_id: &str,
+        timeout: Duration,
+    ) -> OAuth2Result<OAuth2TokenInfo> {
+        let pending = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(session_id))
+            .filter(|pending| pending.started_at.elapsed() < PENDING_LIFETIME)
+            .ok_or_else(|| {
+                OAuth2Error::Authorization(format!("Unknown authorization: {}", session_id))
+            })?;
+
+        let code = tokio::time::timeout(timeout, wait_for_code(&pending))
+            .await
+            .map_err(|_| {
+                OAuth2Error::Authorization("Timed out waiting for the browser".into())
+            })??;
+
+        let params = [
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", pending.redirect_uri.as_str()),
+            ("code_verifier", pending.verifier.as_str()),
+        ];
+        let token = request_token(&pending.client, &pending.key.config, &params).await?;
+        let info = token.info();
+        self.store(pending.key, token);
+        Ok(info)
+    }
+
+    /// Forget the tokens of a collection, or every token
+    pub fn clear(&self, collection: Option<&Path>) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            match collection {
+                Some(collection) => {
+                    tokens.retain(|key, _| key.collection.as_deref() != Some(collection))
+                }
+                None => tokens.clear(),
+            }
+        }
+    }
+
+    fn get(&self, key: &TokenKey) -> Option<OAuth2Token> {
+        self.tokens.lock().ok()?.get(key).cloned()
+    }
+
+    fn store(&self, key: TokenKey, token: OAuth2Token) -> String {
+        let access_token = token.access_token.clone();
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.insert(key, token);
+        }
+        access_token
+    }
+
+    fn remove(&self, key: &TokenKey) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.remove(key);
+        }
+    }
+}
+
+/// Request a token from the token endpoint
+async fn request_token(
+    client: &reqwest::Client,
+    config: &OAuth2Config,
+    params: &[(&str, &str)],
+) -> OAuth2Result<OAuth2Token> {
+    // Public clients have no secret to authenticate with
+    let basic = config.client_authentication == ClientAuthentication::Basic
+        && !config.client_secret.is_empty();
+    let body = {
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        form.extend_pairs(params);
+        if !config.scope.is_empty() && !params.contains(&("grant_type", "authorization_code")) {
+            form.append_pair("scope", &config.scope);
+        }
+        if !basic {
+            form.append_pair("client_id", &config.client_id);
+            if !config.client_secret.is_empty() {
+                form.append_pair("client_secret", &config.client_secret);
+            }
+        }
+        form.finish()
+    };
+
+    let mut builder = client
+        .post(&config.token_url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        );
+    if basic {
+        // The credentials are form-encoded first (RFC 6749 section 2.3.1)
+        let encode = |value: &str| {
+            url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>()
+        };
+        builder = builder.basic_auth(
+            encode(&config.client_id),
+            Some(encode(&config.client_secret)),
+        );
+    }
+
+    let response = builder
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| OAuth2Error::TokenRequest(e.to_string()))?;
+    let status = response.status();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| OAuth2Error::TokenRequest(e.to_string()))?;
+
+    if !status.is_success() {
+        return Err(match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(error) => OAuth2Error::TokenEndpoint {
+                error: error.error,
+                description: error.error_description,
+            },
+            Err(_) => OAuth2Error::TokenRequest(format!("HTTP {}", status)),
+        });
+    }
+
+    let token: TokenResponse = serde_json::from_slice(&body)
+        .map_err(|e| OAuth2Error::TokenRequest(format!("Invalid token response: {}", e)))?;
+    let expires_in = match token.expires_in {
+        Some(serde_json::Value::Number(seconds)) => seconds.as_i64(),
+        Some(serde_json::Value::String(seconds)) => seconds.parse().ok(),
+        _ => None,
+    };
+
+    Ok(OAuth2Token {
+        access_token: token.access_token,
+        token_type: token.token_type.unwrap_or_else(|| "Bearer".to_string()),
+        refresh_token: token.refresh_token,
+        expires_at: expires_in
+            .and_then(chrono::Duration::try_seconds)
+            .and_then(|expires_in| Utc::now().checked_add_signed(expires_in)),
+        scope: token.scope,
+    })
+}
+
+/// Accept connections on the loopback listener until the authorization
+/// redirect arrives
+///
+/// Each connection is answered in its own task, so a connection the browser
+/// opens ahead of time and leaves idle does not hold up the redirect.
+async fn wait_for_code(pending: &PendingAuthorization) -> OAuth2Result<String> {
+    let (sender, mut outcomes) = tokio::sync::mpsc::channel(1);
+    loop {
+        tokio::select! {
+            accepted = pending.listener.accept() => {
+                let (stream, _) =
+                    accepted.map_err(|e| OAuth2Error::Authorization(e.to_string()))?;
+                let sender = sender.clone();
+                let state = pending.state.clone();
+                tokio::spawn(async move {
+                    if let Some(outcome) = answer_callback(stream, &state).await {
+                        // The flow may already be over
+                        let _ = sender.send(outcome).await;
+                    }
+                });
+            }
+            Some(outcome) = outcomes.recv() => return outcome,
+        }
+    }
+}
+
+/// Answer a connection to the loopback listener
+///
+/// # Returns
+/// The outcome of the flow, or `None` if the request was not the redirect
+/// of this flow
+async fn answer_callback(mut stream: TcpStream, state: &str) -> Option<OAuth2Result<String>> {
+    let query =
+        match tokio::time::timeout(CALLBACK_READ_TIMEOUT, read_callback_query(&mut stream)).await {
+            Ok(Some(query)) => query,
+            // Browsers also ask for /favicon.ico and the like
+            Ok(None) => {
+                respond(&mut stream, "404 Not Found", "Not found").await;
+                return None;
+            }
+            Err(_) => return None,
+        };
+    let value = |name: &str| {
+        query
+            .iter()
+            .find(|pair| pair.key == name)
+            .map(|pair| pair.value.clone())
+    };
+
+    if value("state").as_deref() != Some(state) {
+        respond(&mut stream, "400 Bad Request", "Invalid state.").await;
+        return None;
+    }
+    if let Some(error) = value("error") {
+        respond(
+            &mut stream,
+            "200 OK",
+            "Authorization failed. You can close this window.",
+        )
+        .await;
+        let description = value("error_description");
+        return Some(Err(OAuth2Error::TokenEndpoint { error, description }));
+    }
+    let Some(code) = value("code") else {
+        respond(&mut stream, "400 Bad Request", "Missing code.").await;
+        return None;
+    };
+
+    respond(
+        &mut stream,
+        "200 OK",
+        "Authorization complete. You can close this window.",
+    )
+    .await;
+    Some(Ok(code))
+}
+
+/// Read an HTTP request and return its query if it is for the callback path
+async fn read_callback_query(stream: &mut TcpStream) -> Option<Vec<KeyValue>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 || buffer.len() + read > MAX_CALLBACK_SIZE {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let target = request.lines().next()?.split_whitespace().nth(1)?;
+    let (path, query) = params::split_query(target);
+    (path == CALLBACK_PATH).then(|| params::parse_query(query.unwrap_or_default()))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!("<!doctype html><title>Arcanine</title><p>{}</p>", message);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    // The browser may already be gone; the outcome does not depend on it
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Random string of PKCE unreserved characters
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn token_response(access_token: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": access_token,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "refresh_token": "refresh-1"
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_token_is_cached_and_refreshed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(header("authorization", "Basic YXBwOnMzY3JldA=="))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("scope=read+write"))
+            .respond_with(token_response("access-1"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=refresh-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access-2",
+                "expires_in": "3600"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let config = OAuth2Config::new(
+            OAuth2Grant::ClientCredentials,
+            format!("{}/token", mock_server.uri()),
+            "app",
+        )
+        .with_client_secret("s3cret")
+        .with_scope("read write");
+        let tokens = OAuth2Tokens::new();
+        let collection = Path::new("/collections/users.collection.yaml");
+
+        let token = tokens
+            .access_token(&client, &config, Some(collection), false)
+            .await
+            .unwrap();
+        assert_eq!(token, "access-1");
+        // The cached token is reused
+        let token = tokens
+            .access_token(&client, &config, Some(collection), false)
+            .await
+            .unwrap();
+        assert_eq!(token, "access-1");
+
+        // Renewal uses the refresh token, which is kept
+        let token = tokens
+            .access_token(&client, &config, Some(collection), true)
+            .await
+            .unwrap();
+        assert_eq!(token, "access-2");
+        let key = TokenKey {
+            collection: Some(collection.to_path_buf()),
+            config: config.clone(),
+        };
+        let cached = tokens.get(&key).unwrap();
+        assert_eq!(cached.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(!cached.is_expired());
+
+        tokens.clear(Some(collection));
+        assert!(tokens.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_password_grant_and_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=password"))
+            .and(body_string_contains("username=alice"))
+            .and(body_string_contains("password=wrong"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "invalid_grant",
+                "error_description": "Bad credentials"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let config = OAuth2Config::new(
+            OAuth2Grant::Password,
+            format!("{}/token", mock_server.uri()),
+            "app",
+        )
+        .with_credentials("alice", "wrong");
+        let tokens = OAuth2Tokens::new();
+
+        let error = tokens
+            .access_token(&client, &config, None, false)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Token endpoint returned invalid_grant: Bad credentials"
+        );
+
+        let mut authorization_code = config.clone();
+        authorization_code.grant = OAuth2Grant::AuthorizationCode;
+        assert_eq!(
+            tokens
+                .access_token(&client, &authorization_code, None, false)
+                .await,
+            Err(OAuth2Error::AuthorizationRequired)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authorization_code_with_pkce() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=authorization_code"))
+            .and(body_string_contains("code=c0de"))
+            .and(body_string_contains("code_verifier="))
+            .and(body_string_contains("client_id=public-app"))
+            .respond_with(token_response("access-1"))
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let config = OAuth2Config::new(
+            OAuth2Grant::AuthorizationCode,
+            format!("{}/token", mock_server.uri()),
+            "public-app",
+        )
+        .with_authorization_url("https://auth.example.com/authorize");
+        let tokens = OAuth2Tokens::new();
+
+        let start = tokens
+            .begin_authorization(client.clone(), &config, None)
+            .await
+            .unwrap();
+        let url = url::Url::parse(&start.authorization_url).unwrap();
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["redirect_uri"], start.redirect_uri);
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["code_challenge"].len(), 43);
+
+        // The browser is redirected back to the loopback listener
+        let callback = format!("{}?code=c0de&state={}", start.redirect_uri, query["state"]);
+        let browser = tokio::spawn(async move {
+            let favicon = start.redirect_uri.replace(CALLBACK_PATH, "/favicon.ico");
+            let client = reqwest::Client::new();
+            assert_eq!(client.get(favicon).send().await.unwrap().status(), 404);
+            client
+                .get(callback)
+                .send()
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap()
+        });
+
+        let info = tokens
+            .finish_authorization(&start.session_id, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(info.refreshable);
+        assert!(browser.await.unwrap().contains("Authorization complete"));
+
+        assert_eq!(
+            tokens
+                .access_token(&client, &config, None, false)
+                .await
+                .unwrap(),
+            "access-1"
+        );
+        assert!(tokens
+            .finish_authorization(&start.session_id, Duration::from_secs(1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_waits_past_idle_and_stray_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("code=c0de"))
+            .respond_with(token_response("access-1"))
+            .mount(&mock_server)
+            .await;
+
+        let config = OAuth2Config::new(
+            OAuth2Grant::AuthorizationCode,
+            format!("{}/token", mock_server.uri()),
+            "public-app",
+        )
+        .with_authorization_url("https://auth.example.com/authorize");
+        let tokens = OAuth2Tokens::new();
+        let start = tokens
+            .begin_authorization(reqwest::Client::new(), &config, None)
+            .await
+            .unwrap();
+        let state = url::Url::parse(&start.authorization_url)
+            .unwrap()
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .unwrap()
+            .1
+            .into_owned();
+
+        let redirect_uri = start.redirect_uri.clone();
+        let browser = tokio::spawn(async move {
+            // A connection opened ahead of time and never used
+            let address = redirect_uri
+                .trim_start_matches("http://")
+                .trim_end_matches(CALLBACK_PATH)
+                .to_string();
+            let _idle = TcpStream::connect(address).await.unwrap();
+
+            let client = reqwest::Client::new();
+            let get = |url: String| {
+                let client = client.clone();
+                async move { client.get(url).send().await.unwrap().status() }
+            };
+            let favicon = redirect_uri.replace(CALLBACK_PATH, "/favicon.ico");
+            assert_eq!(get(favicon).await, 404);
+            let forged = format!("{}?code=evil&state=wrong", redirect_uri);
+            assert_eq!(get(forged).await, 400);
+            let callback = format!("{}?code=c0de&state={}", redirect_uri, state);
+            get(callback).await
+        });
+
+        let info = tokens
+            .finish_authorization(&start.session_id, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(info.refreshable);
+        assert_eq!(browser.await.unwrap(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_authorizations_are_dropped() {
+        let client = reqwest::Client::new();
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut config = OAuth2Config::new(
+            OAuth2Grant::AuthorizationCode,
+            "https://auth.example.com/token",
+            "public-app",
+        )
+        .with_authorization_url("https://auth.example.com/authorize");
+        config.redirect_port = Some(port);
+        let tokens = OAuth2Tokens::new();
+        let is_pending = |session_id: &str| tokens.pending.lock().unwrap().contains_key(session_id);
+
+        // Starting over replaces the abandoned flow and frees its port
+        let first = tokens
+            .begin_authorization(client.clone(), &config, None)
+            .await
+            .unwrap();
+        let second = tokens
+            .begin_authorization(client.clone(), &config, None)
+            .await
+            .unwrap();
+        assert_eq!(second.redirect_uri, first.redirect_uri);
+        assert!(!is_pending(&first.session_id));
+        assert!(is_pending(&second.session_id));
+
+        // Expired flows are dropped when any other one starts
+        tokens
+            .pending
+            .lock()
+            .unwrap()
+            .get_mut(&second.session_id)
+            .unwrap()
+            .started_at = Instant::now().checked_sub(PENDING_LIFETIME).unwrap();
+        let other = config.clone().with_scope("read");
+        let third = tokens
+            .begin_authorization(client, &other, None)
+            .await
+            .unwrap();
+        assert!(!is_pending(&second.session_id));
+        assert!(is_pending(&third.session_id));
+        assert!(tokens
+            .finish_authorization(&second.session_id, Duration::from_secs(1))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::models::ProxyConfig;
+use reqwest::{NoProxy, Proxy};
+
+/// Configure how a client reaches the network
+///
+/// reqwest reads the proxy environment once per process, so the system mode
+/// is resolved here instead, each time a client is built.
+///
+/// # Arguments
+/// * `builder` - The client builder to configure
+/// * `config` - The effective proxy configuration
+/// * `env` - Looks up an environment variable by name
+pub fn configure(
+    builder: reqwest::ClientBuilder,
+    config: &ProxyConfig,
+    env: impl Fn(&str) -> Option<String>,
+) -> reqwest::Result<reqwest::ClientBuilder> {
+    let proxies = match config {
+        ProxyConfig::None => Vec::new(),
+        ProxyConfig::System => system_proxies(env)?,
+        ProxyConfig::Manual(manual) => {
+            let mut proxy = Proxy::all(&manual.url)?;
+            if let Some(username) = &manual.username {
+                proxy = proxy.basic_auth(username, manual.password.as_deref().unwrap_or_default());
+            }
+            vec![proxy.no_proxy(NoProxy::from_string(&manual.bypass.join(",")))]
+        }
+    };
+
+    // Without an explicit proxy reqwest would fall back to its own view of
+    // the environment
+    let mut builder = builder.no_proxy();
+    for proxy in proxies {
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder)
+}
+
+/// Build proxies from `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and
+/// `NO_PROXY`
+///
+/// Upper case names win over lower case ones, and empty values are ignored.
+/// Scheme-specific proxies take precedence over `ALL_PROXY`.
+pub fn system_proxies(env: impl Fn(&str) -> Option<String>) -> reqwest::Result<Vec<Proxy>> {
+    let var = |name: &str| {
+        env(name)
+            .or_else(|| env(&name.to_lowercase()))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let no_proxy = var("NO_PROXY").unwrap_or_default();
+
+    let mut proxies = Vec::new();
+    if let Some(url) = var("HTTP_PROXY") {
+        proxies.push(Proxy::http(url)?);
+    }
+    if let Some(url) = var("HTTPS_PROXY") {
+        proxies.push(Proxy::https(url)?);
+    }
+    if let Some(url) = var("ALL_PROXY") {
+        proxies.push(Proxy::all(url)?);
+    }
+
+    Ok(proxies
+        .into_iter()
+        .map(|proxy| proxy.no_proxy(NoProxy::from_string(&no_proxy)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ManualProxy;
+    use std::collections::HashMap;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Matches requests proxied to the unreachable test host
+    fn proxied(request: &wiremock::Request) -> bool {
+        request.url.host_str() == Some("api.unreachable.invalid")
+    }
+
+    fn client(config: &ProxyConfig, env: &HashMap<&str, String>) -> reqwest::Client {
+        configure(reqwest::Client::builder(), config, |name| {
+            env.get(name).cloned()
+        })
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    async fn proxy_server() -> MockServer {
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(proxied)
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("via proxy"))
+            .mount(&proxy)
+            .await;
+        proxy
+    }
+
+    #[tokio::test]
+    async fn test_manual_proxy_with_credentials() {
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(proxied)
+            // alice:s3cret
+            .and(header("proxy-authorization", "Basic YWxpY2U6czNjcmV0"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&proxy)
+            .await;
+
+        let config =
+            ProxyConfig::Manual(ManualProxy::new(proxy.uri()).with_credentials("alice", "s3cret"));
+        let response = client(&config, &HashMap::new())
+            .get("http://api.unreachable.invalid/status")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_manual_proxy_bypass() {
+        let proxy = proxy_server().await;
+        let target = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("direct"))
+            .mount(&target)
+            .await;
+
+        let config = ProxyConfig::Manual(ManualProxy::new(proxy.uri()).with_bypass("127.0.0.1"));
+        let client = client(&config, &HashMap::new());
+
+        let body = client
+            .get("http://api.unreachable.invalid/status")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "via proxy");
+
+        let body = client
+            .get(format!("{}/status", target.uri()))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "direct");
+    }
+
+    #[tokio::test]
+    async fn test_system_proxy_from_environment() {
+        let proxy = proxy_server().await;
+        let env = HashMap::from([
+            ("http_proxy", proxy.uri()),
+            ("NO_PROXY", "localhost,.internal.example".to_string()),
+        ]);
+
+        let response = client(&ProxyConfig::System, &env)
+            .get("http://api.unreachable.invalid/status")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "via proxy");
+
+        // Disabling the proxy ignores the environment
+        let result = client(&ProxyConfig::None, &env)
+            .get("http://api.unreachable.invalid/status")
+            .send()
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_system_proxies_skip_empty_and_reject_invalid_values() {
+        let env = HashMap::from([("HTTP_PROXY", " ".to_string())]);
+        assert!(system_proxies(|name| env.get(name).cloned())
+            .unwrap()
+            .is_empty());
+
+        let env = HashMap::from([("HTTPS_PROXY", "http://[::1".to_string())]);
+        assert!(system_proxies(|name| env.get(name).cloned()).is_err());
+    }
+}
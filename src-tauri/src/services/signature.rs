@@ -0,0 +1,251 @@
+use crate::models::{HmacAlgorithm, HmacConfig, SignatureEncoding};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue, DATE};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+
+/// Error signing a request with an HMAC signature
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    /// Streamed bodies cannot be hashed up front; callers read multipart
+    /// forms into memory first
+    #[error("The body of a streamed request cannot be signed")]
+    StreamedBody,
+
+    #[error("Invalid signature header: {0}")]
+    InvalidHeader(String),
+}
+
+/// Sign a request with an HMAC signature
+///
+/// The canonical template is filled in from the request and signed with the
+/// secret; the header value template, filled in with the signature, is then
+/// set on the configured header. A `Date` header is added when a template
+/// uses `{date}` and the request has none.
+///
+/// # Arguments
+/// * `request` - The request, with its final URL, headers and body
+/// * `config` - Resolved signature settings
+/// * `now` - Signing time
+pub fn sign(
+    request: &mut reqwest::Request,
+    config: &HmacConfig,
+    now: DateTime<Utc>,
+) -> Result<(), SignatureError> {
+    let uses = |name: &str| {
+        let placeholder = format!("{{{}}}", name);
+        config.canonical.contains(&placeholder) || config.value.contains(&placeholder)
+    };
+
+    if uses("date") && !request.headers().contains_key(DATE) {
+        let date = now.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        request.headers_mut().insert(DATE, header_value(&date)?);
+    }
+
+    let body_hash = if uses("body_hash") {
+        let body = match request.body() {
+            None => &[][..],
+            Some(body) => body.as_bytes().ok_or(SignatureError::StreamedBody)?,
+        };
+        hex::encode(Sha256::digest(body))
+    } else {
+        String::new()
+    };
+
+    let url = request.url();
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let date = request
+        .headers()
+        .get(DATE)
+        .map(|date| String::from_utf8_lossy(date.as_bytes()).into_owned())
+        .unwrap_or_default();
+    let mut values = vec![
+        ("method", request.method().to_string()),
+        ("path", path),
+        ("query", url.query().unwrap_or_default().to_string()),
+        ("host", host),
+        ("date", date),
+        ("timestamp", now.timestamp().to_string()),
+        ("body_hash", body_hash),
+    ];
+
+    let canonical = render(&config.canonical, &values);
+    let signature = hmac(
+        config.algorithm,
+        config.secret.as_bytes(),
+        canonical.as_bytes(),
+    );
+    let signature = match config.encoding {
+        SignatureEncoding::Hex => hex::encode(signature),
+        SignatureEncoding::Base64 => BASE64_STANDARD.encode(signature),
+    };
+    values.push(("signature", signature));
+
+    let name = HeaderName::from_bytes(config.header.trim().as_bytes())
+        .map_err(|e| SignatureError::InvalidHeader(e.to_string()))?;
+    let value = header_value(&render(&config.value, &values))?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, SignatureError> {
+    HeaderValue::from_str(value).map_err(|e| SignatureError::InvalidHeader(e.to_string()))
+}
+
+fn hmac(algorithm: HmacAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+    const KEY_LENGTH: &str = "HMAC accepts keys of any length";
+    match algorithm {
+        HmacAlgorithm::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).expect(KEY_LENGTH);
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        HmacAlgorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).expect(KEY_LENGTH);
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        HmacAlgorithm::Sha512 => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).expect(KEY_LENGTH);
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+/// Fill in the `{name}` placeholders of a template in a single pass
+///
+/// Unknown placeholders are left as they are.
+fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest[1..].split_once('}').and_then(|(name, _)| {
+            values
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, value)| (name.len() + 2, value))
+        });
+        match value {
+            Some((length, value)) => {
+                rendered.push_str(value);
+                rest = &rest[length..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_hmac_signature() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let mut request = reqwest::Client::new()
+            .post("https://api.example.com:8443/orders?page=2")
+            .body("{\"id\":1}")
+            .build()
+            .unwrap();
+        let config =
+            HmacConfig::new("s3cret").with_header("Authorization", "HMAC partner-1:{signature}");
+
+        sign(&mut request, &config, now).unwrap();
+        assert_eq!(request.headers()["date"], "Fri, 01 Mar 2024 12:00:00 GMT");
+
+        // The canonical string is signed with the secret
+        let canonical = format!(
+            "POST\n/orders?page=2\nFri, 01 Mar 2024 12:00:00 GMT\n{}",
+            hex::encode(Sha256::digest(b"{\"id\":1}"))
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(canonical.as_bytes());
+        assert_eq!(
+            request.headers()["authorization"],
+            format!(
+                "HMAC partner-1:{}",
+                hex::encode(mac.finalize().into_bytes())
+            )
+            .as_str()
+        );
+    }
+
+    #[test]
+    fn test_hmac_templates_and_algorithms() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let request = || {
+            reqwest::Request::new(
+                reqwest::Method::GET,
+                "https://api.example.com/users".parse().unwrap(),
+            )
+        };
+
+        // RFC 4231 test case 2 (and its SHA-1 counterpart from RFC 2202)
+        for (algorithm, expected) in [
+            (
+                HmacAlgorithm::Sha256,
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                HmacAlgorithm::Sha1,
+                "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            ),
+        ] {
+            let mut signed = request();
+            let config = HmacConfig::new("Jefe")
+                .with_algorithm(algorithm)
+                .with_canonical("what do ya want for nothing?");
+            sign(&mut signed, &config, now).unwrap();
+            assert_eq!(signed.headers()["x-signature"], expected);
+            // Only templates that use the date add it
+            assert!(!signed.headers().contains_key("date"));
+        }
+
+        let mut signed = request();
+        let config = HmacConfig::new("Jefe")
+            .with_algorithm(HmacAlgorithm::Sha512)
+            .with_canonical("{method} {host}{path} {timestamp} {unknown}")
+            .with_header("X-Auth", "t={timestamp}, sig={signature}")
+            .with_encoding(SignatureEncoding::Base64);
+        sign(&mut signed, &config, now).unwrap();
+        let signature = hmac(
+            HmacAlgorithm::Sha512,
+            b"Jefe",
+            b"GET api.example.com/users 1709294400 {unknown}",
+        );
+        assert_eq!(
+            signed.headers()["x-auth"],
+            format!("t=1709294400, sig={}", BASE64_STANDARD.encode(signature)).as_str()
+        );
+
+        // Streamed bodies cannot be hashed
+        let mut multipart = reqwest::Client::new()
+            .post("https://api.example.com/upload")
+            .multipart(reqwest::multipart::Form::new().text("name", "value"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            sign(&mut multipart, &HmacConfig::new("s3cret"), now),
+            Err(SignatureError::StreamedBody)
+        ));
+    }
+}
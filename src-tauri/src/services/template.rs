@@ -0,0 +1,606 @@
+use crate::models::{
+    Auth, DynamicVariable, Header, KeyValue, MultipartPart, ProxyConfig, Request, RequestBody,
+    Variable,
+};
+use crate::services::dynamic::DynamicValues;
+use crate::storage::{SecretVault, VaultError, VaultResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum depth of variables referring to other variables
+const MAX_DEPTH: usize = 10;
+
+/// Values available to `{{name}}` placeholders
+///
+/// A value may itself contain placeholders, which are resolved when it is
+/// used, unless it was set as a literal. Later definitions of a name replace
+/// earlier ones. Names starting with `$` are dynamic variables (see
+/// [`DynamicValues`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables(HashMap<String, Value>);
+
+/// The value of a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    /// Defined by the user; its placeholders are resolved
+    Template(String),
+
+    /// Received from a server; used as it is, so a response cannot pull other
+    /// variables (or secrets) into a request
+    Literal(String),
+
+    /// A secret of a vault that is locked; its placeholders cannot be
+    /// resolved until the vault is unlocked
+    Locked,
+}
+
+impl Value {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Template(value) | Self::Literal(value) => Some(value),
+            Self::Locked => None,
+        }
+    }
+}
+
+/// A placeholder that no variable defines
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnresolvedVariable {
+    /// Name inside the braces
+    pub name: String,
+
+    /// Where the placeholder appears (`url`, `headers.Authorization`, `body`, ...)
+    pub location: String,
+}
+
+impl fmt::Display for UnresolvedVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{{{}}}}} in {}", self.name, self.location)
+    }
+}
+
+impl Variables {
+    /// Create an empty set of variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a variable, replacing any previous value
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(name.into(), Value::Template(value.into()));
+    }
+
+    /// Set a variable whose value is used as it is, with any placeholders in
+    /// it left unresolved
+    pub fn set_literal(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(name.into(), Value::Literal(value.into()));
+    }
+
+    /// Set a variable (builder form)
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Add the enabled entries of a variable list
+    ///
+    /// Secret variables take their value from the vault. Secrets that are not
+    /// in the vault (or all of them, without a vault) are left undefined.
+    /// While the vault is locked, secrets are only marked, so that requests
+    /// that do not use them can still be sent; see [`Variables::is_locked`].
+    pub fn extend_from(
+        &mut self,
+        variables: &[Variable],
+        vault: Option<&SecretVault>,
+    ) -> VaultResult<()> {
+        for variable in variables.iter().filter(|variable| variable.enabled) {
+            let value = match (&variable.secret, vault) {
+                (None, _) => Some(variable.value.clone()),
+                (Some(secret), Some(vault)) => match vault.get(secret) {
+                    Ok(value) => value,
+                    Err(VaultError::Locked) => {
+                        self.0
+                            .insert(variable.key.trim().to_string(), Value::Locked);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                },
+                (Some(_), None) => None,
+            };
+            match value {
+                Some(value) => self.set(variable.key.trim(), value),
+                None => {
+                    self.0.remove(variable.key.trim());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the raw value of a variable
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).and_then(Value::as_str)
+    }
+
+    /// Check whether a variable is a secret of the locked vault, i.e. its
+    /// placeholders are unresolved only until the vault is unlocked
+    pub fn is_locked(&self, name: &str) -> bool {
+        matches!(self.0.get(name), Some(Value::Locked))
+    }
+
+    /// Check whether no variable is defined
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Replace the placeholders in a text
+    ///
+    /// # Returns
+    /// The resolved text, or the names of the placeholders that could not be
+    /// resolved (undefined, or part of a cycle)
+    pub fn render(&self, text: &str) -> Result<String, Vec<String>> {
+        self.render_dynamic(text, &mut DynamicValues::new())
+    }
+
+    /// Replace the placeholders in a text, taking dynamic values from the
+    /// current execution
+    pub fn render_dynamic(
+        &self,
+        text: &str,
+        dynamic: &mut DynamicValues,
+    ) -> Result<String, Vec<String>> {
+        let mut unresolved = Vec::new();
+        let rendered = self.render_with(text, &mut Vec::new(), &mut unresolved, dynamic);
+        if unresolved.is_empty() {
+            Ok(rendered)
+        } else {
+            Err(unresolved)
+        }
+    }
+
+    fn render_with<'a>(
+        &'a self,
+        text: &str,
+        stack: &mut Vec<&'a str>,
+        unresolved: &mut Vec<String>,
+        dynamic: &mut DynamicValues,
+    ) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = closing_braces(&rest[start + 2..]) else {
+                break;
+            };
+            let name = rest[start + 2..start + 2 + end].trim();
+            let placeholder = &rest[start..start + end + 4];
+            output.push_str(&rest[..start]);
+
+            if name.starts_with('$') {
+                // Arguments of dynamic variables may use placeholders
+                let known_unresolved = unresolved.len();
+                let expression = self.render_with(name, stack, unresolved, dynamic);
+                if unresolved.len() > known_unresolved {
+                    output.push_str(placeholder);
+                } else if let Some(value) = dynamic.get(&expression) {
+                    output.push_str(&value);
+                } else {
+                    if !unresolved.contains(&expression) {
+                        unresolved.push(expression);
+                    }
+                    output.push_str(placeholder);
+                }
+                rest = &rest[start + end + 4..];
+                continue;
+            }
+
+            match self.0.get_key_value(name) {
+                Some((_, Value::Literal(value))) => output.push_str(value),
+                Some((name, Value::Template(value)))
+                    if !stack.contains(&name.as_str()) && stack.len() < MAX_DEPTH =>
+                {
+                    stack.push(name);
+                    output.push_str(&self.render_with(value, stack, unresolved, dynamic));
+                    stack.pop();
+                }
+                // Empty braces are left as they are
+                _ if name.is_empty() => output.push_str(placeholder),
+                // Undefined, a cycle, or a secret of the locked vault
+                _ => {
+                    if !unresolved.iter().any(|known| known == name) {
+                        unresolved.push(name.to_string());
+                    }
+                    output.push_str(placeholder);
+                }
+            }
+            rest = &rest[start + end + 4..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+/// Find the `}}` that closes a placeholder, skipping nested placeholders
+fn closing_braces(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"{{" => {
+                depth += 1;
+                i += 2;
+            }
+            b"}}" if depth == 0 => return Some(i),
+            b"}}" => {
+                depth -= 1;
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Resolves the placeholders of a request, collecting every failure
+struct Resolver<'a> {
+    variables: &'a Variables,
+    dynamic: DynamicValues,
+    unresolved: Vec<UnresolvedVariable>,
+}
+
+impl Resolver<'_> {
+    fn render(&mut self, text: &mut String, location: impl Fn() -> String) {
+        match self.variables.render_dynamic(text, &mut self.dynamic) {
+            Ok(rendered) => *text = rendered,
+            Err(names) => {
+                let location = location();
+                self.unresolved
+                    .extend(names.into_iter().map(|name| UnresolvedVariable {
+                        name,
+                        location: location.clone(),
+                    }));
+            }
+        }
+    }
+
+    /// Render the strings in a JSON value, e.g. a JWT claim
+    fn render_json(&mut self, value: &mut serde_json::Value, location: &str) {
+        match value {
+            serde_json::Value::String(text) => self.render(text, || location.to_string()),
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.render_json(item, location);
+                }
+            }
+            serde_json::Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    self.render_json(field, &format!("{}.{}", location, name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render_pairs(&mut self, pairs: &mut [KeyValue], location: &str) {
+        for pair in pairs.iter_mut().filter(|pair| pair.enabled) {
+            let key = pair.key.clone();
+            self.render(&mut pair.key, || format!("{}.{}", location, key));
+            self.render(&mut pair.value, || format!("{}.{}", location, key));
+        }
+    }
+}
+
+/// A request with its placeholders replaced
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedRequest {
+    /// The request as it will be sent
+    pub request: Request,
+
+    /// Values generated for the dynamic variables the request uses
+    pub dynamic_variables: Vec<DynamicVariable>,
+}
+
+/// Resolve the `{{name}}` placeholders of a request
+///
+/// The URL, query and path parameters, headers, body, auth and a manual proxy
+/// set on the request are resolved;
+/// disabled entries are left untouched since they are never sent. Dynamic
+/// variables are evaluated afresh on each call.
+///
+/// # Returns
+/// The request as it will be sent, or every placeholder that could not be
+/// resolved
+pub fn resolve_request(
+    request: &Request,
+    variables: &Variables,
+) -> Result<ResolvedRequest, Vec<UnresolvedVariable>> {
+    let mut request = request.clone();
+    let mut resolver = Resolver {
+        variables,
+        dynamic: DynamicValues::new(),
+        unresolved: Vec::new(),
+    };
+
+    resolver.render(&mut request.url, || "url".to_string());
+    resolver.render_pairs(&mut request.query_params, "query_params");
+    resolver.render_pairs(&mut request.path_params, "path_params");
+
+    request.headers = request
+        .headers
+        .iter()
+        .map(|header| {
+            let Header { mut key, mut value } = header.clone();
+            resolver.render(&mut key, || format!("headers.{}", header.key));
+            resolver.render(&mut value, || format!("headers.{}", header.key));
+            (key, value)
+        })
+        .collect();
+
+    match &mut request.body {
+        RequestBody::None => {}
+        RequestBody::Raw { content, .. } | RequestBody::Json { content } => {
+            resolver.render(content, || "body".to_string());
+        }
+        RequestBody::Binary { path, .. } => resolver.render(path, || "body.path".to_string()),
+        RequestBody::FormUrlencoded { fields } => resolver.render_pairs(fields, "body"),
+        RequestBody::Multipart { parts } => {
+            for part in parts.iter_mut().filter(|part| part.is_enabled()) {
+                let location = format!("body.{}", part.name());
+                match part {
+                    MultipartPart::Text { name, value, .. } => {
+                        resolver.render(name, || location.clone());
+                        resolver.render(value, || location.clone());
+                    }
+                    MultipartPart::File {
+                        name,
+                        path,
+                        file_name,
+                        ..
+                    } => {
+                        resolver.render(name, || location.clone());
+                        resolver.render(path, || location.clone());
+                        if let Some(file_name) = file_name {
+                            resolver.render(file_name, || location.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    match &mut request.auth {
+        Auth::None | Auth::Inherit => {}
+        Auth::Basic { username, password } => {
+            resolver.render(username, || "auth.username".to_string());
+            resolver.render(password, || "auth.password".to_string());
+        }
+        Auth::Bearer { token } => resolver.render(token, || "auth.token".to_string()),
+        Auth::ApiKey { key, value, .. } => {
+            resolver.render(key, || "auth.key".to_string());
+            resolver.render(value, || "auth.value".to_string());
+        }
+        Auth::OAuth2(config) => {
+            for (field, value) in [
+                ("token_url", &mut config.token_url),
+                ("client_id", &mut config.client_id),
+                ("client_secret", &mut config.client_secret),
+                ("scope", &mut config.scope),
+                ("username", &mut config.username),
+                ("password", &mut config.password),
+                ("authorization_url", &mut config.authorization_url),
+            ] {
+                resolver.render(value, || format!("auth.{}", field));
+            }
+        }
+        Auth::Digest { username, password } => {
+            resolver.render(username, || "auth.username".to_string());
+            resolver.render(password, || "auth.password".to_string());
+        }
+        Auth::Hmac(config) => {
+            for (field, value) in [
+                ("secret", &mut config.secret),
+                ("canonical", &mut config.canonical),
+                ("header", &mut config.header),
+                ("value", &mut config.value),
+            ] {
+                resolver.render(value, || format!("auth.{}", field));
+            }
+        }
+        Auth::Jwt(config) => {
+            resolver.render(&mut config.key, || "auth.key".to_string());
+            resolver.render(&mut config.key_id, || "auth.key_id".to_string());
+            for (name, value) in config.claims.iter_mut() {
+                resolver.render_json(value, &format!("auth.claims.{}", name));
+            }
+        }
+        Auth::AwsSigV4(config) => {
+            for (field, value) in [
+                ("access_key_id", &mut config.access_key_id),
+                ("secret_access_key", &mut config.secret_access_key),
+                ("session_token", &mut config.session_token),
+                ("region", &mut config.region),
+                ("service", &mut config.service),
+            ] {
+                resolver.render(value, || format!("auth.{}", field));
+            }
+        }
+    }
+
+    if let Some(ProxyConfig::Manual(proxy)) = request
+        .settings
+        .as_mut()
+        .and_then(|settings| settings.proxy.as_mut())
+    {
+        resolver.render(&mut proxy.url, || "settings.proxy.url".to_string());
+        for (field, value) in [
+            ("username", &mut proxy.username),
+            ("password", &mut proxy.password),
+        ] {
+            if let Some(value) = value {
+                resolver.render(value, || format!("settings.proxy.{}", field));
+            }
+        }
+    }
+
+    if resolver.unresolved.is_empty() {
+        Ok(ResolvedRequest {
+            request,
+            dynamic_variables: resolver.dynamic.into_values(),
+        })
+    } else {
+        Err(resolver.unresolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let variables = Variables::new()
+            .with("host", "api.example.com")
+            .with("base_url", "https://{{ host }}/v1")
+            .with("loop", "{{loop}}");
+
+        assert_eq!(
+            variables.render("{{base_url}}/users").unwrap(),
+            "https://api.example.com/v1/users"
+        );
+        assert_eq!(
+            variables.render("{{}} and {{ unclosed").unwrap(),
+            "{{}} and {{ unclosed"
+        );
+        assert_eq!(
+            variables.render("{{token}} {{missing}} {{token}} {{loop}}"),
+            Err(vec![
+                "token".to_string(),
+                "missing".to_string(),
+                "loop".to_string()
+            ])
+        );
+
+        // Literal values are used as they are
+        let mut variables = variables;
+        variables.set_literal("token", "{{host}} {{$uuid}}");
+        assert_eq!(
+            variables.render("Bearer {{token}}").unwrap(),
+            "Bearer {{host}} {{$uuid}}"
+        );
+    }
+
+    #[test]
+    fn test_secrets_of_locked_vault() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        let variables = [
+            Variable::new("host", "api.example.com"),
+            Variable::secret("token", "api-token"),
+            Variable::new("authorization", "Bearer {{token}}"),
+        ];
+
+        // Only placeholders that use the secret are unresolved
+        let mut locked = Variables::new();
+        locked.extend_from(&variables, Some(&vault)).unwrap();
+        assert!(locked.is_locked("token"));
+        assert!(!locked.is_locked("host"));
+        assert_eq!(locked.get("token"), None);
+        assert_eq!(locked.render("{{host}}").unwrap(), "api.example.com");
+        assert_eq!(
+            locked.render("{{authorization}}"),
+            Err(vec!["token".to_string()])
+        );
+
+        vault.unlock("passphrase").unwrap();
+        vault.set("api-token", "t0ken").unwrap();
+        let mut unlocked = Variables::new();
+        unlocked.extend_from(&variables, Some(&vault)).unwrap();
+        assert!(!unlocked.is_locked("token"));
+        assert_eq!(
+            unlocked.render("{{authorization}}").unwrap(),
+            "Bearer t0ken"
+        );
+    }
+
+    #[test]
+    fn test_render_dynamic_variables() {
+        let variables = Variables::new()
+            .with("user", "alice")
+            .with("key", "{{$uuid}}");
+        let mut dynamic = DynamicValues::new();
+
+        let rendered = variables
+            .render_dynamic("{{key}} {{ $uuid }} {{$base64 {{user}}:pw}}", &mut dynamic)
+            .unwrap();
+        let (key, rest) = rendered.split_once(' ').unwrap();
+        assert_eq!(key.len(), 36);
+        assert_eq!(rest, format!("{} YWxpY2U6cHc=", key));
+        assert_eq!(
+            variables.render_dynamic("{{$nope}} {{$base64 {{missing}}}}", &mut dynamic),
+            Err(vec!["$nope".to_string(), "missing".to_string()])
+        );
+
+        let recorded = dynamic.into_values();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].expression, "$uuid");
+        assert_eq!(recorded[0].value, key);
+        assert_eq!(recorded[1].expression, "$base64 alice:pw");
+    }
+
+    #[test]
+    fn test_resolve_request() {
+        let mut variables = Variables::new();
+        variables
+            .extend_from(
+                &[
+                    Variable::new("base_url", "https://api.example.com"),
+                    Variable::new("token", "t0ken"),
+                    Variable::new("ignored", "x").disabled(),
+                ],
+                None,
+            )
+            .unwrap();
+
+        let request = Request::new("Users", "{{base_url}}/users/:id")
+            .with_path_param("id", "{{user_id}}")
+            .with_query_param("limit", "{{limit}}")
+            .with_header("Authorization", "Bearer {{token}}")
+            .with_body(RequestBody::json(
+                r#"{"token":"{{token}}","by":"{{ignored}}"}"#,
+            ))
+            .with_auth(Auth::basic("{{user}}", "{{token}}"));
+        let mut disabled = request.clone();
+        disabled.query_params[0] = disabled.query_params[0].clone().disabled();
+
+        let unresolved = resolve_request(&request, &variables).unwrap_err();
+        assert_eq!(
+            unresolved
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "{{limit}} in query_params.limit",
+                "{{user_id}} in path_params.id",
+                "{{ignored}} in body",
+                "{{user}} in auth.username",
+            ]
+        );
+
+        variables.set("user_id", "7");
+        variables.set("user", "alice");
+        variables.set("ignored", "me");
+        let resolved = resolve_request(&disabled, &variables).unwrap().request;
+        assert_eq!(resolved.build_url(), "https://api.example.com/users/7");
+        assert_eq!(resolved.headers.get("Authorization"), Some("Bearer t0ken"));
+        assert_eq!(
+            resolved.body,
+            RequestBody::json(r#"{"token":"t0ken","by":"me"}"#)
+        );
+        assert_eq!(resolved.auth, Auth::basic("alice", "t0ken"));
+        // Disabled entries keep their placeholders
+        assert_eq!(resolved.query_params[0].value, "{{limit}}");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::models::ResponseTimings;
+use crate::services::tls::TlsHandshake;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use rustls::client::{ClientSessionStore, Tls12ClientSessionValue, Tls13ClientSessionValue};
+use rustls::pki_types::ServerName;
+use rustls::NamedGroup;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+
+tokio::task_local! {
+    /// Recorder of the request currently being sent on this task
+    static CURRENT: PhaseRecorder;
+}
+
+/// Instants at which each connection phase started or ended
+///
+/// All fields stay `None` when the request reused a pooled connection.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionPhases {
+    pub connect_start: Option<Instant>,
+    pub dns_start: Option<Instant>,
+    pub dns_end: Option<Instant>,
+    pub tls_start: Option<Instant>,
+    pub connect_end: Option<Instant>,
+
+    /// What the TLS handshake negotiated, reported by the TLS configuration
+    pub handshake: Option<TlsHandshake>,
+}
+
+impl ConnectionPhases {
+    /// Whether the request went out on an existing pooled connection
+    pub fn reused(&self) -> bool {
+        self.connect_start.is_none()
+    }
+
+    /// Turn the recorded instants into phase durations
+    ///
+    /// # Arguments
+    /// * `sent_at` - When the request was handed to the client
+    /// * `headers_at` - When the response headers arrived
+    /// * `finished_at` - When the response body was fully read
+    pub fn timings(
+        &self,
+        sent_at: Instant,
+        headers_at: Instant,
+        finished_at: Instant,
+    ) -> ResponseTimings {
+        let between = |start: Option<Instant>, end: Option<Instant>| {
+            Some(end?.saturating_duration_since(start?))
+        };
+
+        ResponseTimings {
+            dns: between(self.dns_start, self.dns_end),
+            tcp: between(
+                self.dns_end.or(self.connect_start),
+                self.tls_start.or(self.connect_end),
+            ),
+            tls: between(self.tls_start, self.connect_end),
+            ttfb: headers_at.saturating_duration_since(self.connect_end.unwrap_or(sent_at)),
+            download: finished_at.saturating_duration_since(headers_at),
+        }
+    }
+}
+
+/// Collects connection phases for one request
+///
+/// The recorder is made current for the duration of `scope`; the resolver,
+/// connector layer and TLS session store below, and the certificate verifier
+/// and cipher suites of the TLS configuration, report into whichever
+/// recorder is current on the task that drives the connection.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseRecorder(Arc<Mutex<ConnectionPhases>>);
+
+impl PhaseRecorder {
+    /// Run a future with this recorder as the current one
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        CURRENT.scope(self.clone(), future).await
+    }
+
+    /// Get the phases recorded so far
+    pub fn phases(&self) -> ConnectionPhases {
+        self.0
+            .lock()
+            .map(|phases| phases.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Update the current recorder, if any
+pub(crate) fn record(update: impl FnOnce(&mut ConnectionPhases)) {
+    let _ = CURRENT.try_with(|recorder| {
+        if let Ok(mut phases) = recorder.0.lock() {
+            update(&mut phases);
+        }
+    });
+}
+
+/// DNS resolver that records how long each lookup takes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimingResolver;
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            record(|phases| phases.dns_start = Some(Instant::now()));
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            record(|phases| phases.dns_end = Some(Instant::now()));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Connector layer marking when a new connection starts and is ready
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimingLayer;
+
+impl<S> Layer<S> for TimingLayer {
+    type Service = TimingConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimingConnector { inner }
+    }
+}
+
+/// Connector wrapped by `TimingLayer`
+#[derive(Debug, Clone)]
+pub struct TimingConnector<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimingConnector<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            // Start afresh so only the connection being opened is reported
+            record(|phases| {
+                *phases = ConnectionPhases {
+                    connect_start: Some(Instant::now()),
+                    ..ConnectionPhases::default()
+                }
+            });
+            let result = connecting.await;
+            if result.is_ok() {
+                record(|phases| phases.connect_end = Some(Instant::now()));
+            }
+            result
+        })
+    }
+}
+
+/// TLS session store that marks the start of each handshake
+///
+/// rustls asks the store for a key exchange hint while building the
+/// ClientHello, which is the first thing a handshake does. Everything else
+/// is delegated to the wrapped store.
+#[derive(Debug)]
+pub struct TimingSessionStore {
+    inner: Arc<dyn ClientSessionStore>,
+}
+
+impl TimingSessionStore {
+    /// Wrap an existing session store
+    pub fn new(inner: Arc<dyn ClientSessionStore>) -> Self {
+        Self { inner }
+    }
+}
+
+impl ClientSessionStore for TimingSessionStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.inner.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        record(|phases| {
+            // Keep the first handshake when tunnelling TLS through a TLS proxy
+            phases.tls_start.get_or_insert_with(Instant::now);
+        });
+        self.inner.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.inner.set_tls12_session(server_name, value)
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        self.inner.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.inner.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.inner.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        self.inner.take_tls13_ticket(server_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timings_for_new_connection() {
+        let sent_at = Instant::now();
+        let phases = ConnectionPhases {
+            connect_start: Some(sent_at),
+            dns_start: Some(sent_at),
+            dns_end: Some(sent_at + Duration::from_millis(5)),
+            tls_start: Some(sent_at + Duration::from_millis(15)),
+            connect_end: Some(sent_at + Duration::from_millis(40)),
+            handshake: None,
+        };
+
+        let timings = phases.timings(
+            sent_at,
+            sent_at + Duration::from_millis(100),
+            sent_at + Duration::from_millis(130),
+        );
+
+        assert!(!phases.reused());
+        assert_eq!(timings.dns, Some(Duration::from_millis(5)));
+        assert_eq!(timings.tcp, Some(Duration::from_millis(10)));
+        assert_eq!(timings.tls, Some(Duration::from_millis(25)));
+        assert_eq!(timings.ttfb, Duration::from_millis(60));
+        assert_eq!(timings.download, Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_timings_for_reused_connection() {
+        let sent_at = Instant::now();
+        let phases = ConnectionPhases::default();
+
+        let timings = phases.timings(
+            sent_at,
+            sent_at + Duration::from_millis(20),
+            sent_at + Duration::from_millis(25),
+        );
+
+        assert!(phases.reused());
+        assert_eq!(timings.dns, None);
+        assert_eq!(timings.tcp, None);
+        assert_eq!(timings.tls, None);
+        assert_eq!(timings.ttfb, Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_record_only_reaches_current_recorder() {
+        let recorder = PhaseRecorder::default();
+
+        record(|phases| phases.dns_start = Some(Instant::now()));
+        assert!(recorder.phases().dns_start.is_none());
+
+        recorder
+            .scope(async { record(|phases| phases.dns_start = Some(Instant::now())) })
+            .await;
+        assert!(recorder.phases().dns_start.is_some());
+    }
+}
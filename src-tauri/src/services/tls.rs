@@ -0,0 +1,923 @@
+use crate::models::{CertificateInfo, ClientIdentity, TlsInfo};
+use crate::services::timing::{self, TimingSessionStore};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{
+    ClientSessionMemoryCache, Resumption, VerifierBuilderError, WebPkiServerVerifier,
+};
+use rustls::crypto::cipher::{
+    AeadKey, Iv, KeyBlockShape, MessageDecrypter, MessageEncrypter, Tls12AeadAlgorithm,
+    Tls13AeadAlgorithm, UnsupportedOperationError,
+};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{
+    AlertDescription, CertificateError, CipherSuite, ConnectionTrafficSecrets,
+    DigitallySignedStruct, DistinguishedName, ProtocolVersion, RootCertStore, SignatureScheme,
+    SupportedCipherSuite, Tls12CipherSuite, Tls13CipherSuite,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+/// Number of TLS sessions remembered per client for resumption
+const SESSION_CACHE_SIZE: usize = 256;
+
+/// PEM label of an encrypted PKCS#8 private key
+const ENCRYPTED_KEY_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+
+/// Error loading the certificates and keys of a TLS configuration
+#[derive(Debug, thiserror::Error)]
+pub enum TlsConfigError {
+    #[error("Failed to read '{path}': {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid certificate or key in '{path}': {message}")]
+    Invalid { path: String, message: String },
+
+    /// The private key is encrypted and no passphrase was supplied
+    #[error("Passphrase required for '{0}'")]
+    PassphraseRequired(String),
+
+    /// The supplied passphrase could not decrypt the private key
+    #[error("Wrong passphrase for '{0}'")]
+    WrongPassphrase(String),
+
+    #[error("Failed to build the certificate verifier: {0}")]
+    Verifier(#[from] VerifierBuilderError),
+
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Why a TLS handshake failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsFailureReason {
+    /// The server certificate has expired
+    Expired,
+
+    /// The server certificate is not valid yet
+    NotYetValid,
+
+    /// The server certificate is not valid for the requested host
+    HostnameMismatch,
+
+    /// The server certificate was not issued by a trusted CA
+    UnknownIssuer,
+
+    /// The server certificate has been revoked
+    Revoked,
+
+    /// The server certificate is malformed, badly signed or not meant for
+    /// servers
+    InvalidCertificate,
+
+    /// The server did not accept the client certificate, or required one
+    ClientCertificateRejected,
+
+    /// Client and server support no common protocol version or cipher suite
+    Incompatible,
+
+    /// Any other handshake failure
+    HandshakeFailure,
+}
+
+/// A failed TLS handshake, explained
+#[derive(Debug, Clone, PartialEq, Serialize, thiserror::Error)]
+#[error("{message}")]
+pub struct TlsFailure {
+    pub reason: TlsFailureReason,
+
+    /// Description of the underlying error
+    pub message: String,
+
+    /// Certificates the server presented, if it got that far
+    pub certificates: Vec<CertificateInfo>,
+}
+
+impl TlsFailure {
+    /// Explain a request error caused by a TLS handshake
+    ///
+    /// # Arguments
+    /// * `error` - Error returned by the HTTP client
+    /// * `handshake` - What was recorded of the failed handshake
+    ///
+    /// # Returns
+    /// `None` if the error did not come from TLS
+    pub fn from_error(
+        error: &(dyn std::error::Error + 'static),
+        handshake: Option<&TlsHandshake>,
+    ) -> Option<Self> {
+        let error = find_rustls_error(error)?;
+        let reason = match error {
+            rustls::Error::InvalidCertificate(error) => match error {
+                CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+                    TlsFailureReason::Expired
+                }
+                CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+                    TlsFailureReason::NotYetValid
+                }
+                CertificateError::NotValidForName
+                | CertificateError::NotValidForNameContext { .. } => {
+                    TlsFailureReason::HostnameMismatch
+                }
+                CertificateError::UnknownIssuer => TlsFailureReason::UnknownIssuer,
+                CertificateError::Revoked => TlsFailureReason::Revoked,
+                _ => TlsFailureReason::InvalidCertificate,
+            },
+            rustls::Error::AlertReceived(
+                AlertDescription::CertificateRequired
+                | AlertDescription::BadCertificate
+                | AlertDescription::UnsupportedCertificate
+                | AlertDescription::CertificateRevoked
+                | AlertDescription::CertificateExpired
+                | AlertDescription::CertificateUnknown
+                | AlertDescription::UnknownCA,
+            ) => TlsFailureReason::ClientCertificateRejected,
+            rustls::Error::PeerIncompatible(_)
+            | rustls::Error::AlertReceived(
+                AlertDescription::HandshakeFailure
+                | AlertDescription::ProtocolVersion
+                | AlertDescription::InsufficientSecurity,
+            ) => TlsFailureReason::Incompatible,
+            _ => TlsFailureReason::HandshakeFailure,
+        };
+
+        Some(Self {
+            reason,
+            message: error.to_string(),
+            certificates: handshake
+                .map(|handshake| handshake.certificate_infos())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// What a TLS handshake negotiated, as recorded while it ran
+#[derive(Debug, Clone, Default)]
+pub struct TlsHandshake {
+    pub version: Option<ProtocolVersion>,
+    pub cipher_suite: Option<CipherSuite>,
+
+    /// Certificates presented by the server, starting with its own
+    pub certificates: Vec<CertificateDer<'static>>,
+}
+
+impl TlsHandshake {
+    /// Summarize the handshake for a response
+    ///
+    /// # Returns
+    /// `None` if the handshake did not get as far as choosing a cipher suite
+    pub fn info(&self) -> Option<TlsInfo> {
+        let protocol_version = match self.version? {
+            ProtocolVersion::TLSv1_2 => "TLSv1.2".to_string(),
+            ProtocolVersion::TLSv1_3 => "TLSv1.3".to_string(),
+            version => format!("{:?}", version),
+        };
+
+        Some(TlsInfo {
+            protocol_version,
+            cipher_suite: format!("{:?}", self.cipher_suite?),
+            certificates: self.certificate_infos(),
+        })
+    }
+
+    fn certificate_infos(&self) -> Vec<CertificateInfo> {
+        self.certificates
+            .iter()
+            .filter_map(|certificate| certificate_info(certificate))
+            .collect()
+    }
+}
+
+/// Summarize a DER encoded X.509 certificate
+///
+/// # Returns
+/// `None` if the certificate cannot be parsed
+pub fn certificate_info(der: &[u8]) -> Option<CertificateInfo> {
+    let (_, certificate) = X509Certificate::from_der(der).ok()?;
+    let validity = certificate.validity();
+    let rfc3339 = |timestamp: i64| {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    let subject_alt_names = certificate
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|extension| {
+            extension
+                .value
+                .general_names
+                .iter()
+                .map(|name| match name {
+                    GeneralName::DNSName(name) => format!("DNS:{}", name),
+                    GeneralName::IPAddress(bytes) => <[u8; 4]>::try_from(*bytes)
+                        .map(IpAddr::from)
+                        .or_else(|_| <[u8; 16]>::try_from(*bytes).map(IpAddr::from))
+                        .map_or_else(|_| name.to_string(), |ip| format!("IP:{}", ip)),
+                    GeneralName::RFC822Name(email) => format!("email:{}", email),
+                    GeneralName::URI(uri) => format!("URI:{}", uri),
+                    name => name.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(CertificateInfo {
+        subject: certificate.subject().to_string(),
+        issuer: certificate.issuer().to_string(),
+        subject_alt_names,
+        not_before: rfc3339(validity.not_before.timestamp()),
+        not_after: rfc3339(validity.not_after.timestamp()),
+        serial_number: certificate.raw_serial_as_string(),
+        fingerprint_sha256: hex_with_colons(&Sha256::digest(der)),
+    })
+}
+
+fn hex_with_colons(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Find the rustls error behind a client error
+///
+/// `std::io::Error` hides the error it wraps from `source()`, so it is
+/// unwrapped explicitly.
+fn find_rustls_error<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a rustls::Error> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(error) = error.downcast_ref::<rustls::Error>() {
+            return Some(error);
+        }
+        current = match error.downcast_ref::<std::io::Error>() {
+            Some(error) => error
+                .get_ref()
+                .map(|error| error as &(dyn std::error::Error + 'static)),
+            None => error.source(),
+        };
+    }
+    None
+}
+
+/// Build the rustls configuration used by a `reqwest::Client`
+///
+/// The configuration trusts the bundled Mozilla roots plus any extra CA
+/// certificates, offers HTTP/2 and HTTP/1.1 through ALPN and records the
+/// start of each handshake for the timing breakdown, along with the
+/// server's certificates and the negotiated version and cipher suite.
+///
+/// # Arguments
+/// * `verify_tls` - Whether server certificates are verified
+/// * `ca_certificates` - Paths of extra trusted root certificates
+/// * `identity` - Client certificate presented when the server asks for one
+/// * `passphrase` - Passphrase of the identity's private key, if encrypted
+pub fn client_config(
+    verify_tls: bool,
+    ca_certificates: &[String],
+    identity: Option<&ClientIdentity>,
+    passphrase: Option<&str>,
+) -> Result<rustls::ClientConfig, TlsConfigError> {
+    let provider = Arc::new(CryptoProvider {
+        cipher_suites: recording_cipher_suites().to_vec(),
+        ..crypto::ring::default_provider()
+    });
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let verifier: Arc<dyn ServerCertVerifier> = if verify_tls {
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        for path in ca_certificates {
+            for certificate in load_certificates(path)? {
+                roots.add(certificate).map_err(|e| invalid(path, e))?;
+            }
+        }
+        WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()?
+    } else {
+        Arc::new(NoVerifier(provider))
+    };
+    let builder = builder
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(RecordingVerifier(verifier)));
+
+    let mut config = match identity {
+        Some(identity) => {
+            let (chain, key) = load_identity(identity, passphrase)?;
+            builder.with_client_auth_cert(chain, key)?
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config.resumption = Resumption::store(Arc::new(TimingSessionStore::new(Arc::new(
+        ClientSessionMemoryCache::new(SESSION_CACHE_SIZE),
+    ))));
+
+    Ok(config)
+}
+
+/// Update the handshake of the current phase recorder, if any
+fn record_handshake(update: impl FnOnce(&mut TlsHandshake)) {
+    timing::record(|phases| update(phases.handshake.get_or_insert_with(Default::default)));
+}
+
+/// The default cipher suites, wrapped to report which one a handshake picks
+///
+/// The negotiated suite is only exposed on the rustls connection, which
+/// reqwest keeps to itself. Instead, the AEAD of each suite records the
+/// suite when the handshake asks it for the first traffic keys. Suites must
+/// be `'static`, so they are built once and leaked.
+fn recording_cipher_suites() -> &'static [SupportedCipherSuite] {
+    static SUITES: OnceLock<Vec<SupportedCipherSuite>> = OnceLock::new();
+    SUITES.get_or_init(|| {
+        crypto::ring::default_provider()
+            .cipher_suites
+            .into_iter()
+            .map(|suite| match suite {
+                SupportedCipherSuite::Tls13(suite) => {
+                    let aead_alg = Box::leak(Box::new(RecordingTls13Aead {
+                        suite: suite.common.suite,
+                        inner: suite.aead_alg,
+                    }));
+                    SupportedCipherSuite::Tls13(Box::leak(Box::new(Tls13CipherSuite {
+                        common: rustls::CipherSuiteCommon { ..suite.common },
+                        hkdf_provider: suite.hkdf_provider,
+                        aead_alg,
+                        quic: suite.quic,
+                    })))
+                }
+                SupportedCipherSuite::Tls12(suite) => {
+                    let aead_alg = Box::leak(Box::new(RecordingTls12Aead {
+                        suite: suite.common.suite,
+                        inner: suite.aead_alg,
+                    }));
+                    SupportedCipherSuite::Tls12(Box::leak(Box::new(Tls12CipherSuite {
+                        common: rustls::CipherSuiteCommon { ..suite.common },
+                        prf_provider: suite.prf_provider,
+                        kx: suite.kx,
+                        sign: suite.sign,
+                        aead_alg,
+                    })))
+                }
+            })
+            .collect()
+    })
+}
+
+/// Load the certificate chain and private key of a client identity
+fn load_identity(
+    identity: &ClientIdentity,
+    passphrase: Option<&str>,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), TlsConfigError> {
+    match identity {
+        ClientIdentity::Pem { cert, key } => {
+            let chain = load_certificates(cert)?;
+            let key_path = key.as_deref().unwrap_or(cert);
+            Ok((chain, load_pem_key(key_path, passphrase)?))
+        }
+        ClientIdentity::Pkcs12 { path } => {
+            let data = read(path)?;
+            // Archives without a passphrase are usually encrypted with an
+            // empty one
+            let keystore = p12_keystore::KeyStore::from_pkcs12(&data, passphrase.unwrap_or(""))
+                .map_err(|e| match (e, passphrase) {
+                    (p12_keystore::error::Error::MacError(_), None) => {
+                        TlsConfigError::PassphraseRequired(path.clone())
+                    }
+                    (p12_keystore::error::Error::MacError(_), Some(_)) => {
+                        TlsConfigError::WrongPassphrase(path.clone())
+                    }
+                    (e, _) => invalid(path, e),
+                })?;
+            let (_, key_chain) = keystore
+                .private_key_chain()
+                .ok_or_else(|| invalid(path, "no private key"))?;
+
+            let chain = key_chain
+                .chain()
+                .iter()
+                .map(|certificate| CertificateDer::from(certificate.as_der().to_vec()))
+                .collect();
+            let key = PrivatePkcs8KeyDer::from(key_chain.key().to_vec());
+            Ok((chain, key.into()))
+        }
+    }
+}
+
+/// Load every certificate in a PEM file, or a single DER certificate
+fn load_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, TlsConfigError> {
+    let data = read(path)?;
+    let certificates = CertificateDer::pem_slice_iter(&data)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(path, e))?;
+
+    if !certificates.is_empty() {
+        Ok(certificates)
+    } else if data.first() == Some(&0x30) {
+        // An ASN.1 sequence: assume a DER encoded certificate
+        Ok(vec![CertificateDer::from(data)])
+    } else {
+        Err(invalid(path, "no certificate found"))
+    }
+}
+
+/// Load a PEM private key, decrypting it if needed
+fn load_pem_key(
+    path: &str,
+    passphrase: Option<&str>,
+) -> Result<PrivateKeyDer<'static>, TlsConfigError> {
+    let data = read(path)?;
+    let text = String::from_utf8_lossy(&data);
+
+    let begin = format!("-----BEGIN {}-----", ENCRYPTED_KEY_LABEL);
+    let end = format!("-----END {}-----", ENCRYPTED_KEY_LABEL);
+    let Some(start) = text.find(&begin) else {
+        return PrivateKeyDer::from_pem_slice(&data).map_err(|e| invalid(path, e));
+    };
+
+    let passphrase =
+        passphrase.ok_or_else(|| TlsConfigError::PassphraseRequired(path.to_string()))?;
+    let stop = text[start..]
+        .find(&end)
+        .map(|index| start + index + end.len())
+        .ok_or_else(|| invalid(path, "unterminated encrypted key"))?;
+
+    let (_, document) =
+        pkcs8::Document::from_pem(&text[start..stop]).map_err(|e| invalid(path, e))?;
+    let encrypted = pkcs8::EncryptedPrivateKeyInfo::try_from(document.as_bytes())
+        .map_err(|e| invalid(path, e))?;
+    let decrypted = encrypted
+        .decrypt(passphrase)
+        .map_err(|_| TlsConfigError::WrongPassphrase(path.to_string()))?;
+
+    Ok(PrivatePkcs8KeyDer::from(decrypted.as_bytes().to_vec()).into())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, TlsConfigError> {
+    std::fs::read(path).map_err(|source| TlsConfigError::Read {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn invalid(path: &str, message: impl std::fmt::Display) -> TlsConfigError {
+    TlsConfigError::Invalid {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Verifier that accepts any server certificate, used when TLS verification
+/// is turned off for a request
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Verifier that records the certificates presented by the server before
+/// handing them to the verifier doing the actual checks
+#[derive(Debug)]
+struct RecordingVerifier(Arc<dyn ServerCertVerifier>);
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        record_handshake(|handshake| {
+            handshake.certificates = std::iter::once(end_entity)
+                .chain(intermediates)
+                .map(|certificate| certificate.clone().into_owned())
+                .collect();
+        });
+        self.0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+
+    fn root_hint_subjects(&self) -> Option<&[DistinguishedName]> {
+        self.0.root_hint_subjects()
+    }
+}
+
+/// TLS 1.3 AEAD that records its suite as the negotiated one
+struct RecordingTls13Aead {
+    suite: CipherSuite,
+    inner: &'static dyn Tls13AeadAlgorithm,
+}
+
+impl RecordingTls13Aead {
+    fn record(&self) {
+        record_handshake(|handshake| {
+            handshake.version = Some(ProtocolVersion::TLSv1_3);
+            handshake.cipher_suite = Some(self.suite);
+        });
+    }
+}
+
+impl Tls13AeadAlgorithm for RecordingTls13Aead {
+    fn encrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageEncrypter> {
+        self.record();
+        self.inner.encrypter(key, iv)
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageDecrypter> {
+        self.record();
+        self.inner.decrypter(key, iv)
+    }
+
+    fn key_len(&self) -> usize {
+        self.inner.key_len()
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        self.inner.extract_keys(key, iv)
+    }
+
+    fn fips(&self) -> bool {
+        self.inner.fips()
+    }
+}
+
+/// TLS 1.2 AEAD that records its suite as the negotiated one
+struct RecordingTls12Aead {
+    suite: CipherSuite,
+    inner: &'static dyn Tls12AeadAlgorithm,
+}
+
+impl RecordingTls12Aead {
+    fn record(&self) {
+        record_handshake(|handshake| {
+            handshake.version = Some(ProtocolVersion::TLSv1_2);
+            handshake.cipher_suite = Some(self.suite);
+        });
+    }
+}
+
+impl Tls12AeadAlgorithm for RecordingTls12Aead {
+    fn encrypter(&self, key: AeadKey, iv: &[u8], extra: &[u8]) -> Box<dyn MessageEncrypter> {
+        self.record();
+        self.inner.encrypter(key, iv, extra)
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: &[u8]) -> Box<dyn MessageDecrypter> {
+        self.record();
+        self.inner.decrypter(key, iv)
+    }
+
+    fn key_block_shape(&self) -> KeyBlockShape {
+        self.inner.key_block_shape()
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        self.inner.extract_keys(key, iv, explicit)
+    }
+
+    fn fips(&self) -> bool {
+        self.inner.fips()
+    }
+}
+
+/// Certificates and a minimal HTTPS server for TLS tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{crypto, RootCertStore};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// A certificate authority issuing test certificates
+    pub struct TestCa {
+        pub cert: rcgen::Certificate,
+        pub key: KeyPair,
+    }
+
+    impl TestCa {
+        pub fn new(common_name: &str) -> Self {
+            let mut params = CertificateParams::default();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+            let key = KeyPair::generate().unwrap();
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        /// Issue a leaf certificate for the given subject alternative names
+        pub fn issue(&self, common_name: &str, names: &[&str]) -> (rcgen::Certificate, KeyPair) {
+            self.issue_with(common_name, names, |_| {})
+        }
+
+        /// Issue a leaf certificate, adjusting its parameters before signing
+        pub fn issue_with(
+            &self,
+            common_name: &str,
+            names: &[&str],
+            adjust: impl FnOnce(&mut CertificateParams),
+        ) -> (rcgen::Certificate, KeyPair) {
+            let names = names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+            let mut params = CertificateParams::new(names).unwrap();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+            adjust(&mut params);
+            let key = KeyPair::generate().unwrap();
+            let cert = params.signed_by(&key, &self.cert, &self.key).unwrap();
+            (cert, key)
+        }
+    }
+
+    /// Start an HTTPS server on localhost and return its port
+    ///
+    /// Every request gets a `200` whose body is the number of certificates
+    /// the client presented. With `client_ca` set, connections without a
+    /// client certificate issued by it are rejected during the handshake.
+    pub async fn https_server(
+        cert: &rcgen::Certificate,
+        key: &KeyPair,
+        client_ca: Option<&rcgen::Certificate>,
+    ) -> u16 {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+        let builder = match client_ca {
+            Some(ca) => {
+                let mut roots = RootCertStore::empty();
+                roots.add(ca.der().clone()).unwrap();
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .unwrap();
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let key = PrivateKeyDer::from(PrivatePkcs8KeyDer::from(key.serialize_der()));
+        let config = builder
+            .with_single_cert(vec![cert.der().clone()], key)
+            .unwrap();
+
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let presented = stream
+                        .get_ref()
+                        .1
+                        .peer_certificates()
+                        .map_or(0, |certificates| certificates.len());
+
+                    let mut head = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => head.extend_from_slice(&chunk[..read]),
+                        }
+                    }
+
+                    let body = presented.to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        port
+    }
+
+    /// Encrypt a private key as an `ENCRYPTED PRIVATE KEY` PEM block
+    pub fn encrypted_pem(key: &KeyPair, passphrase: &str) -> String {
+        let der = key.serialize_der();
+        let info = pkcs8::PrivateKeyInfo::try_from(der.as_slice()).unwrap();
+        let params =
+            pkcs8::pkcs5::pbes2::Parameters::pbkdf2_sha256_aes256cbc(2048, b"saltsalt", &[7; 16])
+                .unwrap();
+        let document = info.encrypt_with_params(params, passphrase).unwrap();
+        document
+            .to_pem("ENCRYPTED PRIVATE KEY", pkcs8::LineEnding::LF)
+            .unwrap()
+            .to_string()
+    }
+
+    /// Bundle a certificate chain and key into a PKCS#12 archive
+    pub fn pkcs12(chain: &[&rcgen::Certificate], key: &KeyPair, passphrase: &str) -> Vec<u8> {
+        let chain = chain
+            .iter()
+            .map(|cert| p12_keystore::Certificate::from_der(cert.der()).unwrap());
+        let key_chain = p12_keystore::PrivateKeyChain::new(key.serialize_der(), b"id", chain);
+
+        let mut keystore = p12_keystore::KeyStore::new();
+        keystore.add_entry(
+            "client",
+            p12_keystore::KeyStoreEntry::PrivateKeyChain(key_chain),
+        );
+        keystore.writer(passphrase).write().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn test_pem_identity_with_encrypted_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = TestCa::new("Test CA");
+        let (cert, key) = ca.issue("client", &["client"]);
+
+        let cert_path = dir.path().join("client.pem");
+        let key_path = dir.path().join("client.key");
+        std::fs::write(&cert_path, cert.pem()).unwrap();
+        std::fs::write(&key_path, encrypted_pem(&key, "hunter2")).unwrap();
+        let identity = ClientIdentity::pem(cert_path.to_string_lossy(), key_path.to_string_lossy());
+
+        assert!(matches!(
+            client_config(true, &[], Some(&identity), None),
+            Err(TlsConfigError::PassphraseRequired(path)) if path == identity.key_path()
+        ));
+        assert!(matches!(
+            client_config(true, &[], Some(&identity), Some("wrong")),
+            Err(TlsConfigError::WrongPassphrase(_))
+        ));
+        assert!(client_config(true, &[], Some(&identity), Some("hunter2")).is_ok());
+
+        // Unencrypted keys may share the certificate file
+        std::fs::write(&cert_path, cert.pem() + &key.serialize_pem()).unwrap();
+        let identity = ClientIdentity::Pem {
+            cert: cert_path.to_string_lossy().into_owned(),
+            key: None,
+        };
+        assert!(client_config(true, &[], Some(&identity), None).is_ok());
+    }
+
+    #[test]
+    fn test_pkcs12_identity_and_ca_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = TestCa::new("Test CA");
+        let (cert, key) = ca.issue("client", &["client"]);
+
+        let p12_path = dir.path().join("client.p12");
+        std::fs::write(&p12_path, pkcs12(&[&cert, &ca.cert], &key, "s3cret")).unwrap();
+        let identity = ClientIdentity::pkcs12(p12_path.to_string_lossy());
+
+        assert!(matches!(
+            client_config(true, &[], Some(&identity), None),
+            Err(TlsConfigError::PassphraseRequired(_))
+        ));
+        assert!(matches!(
+            client_config(true, &[], Some(&identity), Some("nope")),
+            Err(TlsConfigError::WrongPassphrase(_))
+        ));
+        assert!(client_config(true, &[], Some(&identity), Some("s3cret")).is_ok());
+
+        // CA files may be PEM bundles or DER
+        let pem_path = dir.path().join("ca.pem");
+        std::fs::write(&pem_path, ca.cert.pem() + &cert.pem()).unwrap();
+        let der_path = dir.path().join("ca.der");
+        std::fs::write(&der_path, ca.cert.der()).unwrap();
+        let paths = [
+            pem_path.to_string_lossy().into_owned(),
+            der_path.to_string_lossy().into_owned(),
+        ];
+        assert!(client_config(true, &paths, None, None).is_ok());
+
+        let missing = [dir
+            .path()
+            .join("missing.pem")
+            .to_string_lossy()
+            .into_owned()];
+        assert!(matches!(
+            client_config(true, &missing, None, None),
+            Err(TlsConfigError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn test_certificate_info() {
+        let ca = TestCa::new("Test CA");
+        let (cert, _) = ca.issue_with("localhost", &["localhost", "127.0.0.1"], |params| {
+            params.not_before = rcgen::date_time_ymd(2024, 1, 1);
+            params.not_after = rcgen::date_time_ymd(2034, 1, 1);
+        });
+
+        let info = certificate_info(cert.der()).unwrap();
+        assert_eq!(info.subject, "CN=localhost");
+        assert_eq!(info.issuer, "CN=Test CA");
+        assert_eq!(
+            info.subject_alt_names,
+            vec!["DNS:localhost", "IP:127.0.0.1"]
+        );
+        assert_eq!(info.not_before, "2024-01-01T00:00:00+00:00");
+        assert_eq!(info.not_after, "2034-01-01T00:00:00+00:00");
+        assert!(!info.serial_number.is_empty());
+
+        let fingerprint = hex_with_colons(&Sha256::digest(cert.der()));
+        assert_eq!(info.fingerprint_sha256, fingerprint);
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+
+        assert!(certificate_info(b"not a certificate").is_none());
+    }
+}
@@ -1,5 +1,5 @@
-use crate::models::{Collection, Request};
-use crate::storage::{YAMLStore, YAMLStoreResult};
+use crate::models::{Collection, Environment, Request};
+use crate::storage::{SecretVault, YAMLStore, YAMLStoreError, YAMLStoreResult};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -19,6 +19,12 @@ pub mod constants {
 
     /// File extension for request files
     pub const REQUEST_EXT: &str = ".request.yaml";
+
+    /// File extension for cookie jars, kept next to their collection file
+    pub const COOKIE_JAR_EXT: &str = ".cookies.yaml";
+
+    /// File extension for environment files
+    pub const ENVIRONMENT_EXT: &str = ".env.yaml";
 }
 
 /// Types of file system changes
@@ -73,6 +79,15 @@ impl CollectionManager {
         })
     }
 
+    /// Refuse to save files containing the value of a secret of this vault
+    ///
+    /// # Arguments
+    /// * `vault` - The secret vault shared with request execution
+    pub fn with_secret_vault(mut self, vault: Arc<SecretVault>) -> Self {
+        self.yaml_store = self.yaml_store.with_secret_vault(vault);
+        self
+    }
+
     /// Scan the base directory for all collection files
     ///
     /// # Returns
@@ -89,6 +104,14 @@ impl CollectionManager {
         Self::scan_directory_recursive(&self.base_path, constants::REQUEST_EXT)
     }
 
+    /// Scan the base directory for all environment files
+    ///
+    /// # Returns
+    /// Vector of paths to all environment files found
+    pub fn scan_environments(&self) -> YAMLStoreResult<Vec<PathBuf>> {
+        Self::scan_directory_recursive(&self.base_path, constants::ENVIRONMENT_EXT)
+    }
+
     /// Load a collection from disk and add it to the index
     ///
     /// # Arguments
@@ -224,6 +247,58 @@ impl CollectionManager {
         Ok(())
     }
 
+    /// Create a new environment file in the base directory
+    ///
+    /// # Arguments
+    /// * `environment` - The environment to save
+    /// * `filename` - The name of the file (without extension)
+    ///
+    /// # Returns
+    /// The full path to the new file
+    pub fn create_environment(
+        &self,
+        environment: &Environment,
+        filename: &str,
+    ) -> YAMLStoreResult<PathBuf> {
+        if filename.trim().is_empty() || filename.contains('/') || filename.contains('\\') {
+            return Err(YAMLStoreError::ValidationError(format!(
+                "Invalid environment filename: {}",
+                filename
+            )));
+        }
+
+        let path = self
+            .base_path
+            .join(format!("{}{}", filename, constants::ENVIRONMENT_EXT));
+        if path.exists() {
+            return Err(YAMLStoreError::ValidationError(format!(
+                "Environment file already exists: {}",
+                path.display()
+            )));
+        }
+
+        self.yaml_store.save_environment(environment, path)
+    }
+
+    /// Load an environment file
+    pub fn load_environment<P: AsRef<Path>>(&self, path: P) -> YAMLStoreResult<Environment> {
+        self.yaml_store.load_environment(path)
+    }
+
+    /// Overwrite an existing environment file
+    pub fn save_environment<P: AsRef<Path>>(
+        &self,
+        path: P,
+        environment: &Environment,
+    ) -> YAMLStoreResult<PathBuf> {
+        self.yaml_store.save_environment(environment, path)
+    }
+
+    /// Delete an environment file
+    pub fn delete_environment<P: AsRef<Path>>(&self, path: P) -> YAMLStoreResult<()> {
+        self.yaml_store.delete_file(path)
+    }
+
     /// Start watching the collections directory for file changes
     ///
     /// This method sets up a file system watcher that will automatically
@@ -303,6 +378,11 @@ impl CollectionManager {
         // Validate each request
         Self::validate_requests(&mut fixed, &mut issues, fix_issues);
 
+        // Settings can't be fixed automatically; report them only
+        if let Err(e) = fixed.settings.validate() {
+            issues.push(format!("Invalid collection settings: {}", e));
+        }
+
         (fixed, issues)
     }
 
@@ -564,6 +644,37 @@ mod tests {
         assert_eq!(collections.len(), 2);
     }
 
+    #[test]
+    fn test_environment_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CollectionManager::new(temp_dir.path()).unwrap();
+        manager
+            .save_collection(&create_test_collection("API"), "api")
+            .unwrap();
+
+        let staging = Environment::new("Staging").with_variable("host", "staging.example.com");
+        let path = manager.create_environment(&staging, "staging").unwrap();
+        assert_eq!(path, temp_dir.path().join("staging.env.yaml"));
+        assert!(manager.create_environment(&staging, "staging").is_err());
+        assert!(manager.create_environment(&staging, "../up").is_err());
+
+        // Environments in subdirectories are found too; collections are not
+        let nested = temp_dir.path().join("team").join("local.env.yaml");
+        manager
+            .save_environment(&nested, &Environment::new("Local"))
+            .unwrap();
+        let mut environments = manager.scan_environments().unwrap();
+        environments.sort();
+        assert_eq!(environments, vec![path.clone(), nested]);
+
+        assert_eq!(manager.load_environment(&path).unwrap(), staging);
+        manager.delete_environment(&path).unwrap();
+        assert!(matches!(
+            manager.load_environment(&path),
+            Err(YAMLStoreError::FileNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_load_all_collections() {
         let temp_dir = TempDir::new().unwrap();
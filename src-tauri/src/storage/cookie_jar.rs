@@ -0,0 +1,492 @@
+use crate::models::{Cookie, CookieList};
+use crate::storage::collection_manager::constants;
+use crate::storage::yaml_store::{YAMLStoreError, YAMLStoreResult};
+use cookie_store::{CookieDomain, CookieError, CookieExpiration, CookieStore, RawCookie};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use url::Url;
+
+/// Cookies of one collection, persisted to a YAML file next to it
+///
+/// Storing and matching follow RFC 6265 (domain, path, `Secure` and expiry
+/// rules). Session cookies are saved as well so a login survives restarting
+/// the application. The file holds live credentials and is meant to be
+/// gitignored (`*.cookies.yaml`).
+#[derive(Debug)]
+pub struct CookieJar {
+    path: PathBuf,
+    state: RwLock<JarState>,
+}
+
+#[derive(Debug)]
+struct JarState {
+    enabled: bool,
+    store: CookieStore,
+}
+
+/// On-disk form of a cookie jar
+#[derive(Serialize, Deserialize)]
+struct JarFile {
+    #[serde(default = "enabled_by_default")]
+    enabled: bool,
+
+    #[serde(default)]
+    cookies: Vec<cookie_store::Cookie<'static>>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl CookieJar {
+    /// Load a cookie jar from a YAML file
+    ///
+    /// Expired cookies are dropped. A missing file means an empty, enabled
+    /// jar.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the jar file, created when the first cookie is
+    ///   stored
+    pub fn load<P: AsRef<Path>>(path: P) -> YAMLStoreResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let file = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            serde_yaml::from_str::<Option<JarFile>>(&content)?
+        } else {
+            None
+        };
+        let (enabled, cookies) =
+            file.map_or((true, Vec::new()), |file| (file.enabled, file.cookies));
+        let store = CookieStore::from_cookies(
+            cookies.into_iter().map(Ok::<_, std::convert::Infallible>),
+            false,
+        )
+        .unwrap_or_default();
+
+        Ok(Self {
+            path,
+            state: RwLock::new(JarState { enabled, store }),
+        })
+    }
+
+    /// Get the path of the jar file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether requests send and store cookies
+    pub fn is_enabled(&self) -> bool {
+        self.state
+            .read()
+            .map(|state| state.enabled)
+            .unwrap_or(false)
+    }
+
+    /// Enable or disable the jar; stored cookies are kept either way
+    pub fn set_enabled(&self, enabled: bool) -> YAMLStoreResult<()> {
+        let mut state = self.write()?;
+        state.enabled = enabled;
+        self.save(&state)
+    }
+
+    /// Build the `Cookie` header for a request URL
+    ///
+    /// # Returns
+    /// `None` if the jar is disabled or no cookie matches
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let state = self.state.read().ok()?;
+        if !state.enabled {
+            return None;
+        }
+
+        let header = state
+            .store
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+
+    /// Store the cookies set by a response and save the jar
+    ///
+    /// Cookies the URL may not set (wrong domain, `Secure` over plain HTTP,
+    /// ...) are ignored, as are all cookies while the jar is disabled.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the response
+    /// * `set_cookie` - Values of its `Set-Cookie` headers
+    pub fn store_response_cookies<'a>(
+        &self,
+        url: &Url,
+        set_cookie: impl IntoIterator<Item = &'a str>,
+    ) -> YAMLStoreResult<()> {
+        let cookies = set_cookie
+            .into_iter()
+            .filter_map(|value| RawCookie::parse(value.to_string()).ok())
+            .collect::<Vec<_>>();
+        if cookies.is_empty() {
+            return Ok(());
+        }
+
+        let mut state = self.write()?;
+        if !state.enabled {
+            return Ok(());
+        }
+        state.store.store_response_cookies(cookies.into_iter(), url);
+        self.save(&state)
+    }
+
+    /// List the unexpired cookies
+    pub fn list(&self) -> CookieList {
+        let Ok(state) = self.state.read() else {
+            return CookieList {
+                enabled: false,
+                cookies: Vec::new(),
+            };
+        };
+
+        let mut cookies: Vec<Cookie> = state.store.iter_unexpired().map(to_model).collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+
+        CookieList {
+            enabled: state.enabled,
+            cookies,
+        }
+    }
+
+    /// Add a cookie, or replace the one with the same domain, path and name
+    pub fn set_cookie(&self, cookie: &Cookie) -> YAMLStoreResult<()> {
+        cookie
+            .validate()
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?;
+        let (raw, url) = to_raw(cookie)?;
+
+        let mut state = self.write()?;
+        match state.store.insert_raw(&raw, &url) {
+            // An expired cookie only removes the one it replaces
+            Ok(_) | Err(CookieError::Expired) => self.save(&state),
+            Err(e) => Err(YAMLStoreError::ValidationError(format!(
+                "Invalid cookie '{}': {}",
+                cookie.name, e
+            ))),
+        }
+    }
+
+    /// Remove a cookie
+    ///
+    /// # Returns
+    /// `true` if the cookie existed
+    pub fn remove_cookie(&self, domain: &str, path: &str, name: &str) -> YAMLStoreResult<bool> {
+        let mut state = self.write()?;
+        let removed = state.store.remove(domain, path, name).is_some();
+        if removed {
+            self.save(&state)?;
+        }
+        Ok(removed)
+    }
+
+    /// Remove every cookie
+    pub fn clear(&self) -> YAMLStoreResult<()> {
+        let mut state = self.write()?;
+        state.store.clear();
+        self.save(&state)
+    }
+
+    fn write(&self) -> YAMLStoreResult<RwLockWriteGuard<'_, JarState>> {
+        self.state.write().map_err(|e| {
+            YAMLStoreError::ValidationError(format!("Cookie jar lock poisoned: {}", e))
+        })
+    }
+
+    /// Write the jar to disk; called with the write lock held so saves
+    /// never interleave
+    fn save(&self, state: &JarState) -> YAMLStoreResult<()> {
+        let mut cookies: Vec<_> = state.store.iter_unexpired().cloned().collect();
+        cookies.sort_by(|a, b| {
+            (String::from(&a.domain), String::from(&a.path), a.name()).cmp(&(
+                String::from(&b.domain),
+                String::from(&b.path),
+                b.name(),
+            ))
+        });
+        let file = JarFile {
+            enabled: state.enabled,
+            cookies,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Atomic write: write to temporary file first
+        let temp_path = self.path.with_extension("yaml.tmp");
+        let mut temp_file = fs::File::create(&temp_path)?;
+        temp_file.write_all(serde_yaml::to_string(&file)?.as_bytes())?;
+        temp_file.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Cookie jars of all collections, each loaded on first use
+#[derive(Debug, Default)]
+pub struct CookieJars {
+    jars: RwLock<HashMap<PathBuf, Arc<CookieJar>>>,
+}
+
+impl CookieJars {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cookie jar of a collection, loading it on first use
+    ///
+    /// # Arguments
+    /// * `collection_path` - Path of the collection file
+    pub fn for_collection(&self, collection_path: &Path) -> YAMLStoreResult<Arc<CookieJar>> {
+        let path = cookie_jar_path(collection_path);
+        if let Some(jar) = self
+            .jars
+            .read()
+            .ok()
+            .and_then(|jars| jars.get(&path).cloned())
+        {
+            return Ok(jar);
+        }
+
+        let jar = Arc::new(CookieJar::load(&path)?);
+        match self.jars.write() {
+            // Keep the first jar if another task loaded it meanwhile
+            Ok(mut jars) => Ok(jars.entry(path).or_insert(jar).clone()),
+            Err(_) => Ok(jar),
+        }
+    }
+}
+
+/// Get the path of a collection's cookie jar
+///
+/// `api.collection.yaml` keeps its cookies in `api.cookies.yaml` in the same
+/// directory.
+pub fn cookie_jar_path(collection_path: &Path) -> PathBuf {
+    let file_name = collection_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = file_name
+        .strip_suffix(constants::COLLECTION_EXT)
+        .unwrap_or(&file_name);
+    collection_path.with_file_name(format!("{}{}", stem, constants::COOKIE_JAR_EXT))
+}
+
+fn to_model(cookie: &cookie_store::Cookie<'_>) -> Cookie {
+    let (domain, host_only) = match &cookie.domain {
+        CookieDomain::HostOnly(domain) => (domain.clone(), true),
+        domain => (String::from(domain), false),
+    };
+    let expires = match &cookie.expires {
+        CookieExpiration::AtUtc(time) => {
+            chrono::DateTime::from_timestamp(time.unix_timestamp(), 0).map(|time| time.to_rfc3339())
+        }
+        CookieExpiration::SessionEnd => None,
+    };
+
+    Cookie {
+        name: cookie.name().to_string(),
+        value: cookie.value().to_string(),
+        domain,
+        host_only,
+        path: String::from(&cookie.path),
+        expires,
+        secure: cookie.secure().unwrap_or(false),
+        http_only: cookie.http_only().unwrap_or(false),
+        same_site: cookie.same_site().map(|same_site| same_site.to_string()),
+    }
+}
+
+/// Turn an edited cookie into a `Set-Cookie` equivalent and a URL allowed to
+/// set it
+fn to_raw(cookie: &Cookie) -> YAMLStoreResult<(RawCookie<'static>, Url)> {
+    let domain = cookie.domain.trim().trim_start_matches('.').to_string();
+    let mut builder = RawCookie::build((cookie.name.clone(), cookie.value.clone()))
+        .path(cookie.path.clone())
+        .secure(cookie.secure)
+        .http_only(cookie.http_only);
+
+    if !cookie.host_only {
+        builder = builder.domain(domain.clone());
+    }
+    if let Some(expires) = &cookie.expires {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(expires)
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?
+            .timestamp();
+        let expires = cookie::time::OffsetDateTime::from_unix_timestamp(timestamp)
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?;
+        builder = builder.expires(expires);
+    }
+    if let Some(same_site) = &cookie.same_site {
+        builder = builder.same_site(match same_site.as_str() {
+            "Strict" => cookie::SameSite::Strict,
+            "Lax" => cookie::SameSite::Lax,
+            _ => cookie::SameSite::None,
+        });
+    }
+
+    let url = Url::parse(&format!("https://{}{}", domain, cookie.path)).map_err(|e| {
+        YAMLStoreError::ValidationError(format!("Invalid cookie domain '{}': {}", domain, e))
+    })?;
+
+    Ok((builder.build(), url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_cookie_jar_path() {
+        assert_eq!(
+            cookie_jar_path(Path::new("/work/api.collection.yaml")),
+            PathBuf::from("/work/api.cookies.yaml")
+        );
+    }
+
+    #[test]
+    fn test_rfc6265_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let jar = CookieJar::load(temp_dir.path().join("api.cookies.yaml")).unwrap();
+
+        jar.store_response_cookies(
+            &url("https://api.example.com/v1/login"),
+            [
+                "session=abc; Path=/; HttpOnly",
+                "shared=1; Domain=example.com; Path=/",
+                "scoped=2; Path=/v1/users",
+                "secure=3; Secure",
+                "gone=4; Max-Age=0",
+                "evil=5; Domain=other.com",
+            ],
+        )
+        .unwrap();
+
+        // Host-only cookies stay on their host, domain cookies reach
+        // subdomains
+        let header = jar.cookie_header(&url("https://api.example.com/")).unwrap();
+        assert!(header.contains("session=abc"));
+        assert!(header.contains("shared=1"));
+        assert!(!header.contains("scoped"));
+        assert!(!header.contains("gone"));
+        assert!(!header.contains("evil"));
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/")),
+            Some("shared=1".to_string())
+        );
+
+        // Path and Secure rules
+        let header = jar
+            .cookie_header(&url("https://api.example.com/v1/users/7"))
+            .unwrap();
+        assert!(header.contains("scoped=2"));
+        let header = jar
+            .cookie_header(&url("http://api.example.com/v1"))
+            .unwrap();
+        assert!(!header.contains("secure=3"));
+
+        let list = jar.list();
+        assert!(list.enabled);
+        assert_eq!(list.cookies.len(), 4);
+        let session = list.cookies.iter().find(|c| c.name == "session").unwrap();
+        assert!(session.host_only);
+        assert!(session.http_only);
+        assert_eq!(session.domain, "api.example.com");
+    }
+
+    #[test]
+    fn test_jar_persists_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("api.cookies.yaml");
+        let jar = CookieJar::load(&path).unwrap();
+
+        jar.set_cookie(
+            &Cookie::new("token", "t0ken", "example.com").with_expires("2099-01-01T00:00:00Z"),
+        )
+        .unwrap();
+        jar.set_cookie(
+            &Cookie::new("expired", "x", "example.com").with_expires("2000-01-01T00:00:00Z"),
+        )
+        .unwrap();
+        jar.store_response_cookies(&url("https://example.com/"), ["session=s1"])
+            .unwrap();
+        jar.set_enabled(false).unwrap();
+
+        let reloaded = CookieJar::load(&path).unwrap();
+        let list = reloaded.list();
+        assert!(!list.enabled);
+        assert_eq!(
+            list.cookies
+                .iter()
+                .map(|cookie| cookie.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["session", "token"]
+        );
+        assert_eq!(
+            list.cookies[1].expires.as_deref(),
+            Some("2099-01-01T00:00:00+00:00")
+        );
+
+        // A disabled jar neither sends nor stores cookies
+        assert!(reloaded
+            .cookie_header(&url("https://example.com/"))
+            .is_none());
+        reloaded
+            .store_response_cookies(&url("https://example.com/"), ["new=1"])
+            .unwrap();
+        assert_eq!(reloaded.list().cookies.len(), 2);
+
+        assert!(reloaded.remove_cookie("example.com", "/", "token").unwrap());
+        assert!(!reloaded.remove_cookie("example.com", "/", "token").unwrap());
+        reloaded.clear().unwrap();
+        assert!(CookieJar::load(&path).unwrap().list().cookies.is_empty());
+    }
+
+    #[test]
+    fn test_set_cookie_rejects_invalid_cookies() {
+        let temp_dir = TempDir::new().unwrap();
+        let jar = CookieJar::load(temp_dir.path().join("api.cookies.yaml")).unwrap();
+
+        let result = jar.set_cookie(&Cookie::new("bad name", "x", "example.com"));
+        assert!(matches!(result, Err(YAMLStoreError::ValidationError(_))));
+        assert!(!jar.path().exists());
+    }
+
+    #[test]
+    fn test_jars_are_shared_per_collection() {
+        let temp_dir = TempDir::new().unwrap();
+        let jars = CookieJars::new();
+        let collection = temp_dir.path().join("api.collection.yaml");
+
+        let jar = jars.for_collection(&collection).unwrap();
+        assert!(Arc::ptr_eq(
+            &jar,
+            &jars.for_collection(&collection).unwrap()
+        ));
+        assert_eq!(jar.path(), temp_dir.path().join("api.cookies.yaml"));
+
+        let other = jars
+            .for_collection(&temp_dir.path().join("other.collection.yaml"))
+            .unwrap();
+        assert!(!Arc::ptr_eq(&jar, &other));
+    }
+}
@@ -1,7 +1,13 @@
 pub mod collection_manager;
+pub mod cookie_jar;
 pub mod request_store;
+pub mod secret_vault;
+pub mod settings_store;
 pub mod yaml_store;
 
 pub use collection_manager::CollectionManager;
+pub use cookie_jar::{cookie_jar_path, CookieJar, CookieJars};
 pub use request_store::RequestStore;
+pub use secret_vault::{SecretVault, VaultError, VaultResult};
+pub use settings_store::SettingsStore;
 pub use yaml_store::{YAMLStore, YAMLStoreError, YAMLStoreResult};
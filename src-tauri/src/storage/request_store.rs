@@ -258,7 +258,7 @@ impl Default for RequestStore {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::HttpMethod;
+    use crate::models::{HttpMethod, RequestBody};
 
     #[test]
     fn test_new_store_is_empty() {
@@ -532,6 +532,6 @@ mod tests {
             retrieved.headers.get("Authorization").unwrap(),
             "Bearer token123"
         );
-        assert_eq!(retrieved.body, Some("{ \"data\": \"test\" }".to_string()));
+        assert_eq!(retrieved.body, RequestBody::from("{ \"data\": \"test\" }"));
     }
 }
@@ -0,0 +1,440 @@
+use crate::storage::yaml_store::YAMLStoreError;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockWriteGuard};
+
+/// Secrets shorter than this are not looked for in files being saved, as
+/// they would match ordinary text
+const MIN_GUARDED_LEN: usize = 4;
+
+/// Error type for secret vault operations
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    /// The vault must be unlocked with its passphrase first
+    #[error("Secret vault is locked")]
+    Locked,
+
+    #[error("Wrong passphrase for the secret vault")]
+    WrongPassphrase,
+
+    #[error("Invalid secret vault file: {0}")]
+    Corrupt(String),
+
+    #[error(transparent)]
+    Store(#[from] YAMLStoreError),
+}
+
+pub type VaultResult<T> = Result<T, VaultError>;
+
+/// Secret values kept encrypted in a local file
+///
+/// The file is encrypted with XChaCha20-Poly1305 under a key derived from a
+/// passphrase with Argon2id, and should live outside the collections
+/// directory. The vault starts locked; once unlocked the secrets stay in
+/// memory for the rest of the session. Unlocking a vault that has no file yet
+/// sets its passphrase.
+pub struct SecretVault {
+    path: PathBuf,
+    state: RwLock<Option<Unlocked>>,
+
+    /// Kept when the vault is locked, so files can still be checked for
+    /// leaks; unset until the vault is first unlocked
+    fingerprints: RwLock<Option<Fingerprints>>,
+}
+
+struct Unlocked {
+    key: Key,
+    kdf: KdfParams,
+    secrets: BTreeMap<String, String>,
+}
+
+/// Salted hashes of the guarded secret values
+struct Fingerprints {
+    salt: [u8; 16],
+
+    /// Byte lengths of the values
+    lengths: BTreeSet<usize>,
+
+    /// Secret names by the hash of their value
+    hashes: HashMap<[u8; 32], String>,
+}
+
+/// On-disk form of the vault
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    kdf: KdfParams,
+
+    /// Base64 nonce of the ciphertext
+    nonce: String,
+
+    /// Base64 encrypted JSON object of secret names to values
+    ciphertext: String,
+}
+
+/// Argon2id parameters used to derive the key
+#[derive(Clone, Serialize, Deserialize)]
+struct KdfParams {
+    /// Base64 random salt
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl std::fmt::Debug for SecretVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretVault")
+            .field("path", &self.path)
+            .field("unlocked", &self.is_unlocked())
+            .finish()
+    }
+}
+
+impl SecretVault {
+    /// Open a vault file; nothing is read until it is unlocked
+    ///
+    /// # Arguments
+    /// * `path` - Path of the vault file, created when the first secret is
+    ///   stored
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            state: RwLock::new(None),
+            fingerprints: RwLock::new(None),
+        }
+    }
+
+    /// Get the path of the vault file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check whether the vault file exists
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Check whether the secrets can be read
+    pub fn is_unlocked(&self) -> bool {
+        self.state
+            .read()
+            .map(|state| state.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Decrypt the vault with its passphrase
+    ///
+    /// If the vault file does not exist yet, the passphrase becomes the
+    /// vault's passphrase.
+    pub fn unlock(&self, passphrase: &str) -> VaultResult<()> {
+        let unlocked = if self.path.exists() {
+            let content = fs::read_to_string(&self.path).map_err(YAMLStoreError::from)?;
+            let file: VaultFile = serde_yaml::from_str(&content).map_err(YAMLStoreError::from)?;
+
+            let key = derive_key(passphrase, &file.kdf)?;
+            let nonce = decode(&file.nonce)?;
+            if nonce.len() != 24 {
+                return Err(VaultError::Corrupt("invalid nonce".to_string()));
+            }
+            let plaintext = XChaCha20Poly1305::new(&key)
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    decode(&file.ciphertext)?.as_slice(),
+                )
+                .map_err(|_| VaultError::WrongPassphrase)?;
+            let secrets = serde_json::from_slice(&plaintext)
+                .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+
+            Unlocked {
+                key,
+                kdf: file.kdf,
+                secrets,
+            }
+        } else {
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            let kdf = KdfParams {
+                salt: STANDARD.encode(salt),
+                memory_kib: Params::DEFAULT_M_COST,
+                iterations: Params::DEFAULT_T_COST,
+                parallelism: Params::DEFAULT_P_COST,
+            };
+
+            Unlocked {
+                key: derive_key(passphrase, &kdf)?,
+                kdf,
+                secrets: BTreeMap::new(),
+            }
+        };
+
+        self.remember(&unlocked.secrets);
+        *self.write()? = Some(unlocked);
+        Ok(())
+    }
+
+    /// Forget the key and the decrypted secrets
+    ///
+    /// Salted hashes of the values are kept to check files for leaks.
+    pub fn lock(&self) {
+        if let Ok(mut state) = self.state.write() {
+            *state = None;
+        }
+    }
+
+    /// List the names of the stored secrets
+    pub fn names(&self) -> VaultResult<Vec<String>> {
+        let state = self.state.read().map_err(|_| VaultError::Locked)?;
+        let unlocked = state.as_ref().ok_or(VaultError::Locked)?;
+        Ok(unlocked.secrets.keys().cloned().collect())
+    }
+
+    /// Get the value of a secret
+    ///
+    /// # Returns
+    /// `None` if no secret has this name
+    pub fn get(&self, name: &str) -> VaultResult<Option<String>> {
+        let state = self.state.read().map_err(|_| VaultError::Locked)?;
+        let unlocked = state.as_ref().ok_or(VaultError::Locked)?;
+        Ok(unlocked.secrets.get(name).cloned())
+    }
+
+    /// Store a secret, replacing any previous value, and save the vault
+    pub fn set(&self, name: &str, value: &str) -> VaultResult<()> {
+        if name.trim().is_empty() {
+            return Err(
+                YAMLStoreError::ValidationError("Secret name cannot be empty".to_string()).into(),
+            );
+        }
+
+        let mut state = self.write()?;
+        let unlocked = state.as_mut().ok_or(VaultError::Locked)?;
+        unlocked
+            .secrets
+            .insert(name.trim().to_string(), value.to_string());
+        self.remember(&unlocked.secrets);
+        self.save(unlocked)
+    }
+
+    /// Remove a secret and save the vault
+    ///
+    /// # Returns
+    /// `true` if the secret existed
+    pub fn remove(&self, name: &str) -> VaultResult<bool> {
+        let mut state = self.write()?;
+        let unlocked = state.as_mut().ok_or(VaultError::Locked)?;
+        let removed = unlocked.secrets.remove(name).is_some();
+        if removed {
+            self.remember(&unlocked.secrets);
+            self.save(unlocked)?;
+        }
+        Ok(removed)
+    }
+
+    /// Find a secret whose value appears in a text
+    ///
+    /// Used before writing files that are meant to be committed. A locked
+    /// vault is checked against the hashes of the values it held when last
+    /// unlocked; see [`SecretVault::can_find_leaks`].
+    ///
+    /// # Returns
+    /// The name of the first secret found
+    pub fn find_leak(&self, text: &str) -> Option<String> {
+        if let Some(unlocked) = self.state.read().ok()?.as_ref() {
+            return unlocked
+                .secrets
+                .iter()
+                .find(|(_, value)| value.len() >= MIN_GUARDED_LEN && text.contains(value.as_str()))
+                .map(|(name, _)| name.clone());
+        }
+
+        let fingerprints = self.fingerprints.read().ok()?;
+        let fingerprints = fingerprints.as_ref()?;
+        fingerprints.lengths.iter().find_map(|&length| {
+            text.as_bytes().windows(length).find_map(|window| {
+                fingerprints
+                    .hashes
+                    .get(&fingerprint(&fingerprints.salt, window))
+                    .cloned()
+            })
+        })
+    }
+
+    /// Check whether [`SecretVault::find_leak`] knows the secret values,
+    /// i.e. the vault has been unlocked in this session
+    pub fn can_find_leaks(&self) -> bool {
+        self.fingerprints
+            .read()
+            .map(|fingerprints| fingerprints.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Hash the guarded values, for checks while the vault is locked
+    fn remember(&self, secrets: &BTreeMap<String, String>) {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let guarded = secrets
+            .iter()
+            .filter(|(_, value)| value.len() >= MIN_GUARDED_LEN);
+        let fingerprints = Fingerprints {
+            salt,
+            lengths: guarded.clone().map(|(_, value)| value.len()).collect(),
+            hashes: guarded
+                .map(|(name, value)| (fingerprint(&salt, value.as_bytes()), name.clone()))
+                .collect(),
+        };
+        if let Ok(mut state) = self.fingerprints.write() {
+            *state = Some(fingerprints);
+        }
+    }
+
+    fn write(&self) -> VaultResult<RwLockWriteGuard<'_, Option<Unlocked>>> {
+        self.state.write().map_err(|e| {
+            YAMLStoreError::ValidationError(format!("Secret vault lock poisoned: {}", e)).into()
+        })
+    }
+
+    /// Encrypt the secrets under a fresh nonce and write the file
+    fn save(&self, unlocked: &Unlocked) -> VaultResult<()> {
+        let plaintext = serde_json::to_vec(&unlocked.secrets)
+            .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&unlocked.key)
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+        let file = VaultFile {
+            kdf: unlocked.kdf.clone(),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(YAMLStoreError::from)?;
+        }
+
+        // Atomic write: write to temporary file first
+        let temp_path = self.path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut temp_file = fs::File::create(&temp_path)?;
+            temp_file.write_all(serde_yaml::to_string(&file).unwrap_or_default().as_bytes())?;
+            temp_file.sync_all()?;
+            fs::rename(&temp_path, &self.path)
+        };
+        write().map_err(YAMLStoreError::from)?;
+
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, kdf: &KdfParams) -> VaultResult<Key> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &decode(&kdf.salt)?, &mut key)
+        .map_err(|e| VaultError::Corrupt(e.to_string()))?;
+    Ok(key)
+}
+
+fn fingerprint(salt: &[u8], value: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(salt)
+        .chain_update(value)
+        .finalize()
+        .into()
+}
+
+fn decode(value: &str) -> VaultResult<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| VaultError::Corrupt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_vault_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("secrets.vault");
+        let vault = SecretVault::new(&path);
+
+        assert!(!vault.is_unlocked());
+        assert!(matches!(vault.set("token", "x"), Err(VaultError::Locked)));
+
+        vault.unlock("correct horse").unwrap();
+        vault.set("prod-token", "s3cr3t-value").unwrap();
+        vault.set("short", "abc").unwrap();
+        assert_eq!(vault.names().unwrap(), vec!["prod-token", "short"]);
+
+        // Nothing is written in plaintext
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t-value"));
+        assert!(!content.contains("prod-token"));
+
+        let reopened = SecretVault::new(&path);
+        assert!(matches!(
+            reopened.get("prod-token"),
+            Err(VaultError::Locked)
+        ));
+        assert!(matches!(
+            reopened.unlock("wrong"),
+            Err(VaultError::WrongPassphrase)
+        ));
+        reopened.unlock("correct horse").unwrap();
+        assert_eq!(
+            reopened.get("prod-token").unwrap().as_deref(),
+            Some("s3cr3t-value")
+        );
+        assert!(reopened.remove("short").unwrap());
+        assert!(!reopened.remove("short").unwrap());
+
+        reopened.lock();
+        assert!(!reopened.is_unlocked());
+    }
+
+    #[test]
+    fn test_find_leak() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = SecretVault::new(temp_dir.path().join("secrets.vault"));
+        assert_eq!(vault.find_leak("anything"), None);
+        assert!(!vault.can_find_leaks());
+
+        vault.unlock("pass").unwrap();
+        vault.set("token", "s3cr3t-value").unwrap();
+        vault.set("pin", "12").unwrap();
+        assert!(vault.can_find_leaks());
+
+        assert_eq!(
+            vault.find_leak("value: Bearer s3cr3t-value\n"),
+            Some("token".to_string())
+        );
+        assert_eq!(vault.find_leak("port: 1234\n"), None);
+
+        // The values are still guarded once the vault is locked
+        vault.lock();
+        assert!(vault.can_find_leaks());
+        assert_eq!(
+            vault.find_leak("value: Bearer s3cr3t-value\n"),
+            Some("token".to_string())
+        );
+        assert_eq!(vault.find_leak("value: s3cr3t\n"), None);
+        assert_eq!(vault.find_leak("port: 1234\n"), None);
+
+        // A vault never unlocked in the session knows nothing
+        let reopened = SecretVault::new(vault.path());
+        assert!(!reopened.can_find_leaks());
+        assert_eq!(reopened.find_leak("value: Bearer s3cr3t-value\n"), None);
+    }
+}
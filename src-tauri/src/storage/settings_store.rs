@@ -0,0 +1,122 @@
+use crate::models::AppSettings;
+use crate::storage::yaml_store::{YAMLStoreError, YAMLStoreResult};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Application settings persisted to a YAML file
+///
+/// The settings are kept in memory and written back on every update. A
+/// missing file means every setting is at its default.
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: RwLock<AppSettings>,
+}
+
+impl SettingsStore {
+    /// Load the settings from a YAML file
+    ///
+    /// # Arguments
+    /// * `path` - Path of the settings file, created on the first update
+    pub fn load<P: AsRef<Path>>(path: P) -> YAMLStoreResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let settings = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            serde_yaml::from_str::<Option<AppSettings>>(&content)?.unwrap_or_default()
+        } else {
+            AppSettings::default()
+        };
+
+        Ok(Self {
+            path,
+            settings: RwLock::new(settings),
+        })
+    }
+
+    /// Get a copy of the current settings
+    pub fn get(&self) -> AppSettings {
+        self.settings
+            .read()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Validate, save and apply new settings
+    pub fn update(&self, settings: AppSettings) -> YAMLStoreResult<()> {
+        settings
+            .validate()
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?;
+
+        let mut current = self.settings.write().map_err(|e| {
+            YAMLStoreError::ValidationError(format!("Settings lock poisoned: {}", e))
+        })?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Atomic write: write to temporary file first
+        let temp_path = self.path.with_extension("yaml.tmp");
+        let mut temp_file = fs::File::create(&temp_path)?;
+        temp_file.write_all(serde_yaml::to_string(&settings)?.as_bytes())?;
+        temp_file.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+
+        *current = settings;
+        Ok(())
+    }
+
+    /// Get the path of the settings file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ManualProxy, ProxyConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+
+        assert_eq!(store.get(), AppSettings::default());
+        assert!(!store.path().exists());
+    }
+
+    #[test]
+    fn test_update_persists_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.yaml");
+        let store = SettingsStore::load(&path).unwrap();
+
+        let settings = AppSettings {
+            proxy: ProxyConfig::Manual(ManualProxy::new("http://proxy.corp:3128")),
+            ..Default::default()
+        };
+        store.update(settings.clone()).unwrap();
+        assert_eq!(store.get(), settings);
+
+        let reloaded = SettingsStore::load(&path).unwrap();
+        assert_eq!(reloaded.get(), settings);
+    }
+
+    #[test]
+    fn test_update_rejects_invalid_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SettingsStore::load(temp_dir.path().join("settings.yaml")).unwrap();
+
+        let result = store.update(AppSettings {
+            proxy: ProxyConfig::Manual(ManualProxy::new("ftp://proxy")),
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(YAMLStoreError::ValidationError(_))));
+        assert_eq!(store.get(), AppSettings::default());
+        assert!(!store.path().exists());
+    }
+}
@@ -1,8 +1,10 @@
-use crate::models::{Collection, Request};
+use crate::models::{Collection, Environment, Request};
+use crate::storage::secret_vault::SecretVault;
 use serde::Serialize;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Error type for YAML storage operations
 #[derive(Debug, thiserror::Error)]
@@ -21,6 +23,11 @@ pub enum YAMLStoreError {
 
     #[error("Validation error: {0}")]
     ValidationError(String),
+
+    /// The file would contain the plaintext value of a vault secret
+    /// (contains the secret name)
+    #[error("Refusing to write the value of secret '{0}' in plaintext")]
+    SecretLeak(String),
 }
 
 pub type YAMLStoreResult<T> = Result<T, YAMLStoreError>;
@@ -28,6 +35,9 @@ pub type YAMLStoreResult<T> = Result<T, YAMLStoreError>;
 /// YAML-based file storage for requests and collections
 pub struct YAMLStore {
     base_path: PathBuf,
+
+    /// Vault whose secret values must never be written
+    secret_vault: Option<Arc<SecretVault>>,
 }
 
 impl YAMLStore {
@@ -40,7 +50,21 @@ impl YAMLStore {
             fs::create_dir_all(&base_path)?;
         }
 
-        Ok(Self { base_path })
+        Ok(Self {
+            base_path,
+            secret_vault: None,
+        })
+    }
+
+    /// Refuse to write files containing the value of a secret of this vault
+    ///
+    /// Collection, request and environment files are meant to be committed,
+    /// so secrets must only be referenced by name in them. Values can only be
+    /// checked once the vault has been unlocked in the session; until then a
+    /// warning is logged for each file written.
+    pub fn with_secret_vault(mut self, vault: Arc<SecretVault>) -> Self {
+        self.secret_vault = Some(vault);
+        self
     }
 
     /// Save a request to a YAML file
@@ -115,6 +139,43 @@ impl YAMLStore {
         Ok(collection)
     }
 
+    /// Save an environment to a YAML file
+    ///
+    /// # Arguments
+    /// * `environment` - The environment to save
+    /// * `file_path` - Path of the `.env.yaml` file
+    pub fn save_environment<P: AsRef<Path>>(
+        &self,
+        environment: &Environment,
+        file_path: P,
+    ) -> YAMLStoreResult<PathBuf> {
+        environment
+            .validate()
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?;
+
+        let full_path = self.resolve_path(file_path)?;
+        self.save_yaml(&full_path, environment)?;
+        Ok(full_path)
+    }
+
+    /// Load an environment from a YAML file
+    pub fn load_environment<P: AsRef<Path>>(&self, file_path: P) -> YAMLStoreResult<Environment> {
+        let full_path = self.resolve_path(file_path)?;
+
+        if !full_path.exists() {
+            return Err(YAMLStoreError::FileNotFound(full_path));
+        }
+
+        let contents = fs::read_to_string(&full_path)?;
+        let environment: Environment = serde_yaml::from_str(&contents)?;
+
+        environment
+            .validate()
+            .map_err(|e| YAMLStoreError::ValidationError(e.to_string()))?;
+
+        Ok(environment)
+    }
+
     /// Delete a file
     pub fn delete_file<P: AsRef<Path>>(&self, file_path: P) -> YAMLStoreResult<()> {
         let full_path = self.resolve_path(file_path)?;
@@ -155,6 +216,18 @@ impl YAMLStore {
         // Serialize to YAML
         let yaml_content = serde_yaml::to_string(data)?;
 
+        if let Some(vault) = &self.secret_vault {
+            if let Some(name) = vault.find_leak(&yaml_content) {
+                return Err(YAMLStoreError::SecretLeak(name));
+            }
+            if vault.exists() && !vault.can_find_leaks() {
+                eprintln!(
+                    "Warning: Secret vault is locked; {} was not checked for secret values",
+                    file_path.display()
+                );
+            }
+        }
+
         // Atomic write: write to temporary file first
         let temp_path = file_path.with_extension("yaml.tmp");
         let mut temp_file = fs::File::create(&temp_path)?;
@@ -207,7 +280,7 @@ impl YAMLStore {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::HttpMethod;
+    use crate::models::{HttpMethod, ManualProxy, ProxyConfig, RequestSettings};
     use tempfile::TempDir;
 
     fn create_test_request() -> Request {
@@ -249,6 +322,33 @@ mod tests {
         assert_eq!(loaded.url, request.url);
     }
 
+    #[test]
+    fn test_request_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = YAMLStore::new(temp_dir.path()).unwrap();
+
+        let request = create_test_request().with_settings(RequestSettings {
+            connect_timeout_ms: Some(2000),
+            timeout_ms: Some(10000),
+            follow_redirects: Some(true),
+            max_redirects: Some(3),
+            verify_tls: Some(false),
+            proxy: Some(ProxyConfig::Manual(
+                ManualProxy::new("http://proxy.corp:3128").with_bypass("localhost"),
+            )),
+        });
+        let saved_path = store.save_request(&request, "with-settings").unwrap();
+
+        let content = fs::read_to_string(&saved_path).unwrap();
+        assert!(content.contains("settings:"));
+        assert!(content.contains("timeout_ms: 10000"));
+        assert!(content.contains("verify_tls: false"));
+        assert!(content.contains("mode: manual"));
+
+        let loaded = store.load_request(&saved_path).unwrap();
+        assert_eq!(loaded.settings, request.settings);
+    }
+
     #[test]
     fn test_save_and_load_collection() {
         let temp_dir = TempDir::new().unwrap();
@@ -271,6 +371,36 @@ mod tests {
         assert_eq!(loaded.requests.len(), collection.requests.len());
     }
 
+    #[test]
+    fn test_refuses_to_write_secret_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = Arc::new(SecretVault::new(temp_dir.path().join("secrets.vault")));
+        let store = YAMLStore::new(temp_dir.path().join("collections"))
+            .unwrap()
+            .with_secret_vault(Arc::clone(&vault));
+        vault.unlock("pass").unwrap();
+        vault.set("api-token", "token123").unwrap();
+
+        // The test request sends `Bearer token123` in plaintext
+        let result = store.save_request(&create_test_request(), "leaky");
+        assert!(matches!(result, Err(YAMLStoreError::SecretLeak(name)) if name == "api-token"));
+        assert!(store.list_request_files().unwrap().is_empty());
+
+        // Referring to the secret by name is fine
+        let collection = Collection::new("Safe").with_secret("token", "api-token");
+        store.save_collection(&collection, "safe").unwrap();
+        let environment = Environment::new("Prod").with_variable("token", "token123");
+        assert!(store
+            .save_environment(&environment, "prod.env.yaml")
+            .is_err());
+
+        // Locking the vault does not lift the check
+        vault.lock();
+        let result = store.save_request(&create_test_request(), "leaky");
+        assert!(matches!(result, Err(YAMLStoreError::SecretLeak(name)) if name == "api-token"));
+        assert!(store.list_request_files().unwrap().is_empty());
+    }
+
     #[test]
     fn test_load_nonexistent_file() {
         let temp_dir = TempDir::new().unwrap();
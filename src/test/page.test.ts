@@ -36,11 +36,11 @@ describe('Main Page Integration', () => {
       );
     };
 
-    // Test the actual convertRecordToHeaders function from +page.svelte
-    const convertRecordToHeaders = (
-      record: Record<string, string>
+    // Test the actual convertResponseHeaders function from +page.svelte
+    const convertResponseHeaders = (
+      headers: { key: string; value: string }[]
     ): { key: string; value: string }[] => {
-      return Object.entries(record).map(([key, value]) => ({ key, value }));
+      return headers.map(({ key, value }) => ({ key, value }));
     };
 
     it('should convert headers array to record', () => {
@@ -72,19 +72,19 @@ describe('Main Page Integration', () => {
       expect(Object.keys(result)).toHaveLength(1);
     });
 
-    it('should convert record to headers array', () => {
-      const record = {
-        'Content-Type': 'application/json',
-        Authorization: 'Bearer token',
-      };
-      const result = convertRecordToHeaders(record);
-      expect(result).toHaveLength(2);
-      expect(result.find((h) => h.key === 'Content-Type')?.value).toBe('application/json');
-      expect(result.find((h) => h.key === 'Authorization')?.value).toBe('Bearer token');
+    it('should keep response headers in order with repeated names', () => {
+      const headers = [
+        { key: 'Content-Type', value: 'application/json' },
+        { key: 'Set-Cookie', value: 'a=1' },
+        { key: 'Set-Cookie', value: 'b=2' },
+      ];
+      const result = convertResponseHeaders(headers);
+      expect(result).toEqual(headers);
+      expect(result.map((h) => h.key)).not.toContain('0');
     });
 
-    it('should handle empty record', () => {
-      const result = convertRecordToHeaders({});
+    it('should handle empty response headers', () => {
+      const result = convertResponseHeaders([]);
       expect(result).toEqual([]);
     });
 
@@ -104,7 +104,9 @@ describe('Main Page Integration', () => {
         { key: 'Authorization', value: 'Bearer token123' },
       ];
       const record = convertHeadersToRecord(originalHeaders);
-      const resultHeaders = convertRecordToHeaders(record);
+      const resultHeaders = convertResponseHeaders(
+        Object.entries(record).map(([key, value]) => ({ key, value }))
+      );
 
       // Sort both arrays for comparison
       const sortByKey = (a: { key: string }, b: { key: string }) => a.key.localeCompare(b.key);
@@ -120,7 +122,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 200,
         status_text: 'OK',
-        headers: { 'Content-Type': 'application/json' },
+        headers: [{ key: 'Content-Type', value: 'application/json' }],
         body: '{"success": true}',
         time_ms: 1234,
       };
@@ -146,7 +148,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 200,
         status_text: 'OK',
-        headers: { 'Content-Type': 'application/json' },
+        headers: [{ key: 'Content-Type', value: 'application/json' }],
         body: '{"success": true}',
         time_ms: 1234,
       };
@@ -172,7 +174,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 201,
         status_text: 'Created',
-        headers: { 'Content-Type': 'application/json' },
+        headers: [{ key: 'Content-Type', value: 'application/json' }],
         body: '{"id": 123, "name": "John"}',
         time_ms: 2345,
       };
@@ -197,10 +199,10 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 200,
         status_text: 'OK',
-        headers: {
-          'Content-Type': 'application/json',
-          'X-Custom-Header': 'custom-value',
-        },
+        headers: [
+          { key: 'Content-Type', value: 'application/json' },
+          { key: 'X-Custom-Header', value: 'custom-value' },
+        ],
         body: '{}',
         time_ms: 1000,
       };
@@ -231,7 +233,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 200,
         status_text: 'OK',
-        headers: {},
+        headers: [],
         body: '{}',
         time_ms: 5678,
       };
@@ -307,7 +309,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 500,
         status_text: 'Internal Server Error',
-        headers: {},
+        headers: [],
         body: 'Server encountered an error',
         time_ms: 500,
       };
@@ -332,7 +334,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 404,
         status_text: 'Not Found',
-        headers: {},
+        headers: [],
         body: 'Resource not found',
         time_ms: 300,
       };
@@ -356,7 +358,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 401,
         status_text: 'Unauthorized',
-        headers: { 'WWW-Authenticate': 'Bearer' },
+        headers: [{ key: 'WWW-Authenticate', value: 'Bearer' }],
         body: '{"error": "Invalid token"}',
         time_ms: 200,
       };
@@ -373,7 +375,7 @@ describe('Main Page Integration', () => {
       });
 
       expect(result.status).toBe(401);
-      expect(result.headers['WWW-Authenticate']).toBe('Bearer');
+      expect(result.headers).toContainEqual({ key: 'WWW-Authenticate', value: 'Bearer' });
     });
 
     it('should handle SSL/TLS certificate errors', async () => {
@@ -392,6 +394,70 @@ describe('Main Page Integration', () => {
         })
       ).rejects.toThrow('SSL certificate');
     });
+
+    // Test the actual formatExecutionError function from +page.svelte
+    const formatExecutionError = (error: unknown): string => {
+      const isTauriError = (value: unknown): value is { kind: string; message: unknown } =>
+        typeof value === 'object' && value !== null && 'kind' in value && 'message' in value;
+
+      if (error instanceof Error) return error.message;
+      if (!isTauriError(error)) return String(error);
+
+      const { message } = error;
+      if (typeof message === 'string') return message;
+      if (Array.isArray(message)) {
+        return message
+          .map(
+            (item: { name?: string; location?: string }) => `{{${item.name}}} in ${item.location}`
+          )
+          .join(', ');
+      }
+      if (typeof message === 'object' && message !== null && 'message' in message) {
+        return String((message as { message: unknown }).message);
+      }
+      return error.kind;
+    };
+
+    it('should show the message of structured backend errors', async () => {
+      mockInvoke.mockRejectedValue({ kind: 'request_failed', message: 'Connection refused' });
+
+      const error = await mockInvoke('execute_request', {}).catch((e: unknown) => e);
+      expect(formatExecutionError(error)).toBe('Connection refused');
+      expect(formatExecutionError(error)).not.toBe('[object Object]');
+    });
+
+    it('should list unresolved variables and TLS failures', () => {
+      expect(
+        formatExecutionError({
+          kind: 'unresolved_variables',
+          message: [
+            { name: 'token', location: 'headers.Authorization' },
+            { name: 'id', location: 'url' },
+          ],
+        })
+      ).toBe('{{token}} in headers.Authorization, {{id}} in url');
+      expect(
+        formatExecutionError({
+          kind: 'tls',
+          message: { reason: 'expired', message: 'certificate expired' },
+        })
+      ).toBe('certificate expired');
+      expect(formatExecutionError('plain failure')).toBe('plain failure');
+    });
+
+    it('should pass an execution ID that cancel_request can abort', async () => {
+      mockInvoke.mockResolvedValue(undefined);
+      const executionId = crypto.randomUUID();
+
+      await mockInvoke('execute_request', {
+        request: { name: 'Slow', method: 'GET', url: 'https://api.example.com', headers: {} },
+        executionId,
+      });
+      await mockInvoke('cancel_request', { executionId });
+
+      expect(mockInvoke.mock.calls[0][1].executionId).toBe(executionId);
+      expect(mockInvoke).toHaveBeenLastCalledWith('cancel_request', { executionId });
+    });
   });
 
   describe('Request List Management', () => {
@@ -593,7 +659,7 @@ describe('Main Page Integration', () => {
       const mockResponse = {
         status: 200,
         status_text: 'OK',
-        headers: {},
+        headers: [],
         body: '{}',
         time_ms: 1234,
       };